@@ -0,0 +1,339 @@
+//! Derive macros for the `ember-rs` EZSP wire codec.
+
+use proc_macro::TokenStream;
+use proc_macro2::{Span, TokenStream as TokenStream2};
+use quote::quote;
+use syn::{
+    parse_macro_input, spanned::Spanned, Attribute, Data, DataEnum, DataStruct, DeriveInput,
+    Error, Fields, Ident, Type,
+};
+
+/// Derives `EzspEncode`, `EzspDecode` and, for fixed size
+/// types, `WireSize` for a struct or a `#[repr]` enum.
+///
+/// Struct fields are encoded in declaration order. Fields must
+/// be types implementing the codec traits, or fixed arrays of
+/// them. `Vec` fields must be marked `#[ezsp(length_prefixed)]`,
+/// and are encoded as a single length byte followed by their items.
+///
+/// Enums must be fieldless, `Copy`, and declare a `#[repr(u8)]`,
+/// `#[repr(u16)]` or `#[repr(u32)]`. They are encoded as their
+/// discriminant, and decoding an unlisted value fails with
+/// `CodecError::InvalidValue`. Enums also get `EzspEnum`, a
+/// `TryFrom` of the repr type that returns the unlisted value in
+/// an `UnknownValue` error, and `From<Enum>` for the repr type.
+/// `EzspEnum::name` returns the variant name.
+///
+/// ```
+/// use ember_rs::codec::EzspCodec;
+///
+/// #[derive(Copy, Clone, EzspCodec)]
+/// #[repr(u8)]
+/// enum Mode { Off = 0, On = 1 }
+///
+/// #[derive(EzspCodec)]
+/// struct Command { mode: Mode, targets: [u16; 2], #[ezsp(length_prefixed)] data: Vec<u8> }
+/// ```
+///
+/// # Rejected types
+///
+/// Fields without a wire format, such as tuples, fail to compile:
+///
+/// ```compile_fail
+/// # use ember_rs::codec::EzspCodec;
+/// #[derive(EzspCodec)]
+/// struct Command { pair: (u8, u8) }
+/// ```
+///
+/// So do `Vec` fields without `#[ezsp(length_prefixed)]`:
+///
+/// ```compile_fail
+/// # use ember_rs::codec::EzspCodec;
+/// #[derive(EzspCodec)]
+/// struct Command { data: Vec<u8> }
+/// ```
+///
+/// and `#[ezsp(length_prefixed)]` on anything but a `Vec`:
+///
+/// ```compile_fail
+/// # use ember_rs::codec::EzspCodec;
+/// #[derive(EzspCodec)]
+/// struct Command { #[ezsp(length_prefixed)] data: [u8; 4] }
+/// ```
+///
+/// Enums without a `#[repr]`:
+///
+/// ```compile_fail
+/// # use ember_rs::codec::EzspCodec;
+/// #[derive(Copy, Clone, EzspCodec)]
+/// enum Mode { Off = 0, On = 1 }
+/// ```
+///
+/// or with a repr other than `u8`, `u16` or `u32`:
+///
+/// ```compile_fail
+/// # use ember_rs::codec::EzspCodec;
+/// #[derive(Copy, Clone, EzspCodec)]
+/// #[repr(i8)]
+/// enum Mode { Off = 0, On = 1 }
+/// ```
+///
+/// and enums with fields are rejected too:
+///
+/// ```compile_fail
+/// # use ember_rs::codec::EzspCodec;
+/// #[derive(Copy, Clone, EzspCodec)]
+/// #[repr(u8)]
+/// enum Mode { Off, Level(u8) }
+/// ```
+#[proc_macro_derive(EzspCodec, attributes(ezsp))]
+pub fn derive_ezsp_codec(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    let expanded = match &input.data {
+        Data::Struct(data) => derive_struct(&input, data),
+        Data::Enum(data) => derive_enum(&input, data),
+        Data::Union(_) => Err(Error::new(input.ident.span(), "EzspCodec cannot be derived for unions")),
+    };
+
+    expanded.unwrap_or_else(Error::into_compile_error).into()
+}
+
+/// How a single struct field is put on the wire.
+enum FieldKind {
+    /// Encoded with its own codec implementation.
+    Plain,
+
+    /// A `Vec` preceded by a length byte.
+    LengthPrefixed,
+}
+
+fn derive_struct(input: &DeriveInput, data: &DataStruct) -> Result<TokenStream2, Error> {
+    let name = &input.ident;
+    let (impl_generics, type_generics, where_clause) = input.generics.split_for_impl();
+
+    let mut encodes = Vec::new();
+    let mut decodes = Vec::new();
+    let mut sizes = Vec::new();
+    let mut fixed_size = true;
+
+    for (index, field) in data.fields.iter().enumerate() {
+        let kind = field_kind(&field.attrs)?;
+        check_field_type(&field.ty, &kind)?;
+
+        let member = match &field.ident {
+            Some(ident) => quote!(#ident),
+            None => {
+                let index = syn::Index::from(index);
+                quote!(#index)
+            }
+        };
+        let ty = &field.ty;
+
+        match kind {
+            FieldKind::Plain => {
+                encodes.push(quote! {
+                    ::ember_rs::codec::EzspEncode::encode(&self.#member, writer)?;
+                });
+                decodes.push(quote! {
+                    <#ty as ::ember_rs::codec::EzspDecode>::decode(reader)?
+                });
+                sizes.push(quote!(<#ty as ::ember_rs::codec::WireSize>::WIRE_SIZE));
+            }
+            FieldKind::LengthPrefixed => {
+                fixed_size = false;
+                encodes.push(quote! {
+                    ::ember_rs::codec::encode_length_prefixed(&self.#member, writer)?;
+                });
+                decodes.push(quote! {
+                    ::ember_rs::codec::decode_length_prefixed(reader)?
+                });
+            }
+        }
+    }
+
+    let construct = match &data.fields {
+        Fields::Named(fields) => {
+            let idents = fields.named.iter().map(|field| &field.ident);
+            quote!(#name { #( #idents: #decodes, )* })
+        }
+        Fields::Unnamed(_) => quote!(#name( #( #decodes, )* )),
+        Fields::Unit => quote!(#name),
+    };
+
+    let wire_size = if fixed_size {
+        quote! {
+            impl #impl_generics ::ember_rs::codec::WireSize for #name #type_generics #where_clause {
+                const WIRE_SIZE: usize = 0 #( + #sizes )*;
+            }
+        }
+    } else {
+        quote!()
+    };
+
+    Ok(quote! {
+        impl #impl_generics ::ember_rs::codec::EzspEncode for #name #type_generics #where_clause {
+            #[allow(unused_variables)]
+            fn encode(&self, writer: &mut ::ember_rs::codec::Writer) -> Result<(), ::ember_rs::codec::CodecError> {
+                #( #encodes )*
+                Ok(())
+            }
+        }
+
+        impl #impl_generics ::ember_rs::codec::EzspDecode for #name #type_generics #where_clause {
+            #[allow(unused_variables)]
+            fn decode(reader: &mut ::ember_rs::codec::Reader) -> Result<Self, ::ember_rs::codec::CodecError> {
+                Ok(#construct)
+            }
+        }
+
+        #wire_size
+    })
+}
+
+fn derive_enum(input: &DeriveInput, data: &DataEnum) -> Result<TokenStream2, Error> {
+    let name = &input.ident;
+    let repr = enum_repr(input)?;
+
+    if let Some(variant) = data.variants.iter().find(|variant| !matches!(variant.fields, Fields::Unit)) {
+        return Err(Error::new(variant.span(), "EzspCodec enums must only have unit variants"));
+    }
+
+    let variants: Vec<_> = data.variants.iter().map(|variant| &variant.ident).collect();
+
+    Ok(quote! {
+        #[allow(deprecated)]
+        impl ::ember_rs::codec::EzspEnum for #name {
+            type Repr = #repr;
+
+            fn from_raw(raw: #repr) -> Option<Self> {
+                #(
+                    if raw == #name::#variants as #repr {
+                        return Some(#name::#variants);
+                    }
+                )*
+                None
+            }
+
+            fn into_raw(self) -> #repr {
+                self as #repr
+            }
+
+            fn name(self) -> &'static str {
+                match self {
+                    #( #name::#variants => stringify!(#variants), )*
+                }
+            }
+        }
+
+        impl ::core::convert::TryFrom<#repr> for #name {
+            type Error = ::ember_rs::codec::UnknownValue<#repr>;
+
+            fn try_from(raw: #repr) -> Result<Self, ::ember_rs::codec::UnknownValue<#repr>> {
+                <#name as ::ember_rs::codec::EzspEnum>::from_raw(raw)
+                    .ok_or(::ember_rs::codec::UnknownValue(raw))
+            }
+        }
+
+        impl ::core::convert::From<#name> for #repr {
+            fn from(value: #name) -> #repr {
+                value as #repr
+            }
+        }
+
+        #[allow(deprecated)]
+        impl ::ember_rs::codec::EzspEncode for #name {
+            fn encode(&self, writer: &mut ::ember_rs::codec::Writer) -> Result<(), ::ember_rs::codec::CodecError> {
+                let raw = match self {
+                    #( #name::#variants => #name::#variants as #repr, )*
+                };
+                ::ember_rs::codec::EzspEncode::encode(&raw, writer)
+            }
+        }
+
+        impl ::ember_rs::codec::EzspDecode for #name {
+            fn decode(reader: &mut ::ember_rs::codec::Reader) -> Result<Self, ::ember_rs::codec::CodecError> {
+                let raw = <#repr as ::ember_rs::codec::EzspDecode>::decode(reader)?;
+                <#name as ::ember_rs::codec::EzspEnum>::from_raw(raw)
+                    .ok_or(::ember_rs::codec::CodecError::InvalidValue)
+            }
+        }
+
+        impl ::ember_rs::codec::WireSize for #name {
+            const WIRE_SIZE: usize = ::core::mem::size_of::<#repr>();
+        }
+    })
+}
+
+/// Reads the integer type from the enum's `#[repr]` attribute.
+fn enum_repr(input: &DeriveInput) -> Result<Ident, Error> {
+    let mut repr = None;
+
+    for attr in input.attrs.iter().filter(|attr| attr.path().is_ident("repr")) {
+        attr.parse_nested_meta(|meta| {
+            for int in ["u8", "u16", "u32"] {
+                if meta.path.is_ident(int) {
+                    repr = Some(Ident::new(int, Span::call_site()));
+                }
+            }
+            Ok(())
+        })?;
+    }
+
+    repr.ok_or_else(|| Error::new(
+        input.ident.span(),
+        "EzspCodec enums must declare #[repr(u8)], #[repr(u16)] or #[repr(u32)]",
+    ))
+}
+
+/// Reads the `#[ezsp(...)]` options of a field.
+fn field_kind(attrs: &[Attribute]) -> Result<FieldKind, Error> {
+    let mut kind = FieldKind::Plain;
+
+    for attr in attrs.iter().filter(|attr| attr.path().is_ident("ezsp")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("length_prefixed") {
+                kind = FieldKind::LengthPrefixed;
+                Ok(())
+            } else {
+                Err(meta.error("unknown ezsp attribute, expected `length_prefixed`"))
+            }
+        })?;
+    }
+
+    Ok(kind)
+}
+
+/// Rejects field types that have no defined wire format.
+fn check_field_type(ty: &Type, kind: &FieldKind) -> Result<(), Error> {
+    match (ty, kind) {
+        (Type::Paren(inner), _) => check_field_type(&inner.elem, kind),
+        (Type::Group(inner), _) => check_field_type(&inner.elem, kind),
+        (Type::Path(path), FieldKind::Plain) => {
+            if is_vec(path) {
+                Err(Error::new(ty.span(), "Vec fields must be marked #[ezsp(length_prefixed)]"))
+            } else {
+                Ok(())
+            }
+        }
+        (Type::Path(path), FieldKind::LengthPrefixed) => {
+            if is_vec(path) {
+                Ok(())
+            } else {
+                Err(Error::new(ty.span(), "#[ezsp(length_prefixed)] can only be used on Vec fields"))
+            }
+        }
+        (Type::Array(array), FieldKind::Plain) => check_field_type(&array.elem, kind),
+        (_, FieldKind::LengthPrefixed) => {
+            Err(Error::new(ty.span(), "#[ezsp(length_prefixed)] can only be used on Vec fields"))
+        }
+        _ => Err(Error::new(
+            ty.span(),
+            "unsupported field type for EzspCodec, expected a codec type or a fixed array",
+        )),
+    }
+}
+
+fn is_vec(path: &syn::TypePath) -> bool {
+    path.path.segments.last().is_some_and(|segment| segment.ident == "Vec")
+}
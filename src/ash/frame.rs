@@ -0,0 +1,477 @@
+//! ASH frame format.
+//!
+//! Frames are written as `[control][data...][crc high][crc low][flag]`
+//! with reserved bytes escaped. The CRC covers the control byte and
+//! the data field, and the data field of DATA frames is XORed with a
+//! pseudo-random sequence to reduce the number of reserved bytes.
+
+use crate::codec::{EzspCodec, Recognized};
+
+/// Marks the end of a frame.
+pub const FLAG: u8 = 0x7E;
+
+/// Marks the next byte as escaped.
+pub const ESCAPE: u8 = 0x7D;
+
+/// XON flow control byte.
+pub const XON: u8 = 0x11;
+
+/// XOFF flow control byte.
+pub const XOFF: u8 = 0x13;
+
+/// Replaces a byte received with a low-level communication error,
+/// causing the frame it appears in to be discarded.
+pub const SUBSTITUTE: u8 = 0x18;
+
+/// Terminates a frame in progress, causing it to be discarded.
+pub const CANCEL: u8 = 0x1A;
+
+/// XORed with escaped bytes.
+const ESCAPE_MASK: u8 = 0x20;
+
+/// The ASH protocol version carried in RSTACK and ERROR frames.
+pub const VERSION: u8 = 0x02;
+
+/// The largest data field a DATA frame can carry.
+pub const MAX_DATA_LENGTH: usize = 200;
+
+/// The number of distinct frame numbers, which wrap around at this value.
+pub const FRAME_NUMBER_MODULUS: u8 = 8;
+
+/// The reason for the NCP resetting, reported in RSTACK and ERROR frames.
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
+#[repr(u8)]
+pub enum ResetCode {
+    /// Reset: Unknown reason.
+    Unknown = 0x00,
+    /// Reset: External.
+    External = 0x01,
+    /// Reset: Power-on.
+    PowerOn = 0x02,
+    /// Reset: Watchdog.
+    Watchdog = 0x03,
+    /// Reset: Assert.
+    Assert = 0x06,
+    /// Reset: Boot loader.
+    Bootloader = 0x09,
+    /// Reset: Software.
+    Software = 0x0B,
+    /// Error: Exceeded maximum ACK timeout count.
+    ExceededMaximumAckTimeoutCount = 0x51,
+    /// Chip-specific error reset code.
+    ChipSpecific = 0x80,
+}
+
+/// Errors produced while reading a single ASH frame.
+#[derive(Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum FrameError {
+    /// The frame was shorter than a control byte and CRC.
+    TooShort,
+
+    /// The data field was longer than [`MAX_DATA_LENGTH`].
+    TooLong,
+
+    /// The CRC did not match the frame contents.
+    BadCrc,
+
+    /// The control byte does not identify a frame type,
+    /// or the data field has the wrong length for it.
+    BadControl(u8),
+
+    /// The frame contained a substitute byte or an invalid escape.
+    CommError,
+}
+
+/// A single ASH frame.
+#[derive(Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum Frame {
+    /// Carries an EZSP frame.
+    Data {
+        /// The number of this frame, modulo 8.
+        frame_number: u8,
+        /// Set when the frame is being retransmitted.
+        retransmit: bool,
+        /// The number of the next frame the sender expects to receive.
+        ack_number: u8,
+        /// The EZSP frame, without pseudo-random XOR applied.
+        data: Vec<u8>,
+    },
+
+    /// Acknowledges received DATA frames.
+    Ack {
+        /// The number of the next frame the sender expects to receive.
+        ack_number: u8,
+        /// Set when the sender cannot receive DATA frames.
+        not_ready: bool,
+    },
+
+    /// Acknowledges received DATA frames and asks
+    /// for the frames after them to be retransmitted.
+    Nak {
+        /// The number of the next frame the sender expects to receive.
+        ack_number: u8,
+        /// Set when the sender cannot receive DATA frames.
+        not_ready: bool,
+    },
+
+    /// Requests that the NCP resets.
+    Rst,
+
+    /// Sent by the NCP after it resets.
+    RstAck {
+        /// The ASH protocol version.
+        version: u8,
+        /// The reason the NCP reset.
+        reset_code: Recognized<ResetCode>,
+    },
+
+    /// Sent by the NCP when it enters the failed state.
+    Error {
+        /// The ASH protocol version.
+        version: u8,
+        /// The reason for the failure.
+        error_code: Recognized<ResetCode>,
+    },
+}
+
+impl Frame {
+    /// The control byte identifying the frame.
+    pub fn control(&self) -> u8 {
+        match self {
+            Frame::Data { frame_number, retransmit, ack_number, .. } => {
+                (frame_number & 0x07) << 4 | (*retransmit as u8) << 3 | (ack_number & 0x07)
+            }
+            Frame::Ack { ack_number, not_ready } => 0x80 | (*not_ready as u8) << 3 | (ack_number & 0x07),
+            Frame::Nak { ack_number, not_ready } => 0xA0 | (*not_ready as u8) << 3 | (ack_number & 0x07),
+            Frame::Rst => 0xC0,
+            Frame::RstAck { .. } => 0xC1,
+            Frame::Error { .. } => 0xC2,
+        }
+    }
+
+    /// The acknowledgement number carried by DATA, ACK and NAK frames.
+    pub fn ack_number(&self) -> Option<u8> {
+        match self {
+            Frame::Data { ack_number, .. }
+            | Frame::Ack { ack_number, .. }
+            | Frame::Nak { ack_number, .. } => Some(*ack_number),
+            _ => None,
+        }
+    }
+
+    /// Writes the frame, including its byte stuffing and
+    /// terminating flag, to the end of `out`.
+    pub fn encode(&self, out: &mut Vec<u8>) {
+        let mut body = vec![self.control()];
+
+        match self {
+            Frame::Data { data, .. } => {
+                let start = body.len();
+                body.extend_from_slice(data);
+                randomize(&mut body[start..]);
+            }
+            Frame::RstAck { version, reset_code: code }
+            | Frame::Error { version, error_code: code } => {
+                body.push(*version);
+                body.push(code.raw());
+            }
+            _ => {}
+        }
+
+        let crc = crc_ccitt(&body);
+        body.extend_from_slice(&crc.to_be_bytes());
+
+        for byte in body {
+            if is_reserved(byte) {
+                out.push(ESCAPE);
+                out.push(byte ^ ESCAPE_MASK);
+            } else {
+                out.push(byte);
+            }
+        }
+        out.push(FLAG);
+    }
+
+    /// Parses a frame from its unstuffed bytes, excluding the flag.
+    pub fn decode(bytes: &[u8]) -> Result<Frame, FrameError> {
+        if bytes.len() < 3 {
+            return Err(FrameError::TooShort);
+        }
+
+        let (body, crc) = bytes.split_at(bytes.len() - 2);
+        if crc_ccitt(body) != u16::from_be_bytes([crc[0], crc[1]]) {
+            return Err(FrameError::BadCrc);
+        }
+
+        let control = body[0];
+        let data = &body[1..];
+
+        if data.len() > MAX_DATA_LENGTH {
+            return Err(FrameError::TooLong);
+        }
+
+        let expect_length = |length: usize| {
+            if data.len() == length { Ok(()) } else { Err(FrameError::BadControl(control)) }
+        };
+
+        if control & 0x80 == 0 {
+            if data.is_empty() {
+                return Err(FrameError::BadControl(control));
+            }
+
+            let mut data = data.to_vec();
+            randomize(&mut data);
+
+            return Ok(Frame::Data {
+                frame_number: (control >> 4) & 0x07,
+                retransmit: control & 0x08 != 0,
+                ack_number: control & 0x07,
+                data,
+            });
+        }
+
+        match control {
+            0x80..=0x9F => {
+                expect_length(0)?;
+                Ok(Frame::Ack { ack_number: control & 0x07, not_ready: control & 0x08 != 0 })
+            }
+            0xA0..=0xBF => {
+                expect_length(0)?;
+                Ok(Frame::Nak { ack_number: control & 0x07, not_ready: control & 0x08 != 0 })
+            }
+            0xC0 => {
+                expect_length(0)?;
+                Ok(Frame::Rst)
+            }
+            0xC1 => {
+                expect_length(2)?;
+                Ok(Frame::RstAck { version: data[0], reset_code: Recognized::from_raw(data[1]) })
+            }
+            0xC2 => {
+                expect_length(2)?;
+                Ok(Frame::Error { version: data[0], error_code: Recognized::from_raw(data[1]) })
+            }
+            _ => Err(FrameError::BadControl(control)),
+        }
+    }
+}
+
+/// Splits a received byte stream into frames.
+///
+/// Handles escaped bytes, and discards frames that are cancelled
+/// or contain substitute bytes. Flow control bytes are ignored.
+#[derive(Default)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Decoder {
+    buffer: Vec<u8>,
+    escaped: bool,
+    corrupted: bool,
+}
+
+impl Decoder {
+    /// Creates a decoder waiting for the start of a frame.
+    pub fn new() -> Self {
+        Decoder::default()
+    }
+
+    /// Feeds a received byte to the decoder, returning
+    /// the result if it completed a frame.
+    pub fn push(&mut self, byte: u8) -> Option<Result<Frame, FrameError>> {
+        match byte {
+            FLAG => {
+                let result = if self.corrupted || self.escaped {
+                    Some(Err(FrameError::CommError))
+                } else if self.buffer.is_empty() {
+                    None
+                } else {
+                    Some(Frame::decode(&self.buffer))
+                };
+                self.clear();
+                result
+            }
+            CANCEL => {
+                self.clear();
+                None
+            }
+            SUBSTITUTE => {
+                self.corrupted = true;
+                None
+            }
+            XON | XOFF => None,
+            ESCAPE => {
+                self.escaped = true;
+                None
+            }
+            _ => {
+                if self.buffer.len() > MAX_DATA_LENGTH + 3 {
+                    self.corrupted = true;
+                } else if self.escaped {
+                    self.buffer.push(byte ^ ESCAPE_MASK);
+                } else {
+                    self.buffer.push(byte);
+                }
+                self.escaped = false;
+                None
+            }
+        }
+    }
+
+    /// Discards any partially received frame.
+    pub fn clear(&mut self) {
+        self.buffer.clear();
+        self.escaped = false;
+        self.corrupted = false;
+    }
+}
+
+/// Whether the byte must be escaped when sent.
+fn is_reserved(byte: u8) -> bool {
+    matches!(byte, FLAG | ESCAPE | XON | XOFF | SUBSTITUTE | CANCEL)
+}
+
+/// Calculates the CRC-CCITT (polynomial 0x1021, initial value
+/// 0xFFFF) used to check ASH frames.
+pub fn crc_ccitt(bytes: &[u8]) -> u16 {
+    let mut crc = 0xFFFFu16;
+    for byte in bytes {
+        crc ^= (*byte as u16) << 8;
+        for _ in 0..8 {
+            crc = if crc & 0x8000 != 0 { (crc << 1) ^ 0x1021 } else { crc << 1 };
+        }
+    }
+    crc
+}
+
+/// Applies (or removes) the pseudo-random XOR sequence
+/// used on the data field of DATA frames.
+pub fn randomize(data: &mut [u8]) {
+    let mut random = 0x42u8;
+    for byte in data {
+        *byte ^= random;
+        random = if random & 0x01 == 0 { random >> 1 } else { (random >> 1) ^ 0xB8 };
+    }
+}
+
+/// The number of steps from frame number `from` to `to`, modulo 8.
+pub(crate) fn distance(from: u8, to: u8) -> u8 {
+    to.wrapping_sub(from) % FRAME_NUMBER_MODULUS
+}
+
+/// The frame number after `number`.
+pub(crate) fn next(number: u8) -> u8 {
+    (number + 1) % FRAME_NUMBER_MODULUS
+}
+
+/// The description of a reset code, for logging.
+pub fn describe_reset_code(code: Recognized<ResetCode>) -> &'static str {
+    match code.known() {
+        Some(ResetCode::Unknown) => "unknown reason",
+        Some(ResetCode::External) => "external",
+        Some(ResetCode::PowerOn) => "power-on",
+        Some(ResetCode::Watchdog) => "watchdog",
+        Some(ResetCode::Assert) => "assert",
+        Some(ResetCode::Bootloader) => "boot loader",
+        Some(ResetCode::Software) => "software",
+        Some(ResetCode::ExceededMaximumAckTimeoutCount) => "exceeded maximum ACK timeout count",
+        Some(ResetCode::ChipSpecific) => "chip-specific error",
+        None => "unrecognized reset code",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(frame: &Frame) -> Vec<u8> {
+        let mut out = Vec::new();
+        frame.encode(&mut out);
+        out
+    }
+
+    fn decode(bytes: &[u8]) -> Vec<Result<Frame, FrameError>> {
+        let mut decoder = Decoder::new();
+        bytes.iter().filter_map(|byte| decoder.push(*byte)).collect()
+    }
+
+    #[test]
+    fn crc () {
+        assert_eq!(crc_ccitt(&[0xC0]), 0x38BC);
+        assert_eq!(crc_ccitt(&[0xC1, 0x02, 0x02]), 0x9B7B);
+    }
+
+    #[test]
+    fn randomize_sequence () {
+        let mut data = [0u8; 5];
+        randomize(&mut data);
+        assert_eq!(data, [0x42, 0x21, 0xA8, 0x54, 0x2A]);
+
+        randomize(&mut data);
+        assert_eq!(data, [0u8; 5]);
+    }
+
+    #[test]
+    fn control_frames () {
+        assert_eq!(encode(&Frame::Rst), [0xC0, 0x38, 0xBC, FLAG]);
+        assert_eq!(
+            encode(&Frame::RstAck { version: VERSION, reset_code: ResetCode::PowerOn.into() }),
+            [0xC1, 0x02, 0x02, 0x9B, 0x7B, FLAG]
+        );
+        assert_eq!(encode(&Frame::Ack { ack_number: 1, not_ready: false })[0], 0x81);
+        assert_eq!(encode(&Frame::Nak { ack_number: 6, not_ready: true })[0], 0xAE);
+    }
+
+    #[test]
+    fn data_frame () {
+        let frame = Frame::Data {
+            frame_number: 0,
+            retransmit: false,
+            ack_number: 0,
+            data: vec![0x00, 0x00, 0x00, 0x04],
+        };
+
+        let bytes = encode(&frame);
+        assert_eq!(bytes, [0x00, 0x42, 0x21, 0xA8, 0x50, 0xED, 0x2C, FLAG]);
+        assert_eq!(decode(&bytes), [Ok(frame)]);
+    }
+
+    #[test]
+    fn byte_stuffing () {
+        let frame = Frame::Data {
+            frame_number: 2,
+            retransmit: true,
+            ack_number: 5,
+            data: vec![0x7E ^ 0x42, 0x11 ^ 0x21, 0x00],
+        };
+
+        let bytes = encode(&frame);
+        assert_eq!(bytes[..5], [0x2D, ESCAPE, 0x7E ^ 0x20, ESCAPE, 0x11 ^ 0x20]);
+        assert_eq!(decode(&bytes), [Ok(frame)]);
+    }
+
+    #[test]
+    fn decoder_discards_bad_frames () {
+        let rst = encode(&Frame::Rst);
+
+        let mut cancelled = vec![0x81, 0x60];
+        cancelled.push(CANCEL);
+        cancelled.extend_from_slice(&rst);
+        assert_eq!(decode(&cancelled), [Ok(Frame::Rst)]);
+
+        let mut substituted = vec![0x81, SUBSTITUTE, 0x59, FLAG];
+        substituted.extend_from_slice(&rst);
+        assert_eq!(decode(&substituted), [Err(FrameError::CommError), Ok(Frame::Rst)]);
+
+        assert_eq!(decode(&[0xC0, 0x38, 0xBD, FLAG]), [Err(FrameError::BadCrc)]);
+        assert_eq!(decode(&[0xC0, FLAG]), [Err(FrameError::TooShort)]);
+    }
+
+    #[test]
+    fn frame_numbers_wrap () {
+        assert_eq!(next(7), 0);
+        assert_eq!(distance(6, 1), 3);
+        assert_eq!(distance(3, 3), 0);
+    }
+}
@@ -0,0 +1,619 @@
+//! ASH (Asynchronous Serial Host) UART link layer.
+//!
+//! Carries EZSP frames between the Host and a UART connected NCP
+//! with error detection, acknowledgement and retransmission.
+//!
+//! Reference: <https://www.silabs.com/documents/public/user-guides/ug101-uart-gateway-protocol-reference.pdf>
+
+pub mod frame;
+
+use std::collections::VecDeque;
+use std::io;
+use std::time::{Duration, Instant};
+
+use crate::codec::Recognized;
+use frame::{Decoder, Frame, FrameError, ResetCode};
+
+/// A bidirectional byte stream to the NCP, such as a serial port.
+pub trait ByteStream {
+    /// Writes all of `bytes` to the stream.
+    fn write_all(&mut self, bytes: &[u8]) -> io::Result<()>;
+
+    /// Reads the bytes that are available into `buf`, returning how
+    /// many were read. Returns `Ok(0)` when no bytes arrive within
+    /// the stream's own read timeout.
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize>;
+}
+
+/// Adapts a [`std::io`] stream to a [`ByteStream`].
+///
+/// Reads that time out or would block are reported as no bytes read.
+#[derive(Debug)]
+pub struct IoStream<T>(pub T);
+
+impl<T: io::Read + io::Write> ByteStream for IoStream<T> {
+    fn write_all(&mut self, bytes: &[u8]) -> io::Result<()> {
+        self.0.write_all(bytes)?;
+        self.0.flush()
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.0.read(buf) {
+            Err(error) if matches!(
+                error.kind(),
+                io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted
+            ) => Ok(0),
+            result => result,
+        }
+    }
+}
+
+/// Timing and window parameters of the link.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct AshConfig {
+    /// The number of DATA frames that can be sent without being acknowledged, 1 to 7.
+    pub window_size: u8,
+
+    /// The acknowledgement timeout used before any round trip has been measured.
+    pub ack_timeout_initial: Duration,
+
+    /// The lower bound of the adaptive acknowledgement timeout.
+    pub ack_timeout_min: Duration,
+
+    /// The upper bound of the adaptive acknowledgement timeout.
+    pub ack_timeout_max: Duration,
+
+    /// How long to wait for a RSTACK after sending a RST.
+    pub reset_timeout: Duration,
+
+    /// The number of consecutive acknowledgement timeouts
+    /// tolerated before the connection is considered lost.
+    pub max_ack_timeouts: u8,
+}
+
+impl Default for AshConfig {
+    /// The values recommended by UG101.
+    fn default() -> Self {
+        AshConfig {
+            window_size: 5,
+            ack_timeout_initial: Duration::from_millis(1600),
+            ack_timeout_min: Duration::from_millis(400),
+            ack_timeout_max: Duration::from_millis(3200),
+            reset_timeout: Duration::from_millis(2500),
+            max_ack_timeouts: 4,
+        }
+    }
+}
+
+/// Errors produced by the ASH link.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum AshError {
+    /// The underlying byte stream failed.
+    Stream(io::ErrorKind),
+
+    /// No RSTACK was received within the reset timeout.
+    ResetTimeout,
+
+    /// The NCP replied to a reset with an unsupported ASH version.
+    Version(u8),
+
+    /// The NCP reset unexpectedly, for the reason given.
+    NcpReset(Recognized<ResetCode>),
+
+    /// The NCP sent an ERROR frame and entered the failed state.
+    NcpError(Recognized<ResetCode>),
+
+    /// Sent DATA frames went unacknowledged for too many timeouts.
+    AckTimeout,
+
+    /// The link has not been reset since it was created or failed.
+    NotConnected,
+
+    /// The EZSP frame is longer than a DATA frame can carry.
+    DataFrameTooLong(usize),
+}
+
+/// A DATA frame waiting for acknowledgement.
+#[derive(Debug)]
+struct Pending {
+    frame_number: u8,
+    data: Vec<u8>,
+    sent_at: Instant,
+    retransmitted: bool,
+}
+
+/// The Host side of an ASH link.
+///
+/// The link must be [reset](Ash::reset) before EZSP frames are
+/// exchanged. Frames are queued by [`send`](Ash::send), and the
+/// link makes progress, delivering received frames and handling
+/// retransmission, whenever [`poll`](Ash::poll) is called.
+#[derive(Debug)]
+pub struct Ash<S> {
+    stream: S,
+    config: AshConfig,
+    decoder: Decoder,
+    connected: bool,
+
+    /// The frame number of the next DATA frame to be sent.
+    frame_tx: u8,
+    /// The frame number of the next DATA frame expected from the NCP.
+    frame_rx: u8,
+    /// Set after a NAK is sent, until an in-sequence DATA frame arrives.
+    rejecting: bool,
+
+    ack_timeout: Duration,
+    ack_timeouts: u8,
+
+    queued: VecDeque<Vec<u8>>,
+    pending: VecDeque<Pending>,
+    received: VecDeque<Vec<u8>>,
+}
+
+impl<S: ByteStream> Ash<S> {
+    /// Creates a disconnected link over `stream`.
+    pub fn new(stream: S, config: AshConfig) -> Self {
+        Ash {
+            stream,
+            decoder: Decoder::new(),
+            connected: false,
+            frame_tx: 0,
+            frame_rx: 0,
+            rejecting: false,
+            ack_timeout: config.ack_timeout_initial,
+            ack_timeouts: 0,
+            queued: VecDeque::new(),
+            pending: VecDeque::new(),
+            received: VecDeque::new(),
+            config,
+        }
+    }
+
+    /// The configuration of the link.
+    pub fn config(&self) -> &AshConfig {
+        &self.config
+    }
+
+    /// The underlying byte stream.
+    pub fn stream(&self) -> &S {
+        &self.stream
+    }
+
+    /// The underlying byte stream, mutably.
+    pub fn stream_mut(&mut self) -> &mut S {
+        &mut self.stream
+    }
+
+    /// Whether the link has been reset and has not failed since.
+    pub fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    /// The current adaptive acknowledgement timeout.
+    pub fn ack_timeout(&self) -> Duration {
+        self.ack_timeout
+    }
+
+    /// Resets the NCP and the link state, returning the
+    /// reset code the NCP reported in its RSTACK.
+    ///
+    /// Any queued or unacknowledged frames are discarded.
+    pub fn reset(&mut self) -> Result<Recognized<ResetCode>, AshError> {
+        self.disconnect();
+
+        let mut bytes = vec![frame::CANCEL];
+        Frame::Rst.encode(&mut bytes);
+        self.stream.write_all(&bytes).map_err(|error| AshError::Stream(error.kind()))?;
+
+        let deadline = Instant::now() + self.config.reset_timeout;
+        while Instant::now() < deadline {
+            for result in self.read_frames()? {
+                match result {
+                    Ok(Frame::RstAck { version, reset_code }) => {
+                        if version != frame::VERSION {
+                            return Err(AshError::Version(version));
+                        }
+
+                        self.connected = true;
+                        return Ok(reset_code);
+                    }
+                    Ok(Frame::Error { error_code, .. }) => return Err(AshError::NcpError(error_code)),
+                    _ => {}
+                }
+            }
+        }
+
+        Err(AshError::ResetTimeout)
+    }
+
+    /// Queues an EZSP frame to be sent, sending it
+    /// immediately if the retransmit window has room.
+    pub fn send(&mut self, ezsp_frame: &[u8]) -> Result<(), AshError> {
+        if !self.connected {
+            return Err(AshError::NotConnected);
+        }
+        if ezsp_frame.is_empty() || ezsp_frame.len() > frame::MAX_DATA_LENGTH {
+            return Err(AshError::DataFrameTooLong(ezsp_frame.len()));
+        }
+
+        self.queued.push_back(ezsp_frame.to_vec());
+        self.transmit_queued()
+    }
+
+    /// Reads from the stream, handling received frames
+    /// and retransmitting unacknowledged ones if needed.
+    pub fn poll(&mut self) -> Result<(), AshError> {
+        if !self.connected {
+            return Err(AshError::NotConnected);
+        }
+
+        for result in self.read_frames()? {
+            if let Err(error) = self.handle(result) {
+                self.disconnect();
+                return Err(error);
+            }
+        }
+
+        if let Err(error) = self.check_ack_timeout() {
+            self.disconnect();
+            return Err(error);
+        }
+
+        self.transmit_queued()
+    }
+
+    /// Polls the link once and returns the next
+    /// received EZSP frame, if there is one.
+    pub fn receive(&mut self) -> Result<Option<Vec<u8>>, AshError> {
+        if self.received.is_empty() {
+            self.poll()?;
+        }
+
+        Ok(self.received.pop_front())
+    }
+
+    /// Polls the link until an EZSP frame is received or `timeout` elapses.
+    pub fn receive_timeout(&mut self, timeout: Duration) -> Result<Option<Vec<u8>>, AshError> {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            if let Some(data) = self.receive()? {
+                return Ok(Some(data));
+            }
+            if Instant::now() >= deadline {
+                return Ok(None);
+            }
+        }
+    }
+
+    /// Whether every sent EZSP frame has been acknowledged.
+    pub fn is_idle(&self) -> bool {
+        self.queued.is_empty() && self.pending.is_empty()
+    }
+
+    fn disconnect(&mut self) {
+        self.connected = false;
+        self.decoder.clear();
+        self.frame_tx = 0;
+        self.frame_rx = 0;
+        self.rejecting = false;
+        self.ack_timeout = self.config.ack_timeout_initial;
+        self.ack_timeouts = 0;
+        self.queued.clear();
+        self.pending.clear();
+        self.received.clear();
+    }
+
+    fn read_frames(&mut self) -> Result<Vec<Result<Frame, FrameError>>, AshError> {
+        let mut buf = [0u8; 256];
+        let count = self.stream.read(&mut buf).map_err(|error| AshError::Stream(error.kind()))?;
+
+        Ok(buf[..count].iter().filter_map(|byte| self.decoder.push(*byte)).collect())
+    }
+
+    fn handle(&mut self, result: Result<Frame, FrameError>) -> Result<(), AshError> {
+        let frame = match result {
+            Ok(frame) => frame,
+            Err(_) => return self.reject(),
+        };
+
+        if let Some(ack_number) = frame.ack_number() {
+            self.acknowledge(ack_number);
+        }
+
+        match frame {
+            Frame::Data { frame_number, retransmit, data, .. } => {
+                if frame_number == self.frame_rx {
+                    self.frame_rx = frame::next(self.frame_rx);
+                    self.rejecting = false;
+                    self.received.push_back(data);
+                    self.write(Frame::Ack { ack_number: self.frame_rx, not_ready: false })
+                } else if retransmit {
+                    // A duplicate of a frame already received.
+                    self.write(Frame::Ack { ack_number: self.frame_rx, not_ready: false })
+                } else {
+                    self.reject()
+                }
+            }
+            Frame::Nak { .. } => self.retransmit_pending(),
+            Frame::Ack { .. } | Frame::Rst => Ok(()),
+            Frame::RstAck { reset_code, .. } => Err(AshError::NcpReset(reset_code)),
+            Frame::Error { error_code, .. } => Err(AshError::NcpError(error_code)),
+        }
+    }
+
+    /// Sends a NAK, unless one has been sent since the last in-sequence DATA frame.
+    fn reject(&mut self) -> Result<(), AshError> {
+        if self.rejecting {
+            return Ok(());
+        }
+
+        self.rejecting = true;
+        self.write(Frame::Nak { ack_number: self.frame_rx, not_ready: false })
+    }
+
+    /// Removes the frames before `ack_number` from the retransmit window.
+    fn acknowledge(&mut self, ack_number: u8) {
+        let Some(oldest) = self.pending.front() else {
+            return;
+        };
+
+        let acknowledged = frame::distance(oldest.frame_number, ack_number) as usize;
+        if acknowledged == 0 || acknowledged > self.pending.len() {
+            return;
+        }
+
+        let now = Instant::now();
+        for pending in self.pending.drain(..acknowledged) {
+            if !pending.retransmitted {
+                let measured = now.duration_since(pending.sent_at);
+                self.ack_timeout = (self.ack_timeout * 7 / 8 + measured / 2)
+                    .clamp(self.config.ack_timeout_min, self.config.ack_timeout_max);
+            }
+        }
+        self.ack_timeouts = 0;
+    }
+
+    fn check_ack_timeout(&mut self) -> Result<(), AshError> {
+        let Some(oldest) = self.pending.front() else {
+            return Ok(());
+        };
+
+        if oldest.sent_at.elapsed() < self.ack_timeout {
+            return Ok(());
+        }
+
+        self.ack_timeouts += 1;
+        if self.ack_timeouts > self.config.max_ack_timeouts {
+            return Err(AshError::AckTimeout);
+        }
+
+        self.ack_timeout = (self.ack_timeout * 2).min(self.config.ack_timeout_max);
+        self.retransmit_pending()
+    }
+
+    fn retransmit_pending(&mut self) -> Result<(), AshError> {
+        let now = Instant::now();
+        let mut bytes = Vec::new();
+
+        for pending in self.pending.iter_mut() {
+            pending.sent_at = now;
+            pending.retransmitted = true;
+
+            Frame::Data {
+                frame_number: pending.frame_number,
+                retransmit: true,
+                ack_number: self.frame_rx,
+                data: pending.data.clone(),
+            }.encode(&mut bytes);
+        }
+
+        if bytes.is_empty() {
+            return Ok(());
+        }
+        self.stream.write_all(&bytes).map_err(|error| AshError::Stream(error.kind()))
+    }
+
+    fn transmit_queued(&mut self) -> Result<(), AshError> {
+        let window_size = self.config.window_size.clamp(1, frame::FRAME_NUMBER_MODULUS - 1) as usize;
+
+        while self.pending.len() < window_size {
+            let Some(data) = self.queued.pop_front() else {
+                break;
+            };
+
+            let frame_number = self.frame_tx;
+            self.frame_tx = frame::next(self.frame_tx);
+
+            self.write(Frame::Data {
+                frame_number,
+                retransmit: false,
+                ack_number: self.frame_rx,
+                data: data.clone(),
+            })?;
+
+            self.pending.push_back(Pending {
+                frame_number,
+                data,
+                sent_at: Instant::now(),
+                retransmitted: false,
+            });
+        }
+
+        Ok(())
+    }
+
+    fn write(&mut self, frame: Frame) -> Result<(), AshError> {
+        let mut bytes = Vec::new();
+        frame.encode(&mut bytes);
+        self.stream.write_all(&bytes).map_err(|error| AshError::Stream(error.kind()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A stream whose reads are scripted by the test,
+    /// decoding everything written to it into frames.
+    #[derive(Default)]
+    struct Loopback {
+        incoming: VecDeque<u8>,
+        decoder: Decoder,
+        written: Vec<Frame>,
+    }
+
+    impl Loopback {
+        fn push(&mut self, frame: Frame) {
+            let mut bytes = Vec::new();
+            frame.encode(&mut bytes);
+            self.incoming.extend(bytes);
+        }
+
+        fn take_written(&mut self) -> Vec<Frame> {
+            std::mem::take(&mut self.written)
+        }
+    }
+
+    impl ByteStream for Loopback {
+        fn write_all(&mut self, bytes: &[u8]) -> io::Result<()> {
+            for byte in bytes {
+                if let Some(result) = self.decoder.push(*byte) {
+                    self.written.push(result.unwrap());
+                }
+            }
+            Ok(())
+        }
+
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            let count = buf.len().min(self.incoming.len());
+            for (slot, byte) in buf.iter_mut().zip(self.incoming.drain(..count)) {
+                *slot = byte;
+            }
+            Ok(count)
+        }
+    }
+
+    fn connected(config: AshConfig) -> Ash<Loopback> {
+        let mut stream = Loopback::default();
+        stream.push(Frame::RstAck { version: frame::VERSION, reset_code: ResetCode::Software.into() });
+
+        let mut ash = Ash::new(stream, config);
+        assert_eq!(ash.reset(), Ok(ResetCode::Software.into()));
+        assert_eq!(ash.stream_mut().take_written(), [Frame::Rst]);
+        ash
+    }
+
+    fn data(frame_number: u8, retransmit: bool, ack_number: u8, data: &[u8]) -> Frame {
+        Frame::Data { frame_number, retransmit, ack_number, data: data.to_vec() }
+    }
+
+    #[test]
+    fn reset_handshake () {
+        let mut ash = Ash::new(Loopback::default(), AshConfig {
+            reset_timeout: Duration::from_millis(10),
+            ..AshConfig::default()
+        });
+        assert_eq!(ash.send(&[0x00]), Err(AshError::NotConnected));
+        assert_eq!(ash.reset(), Err(AshError::ResetTimeout));
+
+        ash.stream_mut().push(Frame::RstAck { version: 0x01, reset_code: ResetCode::PowerOn.into() });
+        assert_eq!(ash.reset(), Err(AshError::Version(0x01)));
+        assert!(!ash.is_connected());
+
+        connected(AshConfig::default());
+    }
+
+    #[test]
+    fn data_exchange () {
+        let mut ash = connected(AshConfig::default());
+
+        ash.send(&[0x00, 0x00, 0x00, 0x04]).unwrap();
+        assert_eq!(ash.stream_mut().take_written(), [data(0, false, 0, &[0x00, 0x00, 0x00, 0x04])]);
+        assert!(!ash.is_idle());
+
+        ash.stream_mut().push(data(0, false, 1, &[0x00, 0x80, 0x00, 0x04, 0x02, 0x00, 0x67]));
+        assert_eq!(ash.receive().unwrap(), Some(vec![0x00, 0x80, 0x00, 0x04, 0x02, 0x00, 0x67]));
+        assert_eq!(ash.stream_mut().take_written(), [Frame::Ack { ack_number: 1, not_ready: false }]);
+        assert!(ash.is_idle());
+
+        // A retransmitted duplicate is acknowledged again but not delivered.
+        ash.stream_mut().push(data(0, true, 1, &[0x00]));
+        assert_eq!(ash.receive().unwrap(), None);
+        assert_eq!(ash.stream_mut().take_written(), [Frame::Ack { ack_number: 1, not_ready: false }]);
+    }
+
+    #[test]
+    fn window_limits_pending_frames () {
+        let mut ash = connected(AshConfig { window_size: 2, ..AshConfig::default() });
+
+        for byte in 0..3 {
+            ash.send(&[byte]).unwrap();
+        }
+        assert_eq!(ash.stream_mut().take_written(), [data(0, false, 0, &[0]), data(1, false, 0, &[1])]);
+
+        ash.stream_mut().push(Frame::Ack { ack_number: 1, not_ready: false });
+        ash.poll().unwrap();
+        assert_eq!(ash.stream_mut().take_written(), [data(2, false, 0, &[2])]);
+
+        ash.stream_mut().push(Frame::Ack { ack_number: 3, not_ready: false });
+        ash.poll().unwrap();
+        assert!(ash.is_idle());
+    }
+
+    #[test]
+    fn nak_and_bad_frames () {
+        let mut ash = connected(AshConfig::default());
+
+        ash.send(&[0x01]).unwrap();
+        ash.send(&[0x02]).unwrap();
+        ash.stream_mut().take_written();
+
+        ash.stream_mut().push(Frame::Nak { ack_number: 1, not_ready: false });
+        ash.poll().unwrap();
+        assert_eq!(ash.stream_mut().take_written(), [data(1, true, 0, &[0x02])]);
+
+        // Out of sequence and corrupted frames are rejected with a single NAK.
+        ash.stream_mut().push(data(3, false, 2, &[0x03]));
+        ash.stream_mut().incoming.extend([0xC0, 0x00, 0x00, frame::FLAG]);
+        ash.poll().unwrap();
+        assert_eq!(ash.stream_mut().take_written(), [Frame::Nak { ack_number: 0, not_ready: false }]);
+        assert!(ash.is_idle());
+    }
+
+    #[test]
+    fn ack_timeout_retransmits () {
+        let mut ash = connected(AshConfig {
+            ack_timeout_initial: Duration::from_millis(5),
+            ack_timeout_min: Duration::from_millis(5),
+            ack_timeout_max: Duration::from_millis(5),
+            max_ack_timeouts: 1,
+            ..AshConfig::default()
+        });
+
+        ash.send(&[0x01]).unwrap();
+        ash.stream_mut().take_written();
+
+        std::thread::sleep(Duration::from_millis(10));
+        ash.poll().unwrap();
+        assert_eq!(ash.stream_mut().take_written(), [data(0, true, 0, &[0x01])]);
+
+        std::thread::sleep(Duration::from_millis(10));
+        assert_eq!(ash.poll(), Err(AshError::AckTimeout));
+        assert!(!ash.is_connected());
+    }
+
+    #[test]
+    fn ncp_errors () {
+        let mut ash = connected(AshConfig::default());
+        ash.stream_mut().push(Frame::Error {
+            version: frame::VERSION,
+            error_code: ResetCode::ExceededMaximumAckTimeoutCount.into(),
+        });
+        assert_eq!(ash.poll(), Err(AshError::NcpError(ResetCode::ExceededMaximumAckTimeoutCount.into())));
+
+        let mut ash = connected(AshConfig::default());
+        ash.stream_mut().push(Frame::RstAck { version: frame::VERSION, reset_code: ResetCode::Watchdog.into() });
+        assert_eq!(ash.poll(), Err(AshError::NcpReset(ResetCode::Watchdog.into())));
+    }
+}
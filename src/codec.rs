@@ -0,0 +1,439 @@
+//! Little-endian wire encoding for EZSP frame parameters.
+//!
+//! All EZSP data is transmitted and received in little endian,
+//! multi-byte values are written least significant byte first.
+
+/// Errors produced while encoding or decoding EZSP values.
+#[derive(Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum CodecError {
+    /// There is not enough space left in the buffer to write the value.
+    BufferTooSmall,
+
+    /// The buffer ended before the value was fully read.
+    UnexpectedEnd,
+
+    /// The bytes read do not represent a valid value.
+    InvalidValue,
+}
+
+/// A cursor that writes encoded values into a byte buffer.
+pub struct Writer<'a> {
+    buf: &'a mut [u8],
+    position: usize,
+}
+
+impl<'a> Writer<'a> {
+    /// Creates a writer starting at the beginning of `buf`.
+    pub fn new(buf: &'a mut [u8]) -> Self {
+        Writer { buf, position: 0 }
+    }
+
+    /// The number of bytes written so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// The number of bytes that can still be written.
+    pub fn remaining(&self) -> usize {
+        self.buf.len() - self.position
+    }
+
+    /// Writes raw bytes.
+    pub fn write_bytes(&mut self, bytes: &[u8]) -> Result<(), CodecError> {
+        if bytes.len() > self.remaining() {
+            return Err(CodecError::BufferTooSmall);
+        }
+
+        self.buf[self.position..self.position + bytes.len()].copy_from_slice(bytes);
+        self.position += bytes.len();
+
+        Ok(())
+    }
+
+    /// Writes a single byte.
+    pub fn write_u8(&mut self, byte: u8) -> Result<(), CodecError> {
+        self.write_bytes(&[byte])
+    }
+}
+
+/// A cursor that reads encoded values from a byte buffer.
+pub struct Reader<'a> {
+    buf: &'a [u8],
+    position: usize,
+}
+
+impl<'a> Reader<'a> {
+    /// Creates a reader starting at the beginning of `buf`.
+    pub fn new(buf: &'a [u8]) -> Self {
+        Reader { buf, position: 0 }
+    }
+
+    /// The number of bytes read so far.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+
+    /// The number of bytes left to read.
+    pub fn remaining(&self) -> usize {
+        self.buf.len() - self.position
+    }
+
+    /// Reads `length` raw bytes.
+    pub fn read_bytes(&mut self, length: usize) -> Result<&'a [u8], CodecError> {
+        if length > self.remaining() {
+            return Err(CodecError::UnexpectedEnd);
+        }
+
+        let bytes = &self.buf[self.position..self.position + length];
+        self.position += length;
+
+        Ok(bytes)
+    }
+
+    /// Reads a single byte.
+    pub fn read_u8(&mut self) -> Result<u8, CodecError> {
+        Ok(self.read_bytes(1)?[0])
+    }
+
+    /// Reads a fixed number of raw bytes.
+    pub fn read_array<const N: usize>(&mut self) -> Result<[u8; N], CodecError> {
+        let mut array = [0u8; N];
+        array.copy_from_slice(self.read_bytes(N)?);
+        Ok(array)
+    }
+}
+
+/// A value that can be written in its EZSP wire format.
+pub trait EzspEncode {
+    /// Writes the value to `writer`.
+    fn encode(&self, writer: &mut Writer) -> Result<(), CodecError>;
+
+    /// Writes the value into the start of `buf`,
+    /// returning the number of bytes written.
+    fn encode_to_slice(&self, buf: &mut [u8]) -> Result<usize, CodecError> {
+        let mut writer = Writer::new(buf);
+        self.encode(&mut writer)?;
+        Ok(writer.position())
+    }
+}
+
+/// A value that can be read from its EZSP wire format.
+pub trait EzspDecode: Sized {
+    /// Reads the value from `reader`.
+    fn decode(reader: &mut Reader) -> Result<Self, CodecError>;
+
+    /// Reads the value from the start of `buf`,
+    /// returning it and the number of bytes consumed.
+    fn decode_from_slice(buf: &[u8]) -> Result<(Self, usize), CodecError> {
+        let mut reader = Reader::new(buf);
+        let value = Self::decode(&mut reader)?;
+        Ok((value, reader.position()))
+    }
+}
+
+/// A value that always occupies the same number of bytes on the wire.
+pub trait WireSize {
+    /// The encoded size in bytes.
+    const WIRE_SIZE: usize;
+}
+
+pub use ember_rs_derive::EzspCodec;
+
+/// A `#[repr]` enum received from or sent to the NCP.
+/// 
+/// Implemented by `#[derive(EzspCodec)]` on enums.
+pub trait EzspEnum: Sized + Copy {
+    /// The integer type the enum is encoded as.
+    type Repr: Copy + Eq + EzspEncode + EzspDecode;
+
+    /// Looks up the variant with the given discriminant.
+    fn from_raw(raw: Self::Repr) -> Option<Self>;
+
+    /// The discriminant of the variant.
+    fn into_raw(self) -> Self::Repr;
+
+    /// The name of the variant, for display.
+    fn name(self) -> &'static str;
+}
+
+/// The error returned when converting a value that
+/// does not match any variant of an enum.
+/// 
+/// Carries the value so it can still be reported.
+#[derive(Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct UnknownValue<T>(pub T);
+
+/// An enum value decoded from the NCP which is preserved
+/// even when it does not match any known variant.
+/// 
+/// Used where newer firmware may report values this crate
+/// doesn't know about, so decoding the rest of the frame
+/// can still succeed.
+#[derive(Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum Recognized<E: EzspEnum> {
+    /// The value matched a variant.
+    Known(E),
+
+    /// The value did not match any variant.
+    Unknown(E::Repr),
+}
+
+impl<E: EzspEnum> Recognized<E> {
+    /// Wraps a raw value, recognizing it if possible.
+    pub fn from_raw(raw: E::Repr) -> Self {
+        match E::from_raw(raw) {
+            Some(value) => Recognized::Known(value),
+            None => Recognized::Unknown(raw),
+        }
+    }
+
+    /// The known variant, if the value was recognized.
+    pub fn known(self) -> Option<E> {
+        match self {
+            Recognized::Known(value) => Some(value),
+            Recognized::Unknown(_) => None,
+        }
+    }
+
+    /// The value as it appeared on the wire.
+    pub fn raw(self) -> E::Repr {
+        match self {
+            Recognized::Known(value) => value.into_raw(),
+            Recognized::Unknown(raw) => raw,
+        }
+    }
+}
+
+impl<E: EzspEnum + PartialEq> PartialEq<E> for Recognized<E> {
+    fn eq(&self, other: &E) -> bool {
+        matches!(self, Recognized::Known(value) if value == other)
+    }
+}
+
+impl<E: EzspEnum> From<E> for Recognized<E> {
+    fn from(value: E) -> Self {
+        Recognized::Known(value)
+    }
+}
+
+impl<E: EzspEnum> EzspEncode for Recognized<E> {
+    fn encode(&self, writer: &mut Writer) -> Result<(), CodecError> {
+        self.raw().encode(writer)
+    }
+}
+
+impl<E: EzspEnum> EzspDecode for Recognized<E> {
+    fn decode(reader: &mut Reader) -> Result<Self, CodecError> {
+        Ok(Self::from_raw(E::Repr::decode(reader)?))
+    }
+}
+
+impl<E: EzspEnum + WireSize> WireSize for Recognized<E> {
+    const WIRE_SIZE: usize = E::WIRE_SIZE;
+}
+
+macro_rules! impl_codec_for_int {
+    ($($int: ty),+) => {
+        $(
+            impl EzspEncode for $int {
+                fn encode(&self, writer: &mut Writer) -> Result<(), CodecError> {
+                    writer.write_bytes(&self.to_le_bytes())
+                }
+            }
+
+            impl EzspDecode for $int {
+                fn decode(reader: &mut Reader) -> Result<Self, CodecError> {
+                    Ok(<$int>::from_le_bytes(reader.read_array()?))
+                }
+            }
+
+            impl WireSize for $int {
+                const WIRE_SIZE: usize = core::mem::size_of::<$int>();
+            }
+        )+
+    };
+}
+
+impl_codec_for_int!(u8, u16, u32, u64, i8, i16, i32);
+
+impl EzspEncode for bool {
+    fn encode(&self, writer: &mut Writer) -> Result<(), CodecError> {
+        writer.write_u8(*self as u8)
+    }
+}
+
+impl EzspDecode for bool {
+    fn decode(reader: &mut Reader) -> Result<Self, CodecError> {
+        Ok(reader.read_u8()? != 0)
+    }
+}
+
+impl WireSize for bool {
+    const WIRE_SIZE: usize = 1;
+}
+
+impl<T: EzspEncode, const N: usize> EzspEncode for [T; N] {
+    fn encode(&self, writer: &mut Writer) -> Result<(), CodecError> {
+        for item in self {
+            item.encode(writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl<T: EzspDecode, const N: usize> EzspDecode for [T; N] {
+    fn decode(reader: &mut Reader) -> Result<Self, CodecError> {
+        let mut items: [Option<T>; N] = core::array::from_fn(|_| None);
+        for item in items.iter_mut() {
+            *item = Some(T::decode(reader)?);
+        }
+        Ok(items.map(|item| item.unwrap()))
+    }
+}
+
+impl<T: WireSize, const N: usize> WireSize for [T; N] {
+    const WIRE_SIZE: usize = T::WIRE_SIZE * N;
+}
+
+/// Writes a single length byte followed by `items`.
+/// 
+/// Used for the variable length arrays in EZSP frames, such
+/// as message contents, which are preceded by their length.
+pub fn encode_length_prefixed<T: EzspEncode>(items: &[T], writer: &mut Writer) -> Result<(), CodecError> {
+    let length = u8::try_from(items.len()).map_err(|_| CodecError::InvalidValue)?;
+    writer.write_u8(length)?;
+    for item in items {
+        item.encode(writer)?;
+    }
+    Ok(())
+}
+
+/// Reads a single length byte followed by that many items.
+pub fn decode_length_prefixed<T: EzspDecode>(reader: &mut Reader) -> Result<Vec<T>, CodecError> {
+    let length = reader.read_u8()?;
+    (0..length).map(|_| T::decode(reader)).collect()
+}
+
+/// Implements [`EzspEncode`] and [`EzspDecode`] for a mask
+/// declared with `bitmask!` by encoding its raw value.
+/// 
+/// Must be invoked from the module the mask is declared in (or a
+/// child of it) as the raw value of the mask is a private field.
+macro_rules! impl_bitmask_codec {
+    ($($name: ident : $repr: ty),+ $(,)?) => {
+        $(
+            impl $crate::codec::EzspEncode for $name {
+                fn encode(&self, writer: &mut $crate::codec::Writer) -> Result<(), $crate::codec::CodecError> {
+                    $crate::codec::EzspEncode::encode(&**self, writer)
+                }
+            }
+
+            impl $crate::codec::EzspDecode for $name {
+                fn decode(reader: &mut $crate::codec::Reader) -> Result<Self, $crate::codec::CodecError> {
+                    Ok($name { mask: <$repr as $crate::codec::EzspDecode>::decode(reader)? })
+                }
+            }
+
+            impl $crate::codec::WireSize for $name {
+                const WIRE_SIZE: usize = core::mem::size_of::<$repr>();
+            }
+        )+
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn integers_are_little_endian () {
+        let mut buf = [0u8; 7];
+        let mut writer = Writer::new(&mut buf);
+
+        0x1234u16.encode(&mut writer).unwrap();
+        0xAABBCCDDu32.encode(&mut writer).unwrap();
+        (-2i8).encode(&mut writer).unwrap();
+
+        assert_eq!(buf, [0x34, 0x12, 0xDD, 0xCC, 0xBB, 0xAA, 0xFE]);
+
+        let mut reader = Reader::new(&buf);
+        assert_eq!(u16::decode(&mut reader), Ok(0x1234));
+        assert_eq!(u32::decode(&mut reader), Ok(0xAABBCCDD));
+        assert_eq!(i8::decode(&mut reader), Ok(-2));
+        assert_eq!(reader.remaining(), 0);
+    }
+
+    #[test]
+    fn arrays () {
+        let mut buf = [0u8; 4];
+        assert_eq!([0x0102u16, 0x0304].encode_to_slice(&mut buf), Ok(4));
+        assert_eq!(buf, [0x02, 0x01, 0x04, 0x03]);
+        assert_eq!(<[u16; 2]>::decode_from_slice(&buf), Ok(([0x0102, 0x0304], 4)));
+    }
+
+    #[test]
+    fn length_prefixed () {
+        let mut buf = [0u8; 5];
+        let mut writer = Writer::new(&mut buf);
+        encode_length_prefixed(&[0x0006u16, 0x0008], &mut writer).unwrap();
+        assert_eq!(buf, [0x02, 0x06, 0x00, 0x08, 0x00]);
+
+        let mut reader = Reader::new(&buf);
+        assert_eq!(decode_length_prefixed::<u16>(&mut reader), Ok(vec![0x0006, 0x0008]));
+        assert_eq!(decode_length_prefixed::<u8>(&mut Reader::new(&[0x03, 0x01])), Err(CodecError::UnexpectedEnd));
+    }
+
+    #[test]
+    fn derived_codec () {
+        #[derive(EzspCodec, Copy, Clone, PartialEq, Debug)]
+        #[repr(u16)]
+        enum Kind {
+            A = 0x0001,
+            B = 0x0100,
+        }
+
+        #[derive(EzspCodec, PartialEq, Debug)]
+        struct Fixed {
+            kind: Kind,
+            values: [u8; 2],
+        }
+
+        #[derive(EzspCodec, PartialEq, Debug)]
+        struct Variable {
+            tag: u8,
+            #[ezsp(length_prefixed)]
+            contents: Vec<u8>,
+        }
+
+        assert_eq!(Kind::WIRE_SIZE, 2);
+        assert_eq!(Fixed::WIRE_SIZE, 4);
+        assert_eq!(
+            Fixed::decode_from_slice(&[0x00, 0x01, 0xAA, 0xBB]),
+            Ok((Fixed { kind: Kind::B, values: [0xAA, 0xBB] }, 4))
+        );
+        assert_eq!(Kind::decode_from_slice(&[0x02, 0x00]), Err(CodecError::InvalidValue));
+        assert_eq!(Kind::try_from(0x0100u16), Ok(Kind::B));
+        assert_eq!(Kind::try_from(0x0002u16), Err(UnknownValue(0x0002)));
+        assert_eq!(u16::from(Kind::A), 0x0001);
+
+        assert_eq!(Recognized::<Kind>::decode_from_slice(&[0x01, 0x00]), Ok((Recognized::Known(Kind::A), 2)));
+        assert_eq!(Recognized::<Kind>::decode_from_slice(&[0x02, 0x00]), Ok((Recognized::Unknown(0x0002), 2)));
+        assert_eq!(Recognized::<Kind>::Unknown(0x0002).raw(), 0x0002);
+        assert_eq!(Recognized::from(Kind::B).known(), Some(Kind::B));
+
+        let mut buf = [0u8; 4];
+        let variable = Variable { tag: 7, contents: vec![1, 2] };
+        assert_eq!(variable.encode_to_slice(&mut buf), Ok(4));
+        assert_eq!(buf, [0x07, 0x02, 0x01, 0x02]);
+        assert_eq!(Variable::decode_from_slice(&buf), Ok((variable, 4)));
+    }
+
+    #[test]
+    fn buffer_bounds () {
+        assert_eq!(0x1234u16.encode_to_slice(&mut [0u8; 1]), Err(CodecError::BufferTooSmall));
+        assert_eq!(u32::decode_from_slice(&[0x00, 0x01]), Err(CodecError::UnexpectedEnd));
+    }
+}
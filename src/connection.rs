@@ -0,0 +1,474 @@
+//! EZSP connection to an NCP over an ASH link.
+
+mod addresses;
+mod configuration;
+mod counters;
+mod endpoint;
+mod incoming;
+mod joining;
+mod messaging;
+mod network;
+mod policy;
+mod routing;
+mod scan;
+mod tables;
+mod utilities;
+mod value;
+
+pub use addresses::AddressCache;
+pub use configuration::{ConfigReport, NcpConfig, Rejected};
+pub use counters::{CounterDelta, CounterSnapshot};
+pub use incoming::{IncomingMessage, MAX_QUEUED_MESSAGES, MessageFilter, Subscription};
+pub use joining::{JoinEvent, JoinEvents};
+pub use messaging::{Destination, OutgoingMessage, SentMessage};
+pub use network::{NetworkChange, NetworkOutcome};
+pub use scan::{Scan, ScanEvent};
+pub use tables::NetworkTables;
+
+use incoming::Subscriber;
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::ash::{Ash, AshConfig, ByteStream};
+use crate::ash::frame::MAX_DATA_LENGTH;
+use crate::codec::{CodecError, EzspDecode, EzspEncode, Recognized};
+use crate::ember::types::{NetworkStatus, Status as EmberStatus};
+use crate::error::Error;
+use crate::ezsp::capabilities::{Capabilities, MAX_PROTOCOL_VERSION};
+use crate::ezsp::endpoint::Endpoint;
+use crate::ezsp::routing::SourceRouteTable;
+use crate::ezsp::types::Status;
+use crate::frames::{CallbackFrame, Frame, Version, VersionResponse};
+use crate::{CallbackTypes, FrameHeader, FrameID, HeaderCodec};
+
+/// The frame ID of the response the NCP sends
+/// when it cannot process a command.
+pub const INVALID_COMMAND: FrameID = 0x0058;
+
+/// A frame sent by the NCP that is not the response to a command.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Callback {
+    /// The header of the callback frame.
+    pub header: FrameHeader,
+
+    /// The callback parameters.
+    pub parameters: Vec<u8>,
+}
+
+impl Callback {
+    /// The frame ID of the callback.
+    pub fn frame_id(&self) -> FrameID {
+        self.header.frame_id
+    }
+
+    /// Decodes the parameters as `C`, or returns `None`
+    /// if the callback is a different frame.
+    pub fn parse<C: CallbackFrame>(&self) -> Option<Result<C, Error>> {
+        (self.frame_id() == C::frame_id())
+            .then(|| C::decode_from_slice(&self.parameters).map(|(callback, _)| callback).map_err(Error::from))
+    }
+}
+
+/// An EZSP connection to an NCP.
+///
+/// [`connect`](Connection::connect) resets the NCP and negotiates
+/// the protocol version, after which commands can be sent with
+/// [`command`](Connection::command). Callbacks received while
+/// waiting for a response are queued until they are polled.
+#[derive(Debug)]
+pub struct Connection<S> {
+    ash: Ash<S>,
+    headers: HeaderCodec,
+    sequence: u8,
+    version: Option<VersionResponse>,
+    capabilities: Option<Capabilities>,
+    callbacks: VecDeque<Callback>,
+    response_timeout: Duration,
+    overflows: usize,
+    endpoints: Vec<Endpoint>,
+    counters_cleared: Option<Instant>,
+    network_status: NetworkStatus,
+    zdo_sequence: u8,
+    source_routes: SourceRouteTable,
+    message_tag: u8,
+    sent_messages: Vec<(u8, Option<Recognized<EmberStatus>>)>,
+    subscribers: Vec<Subscriber>,
+    next_subscription: usize,
+    addresses: AddressCache,
+}
+
+impl<S: ByteStream> Connection<S> {
+    /// Creates a connection over `stream` that has not been reset yet.
+    pub fn new(stream: S, config: AshConfig) -> Self {
+        Connection {
+            ash: Ash::new(stream, config),
+            headers: HeaderCodec::new(),
+            sequence: 0,
+            version: None,
+            capabilities: None,
+            callbacks: VecDeque::new(),
+            response_timeout: Duration::from_secs(5),
+            overflows: 0,
+            endpoints: Vec::new(),
+            counters_cleared: None,
+            network_status: NetworkStatus::NoNetwork,
+            zdo_sequence: 0,
+            source_routes: SourceRouteTable::new(),
+            message_tag: 0,
+            sent_messages: Vec::new(),
+            subscribers: Vec::new(),
+            next_subscription: 0,
+            addresses: AddressCache::new(),
+        }
+    }
+
+    /// Sets how long to wait for the response to a command.
+    pub fn with_response_timeout(mut self, timeout: Duration) -> Self {
+        self.response_timeout = timeout;
+        self
+    }
+
+    /// The underlying ASH link.
+    pub fn ash(&self) -> &Ash<S> {
+        &self.ash
+    }
+
+    /// The underlying ASH link, mutably.
+    pub fn ash_mut(&mut self) -> &mut Ash<S> {
+        &mut self.ash
+    }
+
+    /// The version information reported by the NCP during negotiation.
+    pub fn version(&self) -> Option<&VersionResponse> {
+        self.version.as_ref()
+    }
+
+    /// What the NCP supports at the negotiated protocol version.
+    pub fn capabilities(&self) -> Option<Capabilities> {
+        self.capabilities
+    }
+
+    /// The number of responses that reported the NCP ran out
+    /// of memory and dropped callbacks.
+    pub fn overflows(&self) -> usize {
+        self.overflows
+    }
+
+    /// Resets the NCP and negotiates the newest protocol
+    /// version supported by both sides.
+    pub fn connect(&mut self) -> Result<VersionResponse, Error> {
+        self.ash.reset()?;
+        self.callbacks.clear();
+        self.endpoints.clear();
+        self.counters_cleared = Some(Instant::now());
+        self.network_status = NetworkStatus::NoNetwork;
+        self.sent_messages.clear();
+        self.clear_subscriptions();
+        self.negotiate(MAX_PROTOCOL_VERSION)
+    }
+
+    /// Negotiates the protocol version with the NCP, starting with `desired`.
+    ///
+    /// If the NCP reports a different version that this crate
+    /// supports, negotiation is retried with that version.
+    pub fn negotiate(&mut self, desired: u8) -> Result<VersionResponse, Error> {
+        self.headers.reset();
+        self.version = None;
+        self.capabilities = None;
+
+        let mut response = self.request_version(desired)?;
+        if response.protocol_version != desired {
+            if !Capabilities::is_supported_version(response.protocol_version) {
+                return Err(Error::UnsupportedVersion(response.protocol_version));
+            }
+
+            let reported = response.protocol_version;
+            response = self.request_version(reported)?;
+            if response.protocol_version != reported {
+                return Err(Error::UnsupportedVersion(response.protocol_version));
+            }
+        }
+
+        let capabilities = Capabilities::for_version(response.protocol_version)
+            .ok_or(Error::UnsupportedVersion(response.protocol_version))?;
+
+        self.headers.negotiated(response.protocol_version);
+        self.capabilities = Some(capabilities);
+        self.version = Some(response.clone());
+        Ok(response)
+    }
+
+    /// Sends a command and waits for its response.
+    ///
+    /// Fails with [`Error::UnsupportedCommand`] without sending
+    /// anything if the negotiated version lacks the command.
+    pub fn command<F: Frame>(&mut self, command: &F) -> Result<F::Response, Error> {
+        let capabilities = self.capabilities.ok_or(Error::NotNegotiated)?;
+        capabilities.check_frame(F::frame_id())?;
+
+        self.exchange(F::frame_id(), command)
+    }
+
+    /// Returns the next callback received from the NCP, if any.
+    ///
+    /// Incoming messages taken by a [`subscribe`](Connection::subscribe)d
+    /// filter are not returned.
+    pub fn poll_callback(&mut self) -> Result<Option<Callback>, Error> {
+        if self.callbacks.is_empty() {
+            while let Some(data) = self.ash.receive()? {
+                let (header, length) = self.headers.decode(&data)?;
+                let callback = Callback { header, parameters: data[length..].to_vec() };
+                if !self.observe(&callback) {
+                    self.callbacks.push_back(callback);
+                }
+            }
+        }
+
+        Ok(self.callbacks.pop_front())
+    }
+
+    /// Waits up to `timeout` for a callback accepted by `matches`.
+    ///
+    /// Callbacks that do not match stay queued for
+    /// [`poll_callback`](Connection::poll_callback).
+    pub fn wait_for_callback(
+        &mut self,
+        timeout: Duration,
+        matches: impl Fn(&Callback) -> bool,
+    ) -> Result<Option<Callback>, Error> {
+        if let Some(index) = self.callbacks.iter().position(&matches) {
+            return Ok(self.callbacks.remove(index));
+        }
+
+        self.receive_callback(timeout, matches)
+    }
+
+    /// Receives callbacks for up to `timeout` until one is accepted by
+    /// `matches`, queueing the others unless a subscription took them.
+    fn receive_callback(
+        &mut self,
+        timeout: Duration,
+        matches: impl Fn(&Callback) -> bool,
+    ) -> Result<Option<Callback>, Error> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let Some(data) = self.ash.receive()? else {
+                if Instant::now() >= deadline {
+                    return Ok(None);
+                }
+                continue;
+            };
+
+            let (header, length) = self.headers.decode(&data)?;
+            let callback = Callback { header, parameters: data[length..].to_vec() };
+            let taken = self.observe(&callback);
+            if matches(&callback) {
+                return Ok(Some(callback));
+            }
+            if !taken {
+                self.callbacks.push_back(callback);
+            }
+        }
+    }
+
+    /// Updates the state kept from callbacks as they are received.
+    ///
+    /// Returns whether a subscription took the callback,
+    /// in which case it is not queued.
+    fn observe(&mut self, callback: &Callback) -> bool {
+        self.track_network_status(callback);
+        self.record_source_route(callback);
+        self.track_sent_message(callback);
+        self.track_addresses(callback);
+        self.dispatch_message(callback)
+    }
+
+    fn request_version(&mut self, desired: u8) -> Result<VersionResponse, Error> {
+        self.exchange(Version::frame_id(), &Version { desired_protocol_version: desired })
+    }
+
+    fn exchange<P: EzspEncode, R: EzspDecode>(&mut self, frame_id: FrameID, parameters: &P) -> Result<R, Error> {
+        let response = self.exchange_raw(frame_id, parameters, |id| id == frame_id)?;
+        Ok(R::decode_from_slice(&response.parameters)?.0)
+    }
+
+    /// Sends a command and waits for a response whose frame ID is `accepted`.
+    fn exchange_raw<P: EzspEncode>(
+        &mut self,
+        frame_id: FrameID,
+        parameters: &P,
+        accepted: impl Fn(FrameID) -> bool,
+    ) -> Result<Callback, Error> {
+        let sequence = self.sequence;
+        self.sequence = self.sequence.wrapping_add(1);
+
+        let mut data = [0u8; MAX_DATA_LENGTH];
+        let header_length = self.headers.encode(&FrameHeader::command(sequence, frame_id), &mut data)?;
+        let parameters_length = parameters
+            .encode_to_slice(&mut data[header_length..])
+            .map_err(|error| match error {
+                CodecError::BufferTooSmall => Error::Ezsp(Status::ErrorCommandTooLong),
+                error => Error::Codec(error),
+            })?;
+        self.ash.send(&data[..header_length + parameters_length])?;
+
+        let deadline = Instant::now() + self.response_timeout;
+        loop {
+            let Some(data) = self.ash.receive()? else {
+                if Instant::now() >= deadline {
+                    return Err(Error::Ezsp(Status::ErrorNoResponse));
+                }
+                continue;
+            };
+
+            let (header, length) = self.headers.decode(&data)?;
+            let parameters = data[length..].to_vec();
+
+            let is_response = header.sequence == sequence
+                && header.callback_type() != Some(CallbackTypes::Asynchronous)
+                && (accepted(header.frame_id) || header.frame_id == INVALID_COMMAND);
+            if !is_response {
+                let callback = Callback { header, parameters };
+                if !self.observe(&callback) {
+                    self.callbacks.push_back(callback);
+                }
+                continue;
+            }
+
+            if header.overflow() {
+                self.overflows += 1;
+            }
+            if header.frame_id == INVALID_COMMAND {
+                return Err(match parameters.first().map(|raw| Recognized::<Status>::from_raw(*raw)) {
+                    Some(Recognized::Known(status)) => Error::Ezsp(status),
+                    Some(Recognized::Unknown(raw)) => Error::UnknownEzspStatus(raw),
+                    None => Error::Ezsp(Status::ErrorInvalidFrameID),
+                });
+            }
+            if header.truncated() {
+                return Err(Error::Ezsp(Status::ErrorTruncated));
+            }
+
+            return Ok(Callback { header, parameters });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::frames::{LeaveNetwork, NetworkState};
+    use crate::simulator::{Fault, SimulatedNcp};
+
+    fn connection(ncp: SimulatedNcp) -> Connection<SimulatedNcp> {
+        Connection::new(ncp, AshConfig::default()).with_response_timeout(Duration::from_millis(50))
+    }
+
+    /// A connection that has negotiated a protocol version with `ncp`.
+    pub(super) fn connect_to(ncp: SimulatedNcp) -> Connection<SimulatedNcp> {
+        let mut connection = connection(ncp);
+        connection.connect().unwrap();
+        connection
+    }
+
+    /// A connection that has negotiated a protocol version with a new simulator.
+    pub(super) fn connect() -> Connection<SimulatedNcp> {
+        connect_to(SimulatedNcp::new())
+    }
+
+    #[test]
+    fn connect_negotiates_newest_version () {
+        let mut connection = connection(SimulatedNcp::new());
+        assert_eq!(connection.command(&Version { desired_protocol_version: 12 }), Err(Error::NotNegotiated));
+
+        let version = connection.connect().unwrap();
+        assert_eq!(version, VersionResponse { protocol_version: 12, stack_type: 2, stack_version: 0x7300 });
+        assert_eq!(connection.capabilities().unwrap().protocol_version(), 12);
+
+        let response = connection.command(&Version { desired_protocol_version: 12 }).unwrap();
+        assert_eq!(response.protocol_version, 12);
+    }
+
+    #[test]
+    fn renegotiates_reported_version () {
+        let mut connection = connection(SimulatedNcp::new().with_version(7, 7, 0x6500));
+
+        assert_eq!(connection.connect().unwrap().protocol_version, 7);
+        assert_eq!(connection.ash().stream().negotiated_version(), Some(7));
+        assert_eq!(connection.capabilities().unwrap().header_format(), crate::HeaderFormat::Legacy);
+
+        let mut connection = self::connection(SimulatedNcp::new().with_version(20, 20, 0x9000));
+        assert_eq!(connection.connect(), Err(Error::UnsupportedVersion(20)));
+
+        let mut connection = self::connection(SimulatedNcp::new().with_version(13, 13, 0x7430));
+        assert_eq!(connection.connect(), Err(Error::UnsupportedVersion(13)));
+    }
+
+    #[test]
+    fn unsupported_commands_are_not_sent () {
+        frame! {
+            /// A frame only available before version 8.
+            LargeId: 0x0100
+            parameters {}
+            response LargeIdResponse {}
+        }
+
+        let mut connection = connection(SimulatedNcp::new().with_version(7, 7, 0x6500));
+        connection.connect().unwrap();
+        let sent = connection.ash().stream().received().len();
+
+        assert_eq!(
+            connection.command(&LargeId {}),
+            Err(Error::UnsupportedCommand { frame_id: 0x0100, protocol_version: 7 })
+        );
+        assert_eq!(connection.ash().stream().received().len(), sent);
+    }
+
+    #[test]
+    fn errors_and_callbacks () {
+        let mut connection = connection(SimulatedNcp::new());
+        connection.connect().unwrap();
+
+        connection.ash_mut().stream_mut().emit_callback(0x0019, &[0x90]);
+        connection.ash_mut().stream_mut().inject_fault(Fault::InvalidCommand(Status::ErrorOutOfMemory.into()));
+        let version = Version { desired_protocol_version: 12 };
+        assert_eq!(connection.command(&version), Err(Error::Ezsp(Status::ErrorOutOfMemory)));
+
+        connection.ash_mut().stream_mut().inject_fault(Fault::InvalidCommand(Recognized::Unknown(0xEE)));
+        assert_eq!(connection.command(&version), Err(Error::UnknownEzspStatus(0xEE)));
+
+        let callback = connection.poll_callback().unwrap().unwrap();
+        assert_eq!(callback.frame_id(), 0x0019);
+        assert_eq!(callback.parameters, [0x90]);
+        assert_eq!(connection.poll_callback(), Ok(None));
+
+        connection.ash_mut().stream_mut().inject_fault(Fault::Truncated(2));
+        assert_eq!(connection.command(&version), Err(Error::Ezsp(Status::ErrorTruncated)));
+
+        connection.ash_mut().stream_mut().inject_fault(Fault::Truncated(0));
+        assert_eq!(connection.command(&version), Err(Error::Ezsp(Status::ErrorTruncated)));
+
+        connection.ash_mut().stream_mut().inject_fault(Fault::Overflow);
+        assert!(connection.command(&version).is_ok());
+        assert_eq!(connection.overflows(), 1);
+
+        connection.ash_mut().stream_mut().inject_fault(Fault::NoResponse);
+        assert_eq!(connection.command(&version), Err(Error::Ezsp(Status::ErrorNoResponse)));
+    }
+
+    #[test]
+    fn unknown_statuses_are_preserved () {
+        let mut connection = connection(SimulatedNcp::new());
+        connection.connect().unwrap();
+
+        connection.ash_mut().stream_mut().on_command(LeaveNetwork::frame_id(), |_| vec![0xEE]);
+        let response = connection.command(&LeaveNetwork {}).unwrap();
+        assert_eq!(response.status, Recognized::<EmberStatus>::Unknown(0xEE));
+        assert_eq!(response.status.into_result(), Err(Error::UnknownEmberStatus(0xEE)));
+
+        connection.ash_mut().stream_mut().on_command(NetworkState::frame_id(), |_| vec![0x7E]);
+        assert_eq!(connection.network_state(), Ok(Recognized::Unknown(0x7E)));
+        assert_eq!(connection.network_status(), NetworkStatus::NoNetwork);
+    }
+}
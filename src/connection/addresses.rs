@@ -0,0 +1,329 @@
+//! The address table and the host's EUI64 to node ID cache.
+
+use std::collections::HashMap;
+
+use crate::ash::ByteStream;
+use crate::codec::Recognized;
+use crate::ember::types::{DeviceUpdate, EUI64, NULL_NODE_ID, NodeId, Status as EmberStatus};
+use crate::error::Error;
+use crate::frames::{
+    GetAddressTableRemoteEui64, GetAddressTableRemoteNodeId, IdConflictHandler, IncomingRouteRecordHandler,
+    LookupEui64ByNodeId, LookupNodeIdByEui64, SetAddressTableRemoteEui64, SetAddressTableRemoteNodeId,
+};
+
+use super::joining::ZDO_PROFILE;
+use super::{Callback, Connection, IncomingMessage, JoinEvent};
+
+/// Device_annce, sent by devices when they join or change node ID.
+const DEVICE_ANNOUNCE: u16 = 0x0013;
+
+/// NWK_addr_rsp and IEEE_addr_rsp.
+const NWK_ADDRESS_RESPONSE: u16 = 0x8000;
+const IEEE_ADDRESS_RESPONSE: u16 = 0x8001;
+
+/// Node IDs and EUI64s known to belong together.
+///
+/// Each EUI64 maps to one node ID and each node ID to one EUI64,
+/// so recording a device under a new node ID, e.g. after it
+/// rejoined, forgets its old one.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct AddressCache {
+    node_ids: HashMap<EUI64, NodeId>,
+    eui64s: HashMap<NodeId, EUI64>,
+}
+
+impl AddressCache {
+    /// An empty cache.
+    pub fn new() -> Self {
+        AddressCache::default()
+    }
+
+    /// Records that the device with `eui64` uses `node_id`, returning
+    /// the node ID it was known by before if that differs.
+    pub fn insert(&mut self, node_id: NodeId, eui64: EUI64) -> Option<NodeId> {
+        if let Some(previous) = self.eui64s.insert(node_id, eui64)
+            && previous != eui64
+        {
+            self.node_ids.remove(&previous);
+        }
+
+        let previous = self.node_ids.insert(eui64, node_id).filter(|previous| *previous != node_id)?;
+        self.eui64s.remove(&previous);
+        Some(previous)
+    }
+
+    /// The node ID of the device with `eui64`.
+    pub fn node_id(&self, eui64: &EUI64) -> Option<NodeId> {
+        self.node_ids.get(eui64).copied()
+    }
+
+    /// The EUI64 of the device using `node_id`.
+    pub fn eui64(&self, node_id: NodeId) -> Option<EUI64> {
+        self.eui64s.get(&node_id).copied()
+    }
+
+    /// Forgets the device using `node_id`, e.g. after an ID conflict.
+    pub fn remove_node_id(&mut self, node_id: NodeId) -> Option<EUI64> {
+        let eui64 = self.eui64s.remove(&node_id)?;
+        self.node_ids.remove(&eui64);
+        Some(eui64)
+    }
+
+    /// Forgets the device with `eui64`, e.g. after it left the network.
+    pub fn remove_eui64(&mut self, eui64: &EUI64) -> Option<NodeId> {
+        let node_id = self.node_ids.remove(eui64)?;
+        self.eui64s.remove(&node_id);
+        Some(node_id)
+    }
+
+    /// Forgets every device.
+    pub fn clear(&mut self) {
+        self.node_ids.clear();
+        self.eui64s.clear();
+    }
+
+    /// The number of devices known.
+    pub fn len(&self) -> usize {
+        self.node_ids.len()
+    }
+
+    /// Whether no devices are known.
+    pub fn is_empty(&self) -> bool {
+        self.node_ids.is_empty()
+    }
+
+    /// Every node ID and EUI64 known, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (NodeId, EUI64)> + '_ {
+        self.eui64s.iter().map(|(node_id, eui64)| (*node_id, *eui64))
+    }
+}
+
+impl<S: ByteStream> Connection<S> {
+    /// Points the address table entry at `index` to the device with
+    /// `eui64`. The NCP fills in its node ID if it knows it.
+    pub fn set_address_table_remote_eui64(&mut self, index: u8, eui64: EUI64) -> Result<(), Error> {
+        self.command(&SetAddressTableRemoteEui64 { address_table_index: index, eui64 })?.status.into_result()
+    }
+
+    /// Sets the node ID of the address table entry at `index`,
+    /// whose EUI64 must be set first.
+    pub fn set_address_table_remote_node_id(&mut self, index: u8, node_id: NodeId) -> Result<(), Error> {
+        self.command(&SetAddressTableRemoteNodeId { address_table_index: index, id: node_id })?;
+        Ok(())
+    }
+
+    /// The EUI64 of the address table entry at `index`.
+    pub fn address_table_remote_eui64(&mut self, index: u8) -> Result<EUI64, Error> {
+        let response = self.command(&GetAddressTableRemoteEui64 { address_table_index: index })?;
+        response.status.into_result()?;
+        Ok(response.eui64)
+    }
+
+    /// The node ID of the address table entry at `index`, which is
+    /// [`UNKNOWN_NODE_ID`](crate::ember::types::UNKNOWN_NODE_ID) until the stack
+    /// discovers it and [`TABLE_ENTRY_UNUSED_NODE_ID`](crate::ember::types::TABLE_ENTRY_UNUSED_NODE_ID)
+    /// for unused entries.
+    pub fn address_table_remote_node_id(&mut self, index: u8) -> Result<NodeId, Error> {
+        Ok(self.command(&GetAddressTableRemoteNodeId { address_table_index: index })?.node_id)
+    }
+
+    /// Asks the NCP for the node ID of the device with `eui64`,
+    /// recording it in the address cache if found.
+    pub fn lookup_node_id(&mut self, eui64: &EUI64) -> Result<Option<NodeId>, Error> {
+        let node_id = self.command(&LookupNodeIdByEui64 { eui64: *eui64 })?.node_id;
+        if node_id == NULL_NODE_ID {
+            return Ok(None);
+        }
+        self.addresses.insert(node_id, *eui64);
+        Ok(Some(node_id))
+    }
+
+    /// Asks the NCP for the EUI64 of the device using `node_id`,
+    /// recording it in the address cache if found.
+    pub fn lookup_eui64(&mut self, node_id: NodeId) -> Result<Option<EUI64>, Error> {
+        let response = self.command(&LookupEui64ByNodeId { node_id })?;
+        match response.status {
+            Recognized::Known(EmberStatus::ErrFatal) => Ok(None),
+            status => {
+                status.into_result()?;
+                self.addresses.insert(node_id, response.eui64);
+                Ok(Some(response.eui64))
+            }
+        }
+    }
+
+    /// The node ID of the device with `eui64`, from the address
+    /// cache or, if it is not cached, from the NCP.
+    pub fn resolve_node_id(&mut self, eui64: &EUI64) -> Result<Option<NodeId>, Error> {
+        match self.addresses.node_id(eui64) {
+            Some(node_id) => Ok(Some(node_id)),
+            None => self.lookup_node_id(eui64),
+        }
+    }
+
+    /// The EUI64 of the device using `node_id`, from the address
+    /// cache or, if it is not cached, from the NCP.
+    pub fn resolve_eui64(&mut self, node_id: NodeId) -> Result<Option<EUI64>, Error> {
+        match self.addresses.eui64(node_id) {
+            Some(eui64) => Ok(Some(eui64)),
+            None => self.lookup_eui64(node_id),
+        }
+    }
+
+    /// The node IDs and EUI64s learned from joins, device announcements,
+    /// address responses, route records and lookups.
+    pub fn addresses(&self) -> &AddressCache {
+        &self.addresses
+    }
+
+    /// The address cache, e.g. to add devices known from elsewhere.
+    pub fn addresses_mut(&mut self) -> &mut AddressCache {
+        &mut self.addresses
+    }
+
+    /// Updates the address cache from callbacks that reveal or
+    /// invalidate which node ID a device uses.
+    pub(super) fn track_addresses(&mut self, callback: &Callback) {
+        if let Some(Ok(event)) = JoinEvent::from_callback(callback) {
+            match event {
+                JoinEvent::TrustCenter { update: Recognized::Known(DeviceUpdate::DeviceLeft), eui64, .. } => {
+                    self.addresses.remove_eui64(&eui64);
+                }
+                JoinEvent::Child { joining: false, .. } => {}
+                event => {
+                    self.addresses.insert(event.node_id(), event.eui64());
+                }
+            }
+        } else if let Some(Ok(conflict)) = callback.parse::<IdConflictHandler>() {
+            self.addresses.remove_node_id(conflict.id);
+        } else if let Some(Ok(record)) = callback.parse::<IncomingRouteRecordHandler>() {
+            self.addresses.insert(record.source, record.source_eui);
+        } else if let Some(Ok(message)) = IncomingMessage::from_callback(callback)
+            && let Some((node_id, eui64)) = announced_address(&message)
+        {
+            self.addresses.insert(node_id, eui64);
+        }
+    }
+}
+
+/// The node ID and EUI64 carried by a ZDO device announcement
+/// or a successful network or IEEE address response.
+fn announced_address(message: &IncomingMessage) -> Option<(NodeId, EUI64)> {
+    if message.aps_frame.profile_id != ZDO_PROFILE {
+        return None;
+    }
+
+    let payload = message.payload.as_slice();
+    let (node_id, eui64) = match message.aps_frame.cluster_id {
+        DEVICE_ANNOUNCE => (payload.get(1..3)?, payload.get(3..11)?),
+        NWK_ADDRESS_RESPONSE | IEEE_ADDRESS_RESPONSE if payload.get(1) == Some(&0) => {
+            (payload.get(10..12)?, payload.get(2..10)?)
+        }
+        _ => return None,
+    };
+    Some((NodeId::from_le_bytes(node_id.try_into().ok()?), eui64.try_into().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::tests::connect;
+    use crate::connection::{Destination, OutgoingMessage};
+    use crate::ember::types::{
+        ApsFrame, ApsOption, IncomingMessageType, NetworkStatus, NodeType, TABLE_ENTRY_UNUSED_NODE_ID,
+        UNKNOWN_NODE_ID,
+    };
+    use crate::frames::{CallbackFrame, IncomingMessageHandler};
+    use std::time::Duration;
+
+    fn device_announce(node_id: NodeId, eui64: EUI64) -> IncomingMessageHandler {
+        IncomingMessageHandler {
+            message_type: IncomingMessageType::Broadcast.into(),
+            aps_frame: ApsFrame {
+                profile_id: ZDO_PROFILE,
+                cluster_id: DEVICE_ANNOUNCE,
+                source_endpoint: 0,
+                destination_endpoint: 0,
+                options: ApsOption::none(),
+                group_id: 0,
+                sequence: 1,
+            },
+            last_hop_lqi: 0xFF,
+            last_hop_rssi: -40,
+            sender: node_id,
+            binding_index: 0xFF,
+            address_index: 0xFF,
+            message_contents: [&[0x81][..], &node_id.to_le_bytes(), &eui64, &[0x8E]].concat(),
+        }
+    }
+
+    #[test]
+    fn address_table () {
+        let mut connection = connect();
+        assert_eq!(connection.address_table_remote_node_id(0), Ok(TABLE_ENTRY_UNUSED_NODE_ID));
+        assert_eq!(
+            connection.set_address_table_remote_eui64(8, [1; 8]),
+            Err(Error::Ember(EmberStatus::AddressTableIndexOutOfRange))
+        );
+
+        connection.set_address_table_remote_eui64(2, [1; 8]).unwrap();
+        assert_eq!(connection.address_table_remote_node_id(2), Ok(UNKNOWN_NODE_ID));
+        connection.set_address_table_remote_node_id(2, 0x1001).unwrap();
+        assert_eq!(connection.address_table_remote_eui64(2), Ok([1; 8]));
+        assert_eq!(connection.address_table_remote_node_id(2), Ok(0x1001));
+
+        assert!(connection.addresses().is_empty());
+        assert_eq!(connection.lookup_node_id(&[1; 8]), Ok(Some(0x1001)));
+        assert_eq!(connection.lookup_eui64(0x1001), Ok(Some([1; 8])));
+        assert_eq!(connection.lookup_eui64(0x2002), Ok(None));
+        assert_eq!(connection.addresses().node_id(&[1; 8]), Some(0x1001));
+    }
+
+    #[test]
+    fn cache_follows_callbacks () {
+        let mut connection = connect();
+        let eui64 = [8, 7, 6, 5, 4, 3, 2, 1];
+        let ncp = connection.ash_mut().stream_mut();
+        ncp.set_network_status(NetworkStatus::JoinedNetwork);
+        connection.permit_joining(60, false).unwrap();
+        connection.ash_mut().stream_mut().join_device(0x4A1B, eui64, NodeType::EndDevice);
+        while connection.poll_callback().unwrap().is_some() {}
+        assert_eq!(connection.addresses().node_id(&eui64), Some(0x4A1B));
+
+        // The device rejoined elsewhere and announced its new node ID.
+        let announce = device_announce(0x5C2D, eui64);
+        connection.ash_mut().stream_mut().emit_callback_with(IncomingMessageHandler::frame_id(), &announce);
+        connection.poll_callback().unwrap();
+        assert_eq!(connection.resolve_node_id(&eui64), Ok(Some(0x5C2D)));
+        assert_eq!(connection.addresses().eui64(0x4A1B), None);
+
+        let message = OutgoingMessage::new(0x0104, 0x0006, [0x01]);
+        let sent = connection.send(Destination::Eui64(eui64), &message).unwrap();
+        assert_eq!(connection.wait_message_sent(&sent, Duration::from_millis(100)), Ok(()));
+
+        let conflict = IdConflictHandler { id: 0x5C2D };
+        connection.ash_mut().stream_mut().emit_callback_with(IdConflictHandler::frame_id(), &conflict);
+        connection.poll_callback().unwrap();
+        assert!(connection.addresses().is_empty());
+        assert_eq!(connection.send(Destination::Eui64([9; 8]), &message), Err(Error::UnknownEui64([9; 8])));
+    }
+
+    #[test]
+    fn mappings_stay_one_to_one () {
+        let mut cache = AddressCache::new();
+        assert_eq!(cache.insert(0x1001, [1; 8]), None);
+        assert_eq!(cache.insert(0x1001, [1; 8]), None);
+        assert_eq!(cache.insert(0x2002, [2; 8]), None);
+
+        assert_eq!(cache.insert(0x3003, [1; 8]), Some(0x1001));
+        assert_eq!((cache.node_id(&[1; 8]), cache.eui64(0x1001)), (Some(0x3003), None));
+
+        cache.insert(0x2002, [4; 8]);
+        assert_eq!((cache.node_id(&[2; 8]), cache.eui64(0x2002)), (None, Some([4; 8])));
+        assert_eq!(cache.len(), 2);
+
+        assert_eq!(cache.remove_node_id(0x3003), Some([1; 8]));
+        assert_eq!(cache.remove_eui64(&[4; 8]), Some(0x2002));
+        assert!(cache.is_empty());
+    }
+}
@@ -0,0 +1,231 @@
+//! Reading and writing NCP configuration values.
+
+use crate::ash::ByteStream;
+use crate::error::{Category, Error};
+use crate::ezsp::types::{ConfigID, Status};
+use crate::frames::{GetConfigurationValue, SetConfigurationValue};
+
+use super::Connection;
+
+impl<S: ByteStream> Connection<S> {
+    /// Reads a configuration value from the NCP.
+    pub fn get_configuration_value(&mut self, config_id: ConfigID) -> Result<u16, Error> {
+        self.check_configuration(config_id)?;
+
+        let response = self.command(&GetConfigurationValue { config_id })?;
+        response.status.into_result()?;
+        Ok(response.value)
+    }
+
+    /// Writes a configuration value to the NCP.
+    ///
+    /// Values can only be written before the network is brought up.
+    pub fn set_configuration_value(&mut self, config_id: ConfigID, value: u16) -> Result<(), Error> {
+        self.check_configuration(config_id)?;
+
+        self.command(&SetConfigurationValue { config_id, value })?.status.into_result()
+    }
+
+    fn check_configuration(&self, config_id: ConfigID) -> Result<(), Error> {
+        match self.capabilities() {
+            Some(capabilities) if !capabilities.supports_configuration(config_id) => {
+                Err(Error::UnsupportedConfiguration { config_id, protocol_version: capabilities.protocol_version() })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// A configuration value the NCP refused to set.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Rejected {
+    /// The configuration value.
+    pub config_id: ConfigID,
+
+    /// The value that was requested.
+    pub value: u16,
+
+    /// Why the NCP refused it.
+    pub error: Error,
+}
+
+/// The outcome of applying an [`NcpConfig`].
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct ConfigReport {
+    /// The values that were set, in the order they were applied.
+    pub applied: Vec<(ConfigID, u16)>,
+
+    /// The values the NCP refused to set.
+    pub rejected: Vec<Rejected>,
+
+    /// The values the negotiated protocol version does not have,
+    /// which were skipped without being sent.
+    pub unsupported: Vec<(ConfigID, u16)>,
+}
+
+impl ConfigReport {
+    /// Whether every value was set.
+    pub fn is_complete(&self) -> bool {
+        self.rejected.is_empty() && self.unsupported.is_empty()
+    }
+}
+
+/// A profile of configuration values applied to the NCP at startup.
+///
+/// Values are applied in the order they were added, except
+/// that the packet buffer count is always applied last, as
+/// the NCP allocates it from the memory left over by every
+/// other table.
+///
+/// ```ignore
+/// let report = NcpConfig::new()
+///     .set(ConfigID::StackProfile, 2)
+///     .set(ConfigID::SecurityLevel, 5)
+///     .set(ConfigID::AddressTableSize, 16)
+///     .apply(&mut connection)?;
+/// ```
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct NcpConfig {
+    values: Vec<(ConfigID, u16)>,
+}
+
+impl NcpConfig {
+    /// Creates an empty profile.
+    pub fn new() -> Self {
+        NcpConfig::default()
+    }
+
+    /// Adds a value to the profile, replacing any earlier value for `config_id`.
+    pub fn set(mut self, config_id: ConfigID, value: u16) -> Self {
+        match self.values.iter_mut().find(|(id, _)| *id == config_id) {
+            Some(entry) => entry.1 = value,
+            None => self.values.push((config_id, value)),
+        }
+        self
+    }
+
+    /// The value the profile sets for `config_id`.
+    pub fn get(&self, config_id: ConfigID) -> Option<u16> {
+        self.values.iter().find(|(id, _)| *id == config_id).map(|(_, value)| *value)
+    }
+
+    /// The values in the order they will be applied.
+    pub fn ordered(&self) -> impl Iterator<Item = (ConfigID, u16)> + '_ {
+        let (last, first): (Vec<_>, Vec<_>) = self
+            .values
+            .iter()
+            .partition(|(id, _)| *id == ConfigID::PacketBuffercount);
+
+        first.into_iter().chain(last).copied()
+    }
+
+    /// Writes every value to the NCP.
+    ///
+    /// Values the NCP refuses, or that the negotiated protocol version
+    /// does not have, are reported rather than stopping the profile.
+    /// Failures of the connection itself are returned as an error,
+    /// as no further values could be applied.
+    pub fn apply<S: ByteStream>(&self, connection: &mut Connection<S>) -> Result<ConfigReport, Error> {
+        let mut report = ConfigReport::default();
+
+        for (config_id, value) in self.ordered() {
+            match connection.set_configuration_value(config_id, value) {
+                Ok(()) => report.applied.push((config_id, value)),
+                Err(Error::UnsupportedConfiguration { .. }) => report.unsupported.push((config_id, value)),
+                Err(error) if is_rejection(&error) => {
+                    report.rejected.push(Rejected { config_id, value, error })
+                }
+                Err(error) => return Err(error),
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+/// Whether the error is the NCP refusing a value, rather than the connection failing.
+fn is_rejection(error: &Error) -> bool {
+    match error {
+        Error::Ezsp(status) => status.category() != Category::Ash
+            && !matches!(status, Status::ErrorNoResponse | Status::ErrorVersionNotSet),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::tests::connect_to;
+    use crate::simulator::SimulatedNcp;
+
+    #[test]
+    fn get_and_set () {
+        let mut connection = connect_to(SimulatedNcp::new());
+
+        assert_eq!(connection.get_configuration_value(ConfigID::AddressTableSize), Ok(8));
+        assert_eq!(connection.set_configuration_value(ConfigID::AddressTableSize, 12), Ok(()));
+        assert_eq!(connection.get_configuration_value(ConfigID::AddressTableSize), Ok(12));
+
+        assert_eq!(
+            connection.get_configuration_value(ConfigID::ZLLGroupAddresses),
+            Err(Error::Ezsp(Status::ErrorInvalidID))
+        );
+    }
+
+    #[test]
+    fn profile_order () {
+        let config = NcpConfig::new()
+            .set(ConfigID::PacketBuffercount, 255)
+            .set(ConfigID::StackProfile, 2)
+            .set(ConfigID::MaxHops, 30)
+            .set(ConfigID::StackProfile, 1);
+
+        assert_eq!(config.get(ConfigID::StackProfile), Some(1));
+        assert_eq!(config.ordered().collect::<Vec<_>>(), [
+            (ConfigID::StackProfile, 1),
+            (ConfigID::MaxHops, 30),
+            (ConfigID::PacketBuffercount, 255),
+        ]);
+    }
+
+    #[test]
+    fn apply_reports_rejections () {
+        let mut ncp = SimulatedNcp::new();
+        ncp.set_configuration(ConfigID::GPSinkTableSize, 2);
+        ncp.reject_configuration(ConfigID::GPSinkTableSize, Status::ErrorInvalidCall);
+        let mut connection = connect_to(ncp);
+
+        let report = NcpConfig::new()
+            .set(ConfigID::GPSinkTableSize, 4)
+            .set(ConfigID::NeighborTableSize, 24)
+            .set(ConfigID::ZLLGroupAddresses, 1)
+            .apply(&mut connection)
+            .unwrap();
+
+        assert!(!report.is_complete());
+        assert_eq!(report.applied, [(ConfigID::NeighborTableSize, 24)]);
+        assert_eq!(report.rejected, [
+            Rejected { config_id: ConfigID::GPSinkTableSize, value: 4, error: Error::Ezsp(Status::ErrorInvalidCall) },
+            Rejected { config_id: ConfigID::ZLLGroupAddresses, value: 1, error: Error::Ezsp(Status::ErrorInvalidID) },
+        ]);
+        assert_eq!(connection.ash().stream().configuration(ConfigID::NeighborTableSize), Some(24));
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn apply_skips_unsupported_values () {
+        let mut connection = connect_to(SimulatedNcp::new());
+        assert_eq!(
+            connection.get_configuration_value(ConfigID::TransientKeyTimeoutS),
+            Err(Error::UnsupportedConfiguration { config_id: ConfigID::TransientKeyTimeoutS, protocol_version: 12 })
+        );
+
+        let sent = connection.ash().stream().received().len();
+        let report = NcpConfig::new().set(ConfigID::TransientKeyTimeoutS, 300).apply(&mut connection).unwrap();
+
+        assert!(!report.is_complete());
+        assert!(report.rejected.is_empty());
+        assert_eq!(report.unsupported, [(ConfigID::TransientKeyTimeoutS, 300)]);
+        assert_eq!(connection.ash().stream().received().len(), sent);
+    }
+}
@@ -0,0 +1,110 @@
+//! Reading the NCP's stack counters.
+
+use std::time::{Duration, Instant};
+
+use crate::ash::ByteStream;
+use crate::ember::types::CounterType;
+use crate::error::Error;
+use crate::ezsp::counters::Counters;
+use crate::frames::{ReadAndClearCounters, ReadCounters};
+
+use super::Connection;
+
+/// Counters read from the NCP and when they were read.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct CounterSnapshot {
+    /// The counter values.
+    pub counters: Counters,
+
+    /// When the counters were read.
+    pub taken_at: Instant,
+}
+
+impl CounterSnapshot {
+    /// How much the counters grew between `earlier` and this snapshot.
+    pub fn delta(&self, earlier: &CounterSnapshot) -> CounterDelta {
+        CounterDelta {
+            counters: self.counters.since(&earlier.counters),
+            elapsed: self.taken_at.saturating_duration_since(earlier.taken_at),
+        }
+    }
+}
+
+/// How much the counters grew over a period of time.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct CounterDelta {
+    /// The growth of each counter.
+    pub counters: Counters,
+
+    /// The length of the period.
+    pub elapsed: Duration,
+}
+
+impl CounterDelta {
+    /// How much a counter grew.
+    pub fn get(&self, counter: CounterType) -> u16 {
+        self.counters.get(counter)
+    }
+
+    /// The average rate a counter grew at, per second.
+    pub fn per_second(&self, counter: CounterType) -> f64 {
+        self.counters.per_second(counter, self.elapsed)
+    }
+}
+
+impl<S: ByteStream> Connection<S> {
+    /// Reads the counters without clearing them.
+    pub fn read_counters(&mut self) -> Result<CounterSnapshot, Error> {
+        let values = self.command(&ReadCounters {})?.values;
+        Ok(CounterSnapshot { counters: Counters::new(values), taken_at: Instant::now() })
+    }
+
+    /// Reads and clears the counters, returning how much they grew
+    /// since they were last cleared or the NCP was reset by
+    /// [`connect`](Connection::connect).
+    ///
+    /// The elapsed time is zero if neither happened on this connection.
+    pub fn read_and_clear_counters(&mut self) -> Result<CounterDelta, Error> {
+        let values = self.command(&ReadAndClearCounters {})?.values;
+        let now = Instant::now();
+        let elapsed = self.counters_cleared.map_or(Duration::ZERO, |cleared| now.saturating_duration_since(cleared));
+        self.counters_cleared = Some(now);
+
+        Ok(CounterDelta { counters: Counters::new(values), elapsed })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::tests::connect;
+
+    #[test]
+    fn snapshots_and_deltas () {
+        let mut connection = connect();
+        connection.ash_mut().stream_mut().set_counter(CounterType::MACTXUnicastRetry, 5);
+        let first = connection.read_counters().unwrap();
+        assert_eq!(first.counters[CounterType::MACTXUnicastRetry], 5);
+
+        connection.ash_mut().stream_mut().set_counter(CounterType::MACTXUnicastRetry, 8);
+        connection.ash_mut().stream_mut().set_counter(CounterType::APSDataTXUnicastFailed, 1);
+        let second = connection.read_counters().unwrap();
+        let delta = second.delta(&first);
+        assert_eq!(delta.get(CounterType::MACTXUnicastRetry), 3);
+        assert_eq!(delta.get(CounterType::APSDataTXUnicastFailed), 1);
+        assert_eq!(delta.elapsed, second.taken_at - first.taken_at);
+    }
+
+    #[test]
+    fn read_and_clear () {
+        let mut connection = connect();
+        connection.ash_mut().stream_mut().set_counter(CounterType::MACTXUnicastRetry, 5);
+
+        let delta = connection.read_and_clear_counters().unwrap();
+        assert_eq!(delta.get(CounterType::MACTXUnicastRetry), 5);
+        assert!(delta.elapsed > Duration::ZERO);
+        assert!(delta.per_second(CounterType::MACTXUnicastRetry) > 0.0);
+        assert_eq!(connection.ash().stream().counter(CounterType::MACTXUnicastRetry), 0);
+        assert_eq!(connection.read_counters().unwrap().counters, Counters::default());
+    }
+}
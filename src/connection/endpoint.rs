@@ -0,0 +1,106 @@
+//! Registering application endpoints on the NCP.
+
+use crate::ash::ByteStream;
+use crate::error::Error;
+use crate::ezsp::endpoint::Endpoint;
+use crate::ezsp::types::{EndpointFlags, ValueID};
+use crate::frames::SetValue;
+
+use super::Connection;
+
+impl<S: ByteStream> Connection<S> {
+    /// Registers an endpoint on the NCP.
+    ///
+    /// Endpoints must be added before the network is brought up, and are
+    /// forgotten when the NCP resets. Adding an endpoint ID twice fails
+    /// with [`Error::DuplicateEndpoint`] without sending anything.
+    pub fn add_endpoint(&mut self, endpoint: Endpoint) -> Result<(), Error> {
+        if self.endpoint(endpoint.id()).is_some() {
+            return Err(Error::DuplicateEndpoint(endpoint.id()));
+        }
+
+        self.command(&endpoint.to_frame())?.status.into_result()?;
+        self.endpoints.push(endpoint);
+        Ok(())
+    }
+
+    /// The endpoints registered since the NCP was last reset.
+    pub fn endpoints(&self) -> &[Endpoint] {
+        &self.endpoints
+    }
+
+    /// The registered endpoint with the ID.
+    pub fn endpoint(&self, id: u8) -> Option<&Endpoint> {
+        self.endpoints.iter().find(|endpoint| endpoint.id() == id)
+    }
+
+    /// Enables or disables a registered endpoint.
+    ///
+    /// Disabled endpoints are not discoverable via ZDO. The current
+    /// flags can be read with [`endpoint_flags`](Connection::endpoint_flags).
+    /// Fails with [`Error::InvalidEndpoint`] without sending anything if
+    /// no endpoint with the ID is registered.
+    pub fn set_endpoint_flags(&mut self, id: u8, flags: EndpointFlags) -> Result<(), Error> {
+        if self.endpoint(id).is_none() {
+            return Err(Error::InvalidEndpoint(id));
+        }
+
+        let [low, high] = (flags as u16).to_le_bytes();
+        let value = vec![id, low, high];
+        self.command(&SetValue { value_id: ValueID::EndpointFlags, value })?.status.into_result()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::tests::connect;
+    use crate::ember::types::NetworkStatus;
+    use crate::ezsp::types::Status;
+
+    fn home_automation(id: u8) -> Endpoint {
+        Endpoint::builder(id, 0x0104, 0x0005).input_clusters(&[0x0000, 0x0006]).output_cluster(0x0019).build().unwrap()
+    }
+
+    #[test]
+    fn endpoints_are_tracked () {
+        let mut connection = connect();
+
+        connection.add_endpoint(home_automation(1)).unwrap();
+        assert_eq!(connection.endpoints(), [home_automation(1)]);
+        assert_eq!(
+            connection.ash().stream().endpoint(1),
+            Some(&[1, 0x04, 0x01, 0x05, 0x00, 0, 2, 1, 0x00, 0x00, 0x06, 0x00, 0x19, 0x00][..])
+        );
+
+        let sent = connection.ash().stream().received().len();
+        assert_eq!(connection.add_endpoint(home_automation(1)), Err(Error::DuplicateEndpoint(1)));
+        assert_eq!(connection.ash().stream().received().len(), sent);
+
+        connection.connect().unwrap();
+        assert!(connection.endpoints().is_empty());
+    }
+
+    #[test]
+    fn endpoints_cannot_be_added_once_network_is_up () {
+        let mut connection = connect();
+        connection.ash_mut().stream_mut().set_network_status(NetworkStatus::JoinedNetwork);
+
+        assert_eq!(connection.add_endpoint(home_automation(1)), Err(Error::Ezsp(Status::ErrorInvalidCall)));
+        assert!(connection.endpoints().is_empty());
+    }
+
+    #[test]
+    fn flags () {
+        let mut connection = connect();
+        connection.add_endpoint(home_automation(1)).unwrap();
+        assert_eq!(connection.endpoint_flags(1), Ok(EndpointFlags::Enabled));
+
+        connection.set_endpoint_flags(1, EndpointFlags::Disabled).unwrap();
+        assert_eq!(connection.endpoint_flags(1), Ok(EndpointFlags::Disabled));
+        assert_eq!(
+            connection.set_endpoint_flags(2, EndpointFlags::Disabled),
+            Err(Error::InvalidEndpoint(2))
+        );
+    }
+}
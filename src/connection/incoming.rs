@@ -0,0 +1,287 @@
+//! Receiving APS messages through filtered subscriptions.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use crate::ash::ByteStream;
+use crate::codec::Recognized;
+use crate::ember::types::{ApsFrame, IncomingMessageType, NodeId};
+use crate::error::Error;
+use crate::frames::IncomingMessageHandler;
+
+use super::{Callback, Connection};
+
+/// The binding or address index reported when none matches.
+const NO_INDEX: u8 = 0xFF;
+
+/// A message received from the network, from incomingMessageHandler.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct IncomingMessage {
+    /// How the message was addressed.
+    pub message_type: Recognized<IncomingMessageType>,
+
+    /// The APS header of the message.
+    pub aps_frame: ApsFrame,
+
+    /// The link quality from the node that last relayed the message.
+    pub last_hop_lqi: u8,
+
+    /// The RSSI in dBm of the last hop.
+    pub last_hop_rssi: i8,
+
+    /// The node that sent the message.
+    pub sender: NodeId,
+
+    /// The binding table entry matching the message, if any.
+    pub binding_index: Option<u8>,
+
+    /// The address table entry of the sender, if any.
+    pub address_index: Option<u8>,
+
+    /// The APS payload.
+    pub payload: Vec<u8>,
+}
+
+impl IncomingMessage {
+    /// Decodes an incomingMessageHandler callback,
+    /// or returns `None` for any other callback.
+    pub fn from_callback(callback: &Callback) -> Option<Result<IncomingMessage, Error>> {
+        let message = callback.parse::<IncomingMessageHandler>()?;
+        Some(message.map(|message| IncomingMessage {
+            message_type: message.message_type,
+            aps_frame: message.aps_frame,
+            last_hop_lqi: message.last_hop_lqi,
+            last_hop_rssi: message.last_hop_rssi,
+            sender: message.sender,
+            binding_index: (message.binding_index != NO_INDEX).then_some(message.binding_index),
+            address_index: (message.address_index != NO_INDEX).then_some(message.address_index),
+            payload: message.message_contents,
+        }))
+    }
+}
+
+/// Selects the messages a subscription receives.
+///
+/// The default filter accepts every message.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Default)]
+pub struct MessageFilter {
+    /// The application profile to accept, or any.
+    pub profile_id: Option<u16>,
+
+    /// The cluster to accept, or any.
+    pub cluster_id: Option<u16>,
+
+    /// The local endpoint to accept messages for, or any.
+    pub endpoint: Option<u8>,
+}
+
+impl MessageFilter {
+    /// A filter accepting every message.
+    pub fn new() -> Self {
+        MessageFilter::default()
+    }
+
+    /// Accepts messages of `profile_id` only.
+    pub fn profile(mut self, profile_id: u16) -> Self {
+        self.profile_id = Some(profile_id);
+        self
+    }
+
+    /// Accepts messages for `cluster_id` only.
+    pub fn cluster(mut self, cluster_id: u16) -> Self {
+        self.cluster_id = Some(cluster_id);
+        self
+    }
+
+    /// Accepts messages addressed to `endpoint` only.
+    pub fn endpoint(mut self, endpoint: u8) -> Self {
+        self.endpoint = Some(endpoint);
+        self
+    }
+
+    /// Whether the filter accepts `message`.
+    pub fn matches(&self, message: &IncomingMessage) -> bool {
+        let aps_frame = &message.aps_frame;
+        self.profile_id.is_none_or(|profile_id| profile_id == aps_frame.profile_id)
+            && self.cluster_id.is_none_or(|cluster_id| cluster_id == aps_frame.cluster_id)
+            && self.endpoint.is_none_or(|endpoint| endpoint == aps_frame.destination_endpoint)
+    }
+}
+
+/// The number of messages queued for a subscription
+/// before the oldest are dropped.
+pub const MAX_QUEUED_MESSAGES: usize = 64;
+
+/// A handle to the messages received for one filter.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Subscription(usize);
+
+/// The messages queued for a subscription.
+#[derive(Debug)]
+pub(super) struct Subscriber {
+    subscription: Subscription,
+    filter: MessageFilter,
+    messages: VecDeque<IncomingMessage>,
+}
+
+impl<S: ByteStream> Connection<S> {
+    /// Starts queueing the messages accepted by `filter`.
+    ///
+    /// Every subscription receives its own copy of each message it
+    /// accepts, from the time it is made until it is dropped with
+    /// [`unsubscribe`](Connection::unsubscribe). Messages taken by a
+    /// subscription no longer reach [`poll_callback`](Connection::poll_callback),
+    /// and past [`MAX_QUEUED_MESSAGES`] the oldest are dropped.
+    pub fn subscribe(&mut self, filter: MessageFilter) -> Subscription {
+        let subscription = Subscription(self.next_subscription);
+        self.next_subscription += 1;
+        self.subscribers.push(Subscriber { subscription, filter, messages: VecDeque::new() });
+        subscription
+    }
+
+    /// Stops queueing messages for `subscription`, dropping those not yet
+    /// taken. Returns whether the subscription existed.
+    pub fn unsubscribe(&mut self, subscription: Subscription) -> bool {
+        let count = self.subscribers.len();
+        self.subscribers.retain(|subscriber| subscriber.subscription != subscription);
+        self.subscribers.len() != count
+    }
+
+    /// Waits up to `timeout` for the next message of `subscription`.
+    ///
+    /// A zero timeout only takes messages already received. Returns
+    /// `None` if no message arrives in time or the subscription was
+    /// dropped.
+    pub fn next_message(
+        &mut self,
+        subscription: Subscription,
+        timeout: Duration,
+    ) -> Result<Option<IncomingMessage>, Error> {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            let Some(subscriber) = self.subscribers.iter_mut().find(|subscriber| subscriber.subscription == subscription)
+            else {
+                return Ok(None);
+            };
+            if let Some(message) = subscriber.messages.pop_front() {
+                return Ok(Some(message));
+            }
+
+            let filter = subscriber.filter;
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let accepted = self.receive_callback(remaining, |callback| {
+                matches!(IncomingMessage::from_callback(callback), Some(Ok(message)) if filter.matches(&message))
+            })?;
+            if accepted.is_none() {
+                return Ok(None);
+            }
+        }
+    }
+
+    /// Queues the message of an incomingMessageHandler callback
+    /// for every subscription accepting it, returning whether any did.
+    pub(super) fn dispatch_message(&mut self, callback: &Callback) -> bool {
+        let Some(Ok(message)) = IncomingMessage::from_callback(callback) else {
+            return false;
+        };
+
+        let mut taken = false;
+        for subscriber in &mut self.subscribers {
+            if subscriber.filter.matches(&message) {
+                if subscriber.messages.len() == MAX_QUEUED_MESSAGES {
+                    subscriber.messages.pop_front();
+                }
+                subscriber.messages.push_back(message.clone());
+                taken = true;
+            }
+        }
+        taken
+    }
+
+    /// Drops the messages queued for every subscription.
+    pub(super) fn clear_subscriptions(&mut self) {
+        for subscriber in &mut self.subscribers {
+            subscriber.messages.clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::tests::connect;
+    use crate::ember::types::ApsOption;
+    use crate::frames::CallbackFrame;
+    use crate::simulator::SimulatedNcp;
+
+    fn incoming(cluster_id: u16, destination_endpoint: u8, payload: &[u8]) -> IncomingMessageHandler {
+        IncomingMessageHandler {
+            message_type: IncomingMessageType::Unicast.into(),
+            aps_frame: ApsFrame {
+                profile_id: 0x0104,
+                cluster_id,
+                source_endpoint: 1,
+                destination_endpoint,
+                options: ApsOption::none(),
+                group_id: 0,
+                sequence: 7,
+            },
+            last_hop_lqi: 0xE0,
+            last_hop_rssi: -52,
+            sender: 0x1234,
+            binding_index: NO_INDEX,
+            address_index: 2,
+            message_contents: payload.to_vec(),
+        }
+    }
+
+    #[test]
+    fn subscriptions_receive_matching_messages () {
+        let mut connection = connect();
+        let everything = connection.subscribe(MessageFilter::new());
+        let on_off = connection.subscribe(MessageFilter::new().profile(0x0104).cluster(0x0006));
+        let endpoint_2 = connection.subscribe(MessageFilter::new().endpoint(2));
+
+        let ncp = connection.ash_mut().stream_mut();
+        ncp.emit_callback_with(IncomingMessageHandler::frame_id(), &incoming(0x0006, 1, &[1]));
+        ncp.emit_callback_with(IncomingMessageHandler::frame_id(), &incoming(0x0008, 2, &[2]));
+        ncp.emit_callback_with(IncomingMessageHandler::frame_id(), &incoming(0x0006, 2, &[3]));
+
+        let message = connection.next_message(on_off, Duration::from_millis(50)).unwrap().unwrap();
+        assert_eq!((message.sender, message.last_hop_rssi, message.payload.as_slice()), (0x1234, -52, &[1][..]));
+        assert_eq!((message.binding_index, message.address_index), (None, Some(2)));
+        assert_eq!(connection.next_message(on_off, Duration::ZERO).unwrap().unwrap().payload, [3]);
+        assert_eq!(connection.next_message(on_off, Duration::from_millis(20)), Ok(None));
+
+        let payloads = |connection: &mut Connection<SimulatedNcp>, subscription| {
+            std::iter::from_fn(|| connection.next_message(subscription, Duration::ZERO).unwrap())
+                .map(|message| message.payload[0])
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(payloads(&mut connection, endpoint_2), [2, 3]);
+        assert_eq!(payloads(&mut connection, everything), [1, 2, 3]);
+
+        assert!(connection.unsubscribe(everything));
+        assert!(!connection.unsubscribe(everything));
+        assert_eq!(connection.next_message(everything, Duration::ZERO), Ok(None));
+    }
+
+    #[test]
+    fn taken_messages_leave_the_callback_queue () {
+        let mut connection = connect();
+        let on_off = connection.subscribe(MessageFilter::new().cluster(0x0006));
+
+        let ncp = connection.ash_mut().stream_mut();
+        for payload in 0..=MAX_QUEUED_MESSAGES as u8 {
+            ncp.emit_callback_with(IncomingMessageHandler::frame_id(), &incoming(0x0006, 1, &[payload]));
+        }
+        ncp.emit_callback_with(IncomingMessageHandler::frame_id(), &incoming(0x0008, 1, &[0xFF]));
+
+        let callback = connection.poll_callback().unwrap().unwrap();
+        assert_eq!(IncomingMessage::from_callback(&callback).unwrap().unwrap().payload, [0xFF]);
+        assert_eq!(connection.poll_callback(), Ok(None));
+
+        assert_eq!(connection.next_message(on_off, Duration::ZERO).unwrap().unwrap().payload, [1]);
+    }
+}
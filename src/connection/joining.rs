@@ -0,0 +1,237 @@
+//! Permitting joins and following the devices that join.
+
+use std::time::{Duration, Instant};
+
+use crate::ash::ByteStream;
+use crate::codec::Recognized;
+use crate::ember::types::{
+    ApsFrame, ApsOption, BROADCAST_ADDRESS, DeviceUpdate, EUI64, JoinDecision, NodeId, NodeType,
+};
+use crate::error::Error;
+use crate::frames::{CallbackFrame, ChildJoinHandler, PermitJoining, SendBroadcast, TrustCenterJoinHandler};
+
+use super::{Callback, Connection};
+
+/// The ZDO profile and endpoint.
+pub(super) const ZDO_PROFILE: u16 = 0x0000;
+const ZDO_ENDPOINT: u8 = 0x00;
+
+/// Mgmt_Permit_Joining_req
+const MGMT_PERMIT_JOINING_REQUEST: u16 = 0x0036;
+
+/// A device joining or leaving, as reported by the NCP.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum JoinEvent {
+    /// A child of this node joined or left, from childJoinHandler.
+    Child {
+        /// The index of the child in the child table.
+        index: u8,
+
+        /// Whether the child joined rather than left.
+        joining: bool,
+
+        /// The node ID of the child.
+        node_id: NodeId,
+
+        /// The EUI64 of the child.
+        eui64: EUI64,
+
+        /// The node type of the child.
+        node_type: Recognized<NodeType>,
+    },
+
+    /// The trust center decided on a device joining, rejoining or
+    /// leaving anywhere in the network, from trustCenterJoinHandler.
+    TrustCenter {
+        /// The node ID of the device.
+        node_id: NodeId,
+
+        /// The EUI64 of the device.
+        eui64: EUI64,
+
+        /// What the device did.
+        update: Recognized<DeviceUpdate>,
+
+        /// How the trust center policy handled it.
+        decision: Recognized<JoinDecision>,
+
+        /// The node ID of the device's parent.
+        parent_id: NodeId,
+    },
+}
+
+impl JoinEvent {
+    /// Decodes a childJoinHandler or trustCenterJoinHandler callback,
+    /// or returns `None` for any other callback.
+    pub fn from_callback(callback: &Callback) -> Option<Result<JoinEvent, Error>> {
+        if let Some(child) = callback.parse::<ChildJoinHandler>() {
+            return Some(child.map(|child| JoinEvent::Child {
+                index: child.index,
+                joining: child.joining,
+                node_id: child.child_id,
+                eui64: child.child_eui64,
+                node_type: child.child_type,
+            }));
+        }
+
+        let join = callback.parse::<TrustCenterJoinHandler>()?;
+        Some(join.map(|join| JoinEvent::TrustCenter {
+            node_id: join.new_node_id,
+            eui64: join.new_node_eui64,
+            update: join.status,
+            decision: join.policy_decision,
+            parent_id: join.parent_of_new_node_id,
+        }))
+    }
+
+    /// The node ID of the device.
+    pub fn node_id(&self) -> NodeId {
+        match self {
+            JoinEvent::Child { node_id, .. } | JoinEvent::TrustCenter { node_id, .. } => *node_id,
+        }
+    }
+
+    /// The EUI64 of the device.
+    pub fn eui64(&self) -> EUI64 {
+        match self {
+            JoinEvent::Child { eui64, .. } | JoinEvent::TrustCenter { eui64, .. } => *eui64,
+        }
+    }
+}
+
+/// Join events received until a deadline.
+///
+/// Ends when the deadline passes, or after the first error.
+/// Other callbacks stay queued on the connection.
+#[derive(Debug)]
+pub struct JoinEvents<'a, S> {
+    connection: &'a mut Connection<S>,
+    deadline: Instant,
+    finished: bool,
+}
+
+impl<S: ByteStream> Iterator for JoinEvents<'_, S> {
+    type Item = Result<JoinEvent, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let timeout = self.deadline.saturating_duration_since(Instant::now());
+        let event = match self.connection.wait_for_callback(timeout, is_join_callback) {
+            Ok(Some(callback)) => JoinEvent::from_callback(&callback)?,
+            Ok(None) => return None,
+            Err(error) => Err(error),
+        };
+
+        self.finished = event.is_err();
+        Some(event)
+    }
+}
+
+impl<S: ByteStream> Connection<S> {
+    /// Permits devices to join through this node for `duration`
+    /// seconds, where 0 closes the network and 0xFF keeps it open.
+    ///
+    /// With `broadcast`, a Mgmt_Permit_Joining_req is also broadcast so
+    /// every router opens for the same duration. Zigbee 3.0 routers
+    /// treat a broadcast duration of 0xFF as 0xFE.
+    pub fn permit_joining(&mut self, duration: u8, broadcast: bool) -> Result<(), Error> {
+        self.command(&PermitJoining { duration })?.status.into_result()?;
+        if !broadcast {
+            return Ok(());
+        }
+
+        let sequence = self.zdo_sequence;
+        self.zdo_sequence = self.zdo_sequence.wrapping_add(1);
+        let request = SendBroadcast {
+            destination: BROADCAST_ADDRESS,
+            aps_frame: ApsFrame {
+                profile_id: ZDO_PROFILE,
+                cluster_id: MGMT_PERMIT_JOINING_REQUEST,
+                source_endpoint: ZDO_ENDPOINT,
+                destination_endpoint: ZDO_ENDPOINT,
+                options: ApsOption::none(),
+                group_id: 0,
+                sequence: 0,
+            },
+            radius: 0,
+            message_tag: self.next_message_tag(),
+            message_contents: vec![sequence, duration, 1],
+        };
+        self.command(&request)?.status.into_result()
+    }
+
+    /// Follows devices joining and leaving for `timeout`.
+    pub fn join_events(&mut self, timeout: Duration) -> JoinEvents<'_, S> {
+        JoinEvents { deadline: Instant::now() + timeout, connection: self, finished: false }
+    }
+}
+
+fn is_join_callback(callback: &Callback) -> bool {
+    [ChildJoinHandler::frame_id(), TrustCenterJoinHandler::frame_id()].contains(&callback.frame_id())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::tests::connect;
+    use crate::codec::EzspDecode;
+    use crate::ember::types::{NetworkStatus, Status as EmberStatus};
+    use crate::frames::Frame;
+
+    #[test]
+    fn permit_joining_broadcasts () {
+        let mut connection = connect();
+        assert_eq!(connection.permit_joining(60, false), Err(Error::Ember(EmberStatus::InvalidCall)));
+
+        connection.ash_mut().stream_mut().set_network_status(NetworkStatus::JoinedNetwork);
+        connection.permit_joining(60, true).unwrap();
+        connection.permit_joining(0, true).unwrap();
+        assert_eq!(connection.ash().stream().permit_joining(), 0);
+
+        let broadcasts: Vec<_> = connection
+            .ash()
+            .stream()
+            .received()
+            .iter()
+            .filter(|command| command.header.frame_id == SendBroadcast::frame_id())
+            .map(|command| SendBroadcast::decode_from_slice(&command.parameters).unwrap().0)
+            .collect();
+        assert_eq!(broadcasts.len(), 2);
+        assert_eq!(broadcasts[0].destination, BROADCAST_ADDRESS);
+        assert_eq!(broadcasts[0].aps_frame.cluster_id, MGMT_PERMIT_JOINING_REQUEST);
+        assert_eq!(broadcasts[0].message_contents, [0, 60, 1]);
+        assert_eq!(broadcasts[1].message_contents, [1, 0, 1]);
+    }
+
+    #[test]
+    fn join_events () {
+        let mut connection = connect();
+        let eui64 = [8, 7, 6, 5, 4, 3, 2, 1];
+        connection.ash_mut().stream_mut().set_network_status(NetworkStatus::JoinedNetwork);
+        connection.ash_mut().stream_mut().set_node_id(0x0000);
+        connection.ash_mut().stream_mut().join_device(0x1111, [1; 8], NodeType::Router);
+        assert_eq!(connection.join_events(Duration::from_millis(20)).count(), 0);
+
+        connection.permit_joining(0xFF, false).unwrap();
+        connection.ash_mut().stream_mut().join_device(0x4A1B, eui64, NodeType::SleepyEndDevice);
+
+        let events: Vec<_> = connection.join_events(Duration::from_millis(50)).collect::<Result<_, _>>().unwrap();
+        assert_eq!(events, [
+            JoinEvent::TrustCenter {
+                node_id: 0x4A1B,
+                eui64,
+                update: DeviceUpdate::StandardSecurityUnsecuredJoin.into(),
+                decision: JoinDecision::UsePreconfiguredKey.into(),
+                parent_id: 0x0000,
+            },
+            JoinEvent::Child { index: 0, joining: true, node_id: 0x4A1B, eui64, node_type: NodeType::SleepyEndDevice.into() },
+        ]);
+        assert!(events.iter().all(|event| event.node_id() == 0x4A1B && event.eui64() == eui64));
+
+        let opened = connection.poll_callback().unwrap().unwrap();
+        assert_eq!(opened.parameters, [EmberStatus::NetworkOpened as u8]);
+    }
+}
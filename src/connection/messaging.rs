@@ -0,0 +1,346 @@
+//! Sending APS messages and following their delivery.
+
+use std::time::{Duration, Instant};
+
+use crate::ash::ByteStream;
+use crate::ember::types::{
+    ApsFrame, ApsOption, ApsOptionFlags, BROADCAST_ADDRESS, EUI64, NodeId, OutgoingMessageType,
+    RX_ON_WHEN_IDLE_BROADCAST_ADDRESS, SLEEPY_BROADCAST_ADDRESS,
+};
+use crate::error::Error;
+use crate::frames::{MessageSentHandler, SendBroadcast, SendMulticast, SendReply, SendUnicast};
+
+use super::{Callback, Connection};
+
+/// The hops a multicast is relayed by nodes outside the group,
+/// which the stack treats as unlimited.
+const MULTICAST_NONMEMBER_RADIUS: u8 = 7;
+
+/// Where a message is sent.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Destination {
+    /// A unicast to a node ID.
+    Direct(NodeId),
+
+    /// A unicast to the node with an EUI64, whose node ID is taken from
+    /// the address cache or, failing that, looked up on the NCP.
+    Eui64(EUI64),
+
+    /// A unicast to the node of an address table entry.
+    AddressTable(u8),
+
+    /// A unicast to the node of a binding table entry.
+    Binding(u8),
+
+    /// A multicast to the members of a group.
+    Group(u16),
+
+    /// A broadcast to [`BROADCAST_ADDRESS`], [`RX_ON_WHEN_IDLE_BROADCAST_ADDRESS`]
+    /// or [`SLEEPY_BROADCAST_ADDRESS`].
+    Broadcast(NodeId),
+}
+
+impl Destination {
+    /// The message type messageSentHandler reports for the destination.
+    pub fn message_type(&self) -> OutgoingMessageType {
+        match self {
+            Destination::Direct(_) | Destination::Eui64(_) => OutgoingMessageType::Direct,
+            Destination::AddressTable(_) => OutgoingMessageType::ViaAddressTable,
+            Destination::Binding(_) => OutgoingMessageType::ViaBinding,
+            Destination::Group(_) => OutgoingMessageType::Multicast,
+            Destination::Broadcast(_) => OutgoingMessageType::Broadcast,
+        }
+    }
+
+    /// Whether the destination is a single node.
+    pub fn is_unicast(&self) -> bool {
+        matches!(
+            self,
+            Destination::Direct(_) | Destination::Eui64(_) | Destination::AddressTable(_) | Destination::Binding(_)
+        )
+    }
+}
+
+/// The APS header and payload of a message to send.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct OutgoingMessage {
+    /// The application profile the cluster belongs to.
+    pub profile_id: u16,
+
+    /// The cluster the message is for.
+    pub cluster_id: u16,
+
+    /// The endpoint sending the message.
+    pub source_endpoint: u8,
+
+    /// The endpoint receiving the message. Ignored for multicasts.
+    pub destination_endpoint: u8,
+
+    /// The APS options. Retries are dropped for multicasts and
+    /// broadcasts, which are not acknowledged.
+    pub options: ApsOption,
+
+    /// The number of hops the message may travel, or 0 for the
+    /// stack's maximum. Ignored for unicasts.
+    pub radius: u8,
+
+    /// The APS payload.
+    pub payload: Vec<u8>,
+}
+
+impl OutgoingMessage {
+    /// A message between endpoints 1 that is retried
+    /// and discovers routes as needed.
+    pub fn new(profile_id: u16, cluster_id: u16, payload: impl Into<Vec<u8>>) -> Self {
+        OutgoingMessage {
+            profile_id,
+            cluster_id,
+            source_endpoint: 1,
+            destination_endpoint: 1,
+            options: ApsOptionFlags::Retry | ApsOptionFlags::EnableRouteDiscovery,
+            radius: 0,
+            payload: payload.into(),
+        }
+    }
+
+    /// The APS frame carrying the message to `destination`.
+    pub fn aps_frame(&self, destination: Destination) -> ApsFrame {
+        let mut options = self.options;
+        if !destination.is_unicast() {
+            options.unset(ApsOptionFlags::Retry);
+        }
+
+        ApsFrame {
+            profile_id: self.profile_id,
+            cluster_id: self.cluster_id,
+            source_endpoint: self.source_endpoint,
+            destination_endpoint: self.destination_endpoint,
+            options,
+            group_id: match destination {
+                Destination::Group(group_id) => group_id,
+                _ => 0,
+            },
+            sequence: 0,
+        }
+    }
+}
+
+/// A message the NCP queued for transmission.
+///
+/// Pass it to [`wait_message_sent`](Connection::wait_message_sent)
+/// to learn whether it was delivered.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct SentMessage {
+    /// Where the message was sent.
+    pub destination: Destination,
+
+    /// The tag messageSentHandler refers to the message by.
+    pub tag: u8,
+
+    /// The APS sequence number the message is sent with.
+    pub sequence: u8,
+}
+
+impl<S: ByteStream> Connection<S> {
+    /// Queues `message` for transmission to `destination`.
+    ///
+    /// Fails with [`Error::InvalidBroadcastAddress`] if a broadcast
+    /// destination is not one of the broadcast addresses, and with
+    /// [`Error::UnknownEui64`] if the node ID of an EUI64 is not known.
+    pub fn send(&mut self, destination: Destination, message: &OutgoingMessage) -> Result<SentMessage, Error> {
+        if let Destination::Broadcast(address) = destination
+            && ![BROADCAST_ADDRESS, RX_ON_WHEN_IDLE_BROADCAST_ADDRESS, SLEEPY_BROADCAST_ADDRESS].contains(&address)
+        {
+            return Err(Error::InvalidBroadcastAddress(address));
+        }
+
+        let tag = self.next_message_tag();
+        let aps_frame = message.aps_frame(destination);
+        let message_contents = message.payload.clone();
+
+        // The outcome is tracked before sending, in case
+        // messageSentHandler arrives ahead of the response.
+        self.sent_messages.push((tag, None));
+        let sent = match destination {
+            Destination::Direct(node_id) => self.send_unicast(destination, node_id, aps_frame, tag, message_contents),
+            Destination::Eui64(eui64) => self
+                .resolve_node_id(&eui64)
+                .and_then(|node_id| node_id.ok_or(Error::UnknownEui64(eui64)))
+                .and_then(|node_id| self.send_unicast(destination, node_id, aps_frame, tag, message_contents)),
+            Destination::AddressTable(index) | Destination::Binding(index) => {
+                self.send_unicast(destination, u16::from(index), aps_frame, tag, message_contents)
+            }
+            Destination::Group(_) => self
+                .command(&SendMulticast {
+                    aps_frame,
+                    hops: message.radius,
+                    nonmember_radius: MULTICAST_NONMEMBER_RADIUS,
+                    message_tag: tag,
+                    message_contents,
+                })
+                .and_then(|response| response.status.into_result().map(|()| response.sequence)),
+            Destination::Broadcast(address) => self
+                .command(&SendBroadcast {
+                    destination: address,
+                    aps_frame,
+                    radius: message.radius,
+                    message_tag: tag,
+                    message_contents,
+                })
+                .and_then(|response| response.status.into_result().map(|()| response.sequence)),
+        };
+
+        match sent {
+            Ok(sequence) => Ok(SentMessage { destination, tag, sequence }),
+            Err(error) => {
+                self.sent_messages.retain(|(pending, _)| *pending != tag);
+                Err(error)
+            }
+        }
+    }
+
+    /// Replies to a unicast received from `sender`.
+    ///
+    /// Replies are not reported by messageSentHandler.
+    pub fn send_reply(&mut self, sender: NodeId, message: &OutgoingMessage) -> Result<(), Error> {
+        let frame = SendReply {
+            sender,
+            aps_frame: message.aps_frame(Destination::Direct(sender)),
+            message_contents: message.payload.clone(),
+        };
+        self.command(&frame)?.status.into_result()
+    }
+
+    /// Waits up to `timeout` for the messageSentHandler of `message`.
+    ///
+    /// Succeeds once unicasts are acknowledged and multicasts and
+    /// broadcasts are transmitted, and fails with the status reported
+    /// otherwise, e.g. [`DeliveryFailed`](crate::ember::types::Status::DeliveryFailed).
+    /// Fails with [`Error::Timeout`] if the stack does not report on the
+    /// message in time, and with [`Error::UnknownMessageTag`] if its
+    /// outcome was already returned.
+    pub fn wait_message_sent(&mut self, message: &SentMessage, timeout: Duration) -> Result<(), Error> {
+        let deadline = Instant::now() + timeout;
+
+        loop {
+            let index = self
+                .sent_messages
+                .iter()
+                .position(|(tag, _)| *tag == message.tag)
+                .ok_or(Error::UnknownMessageTag(message.tag))?;
+            if let (_, Some(status)) = self.sent_messages[index] {
+                self.sent_messages.remove(index);
+                return status.into_result();
+            }
+
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let sent = self.wait_for_callback(remaining, |callback| sent_message_tag(callback) == Some(message.tag))?;
+            if sent.is_none() {
+                return Err(Error::Timeout);
+            }
+        }
+    }
+
+    /// The tag for the next message, forgetting the
+    /// outcome of any earlier message with the same tag.
+    pub(super) fn next_message_tag(&mut self) -> u8 {
+        let tag = self.message_tag;
+        self.message_tag = self.message_tag.wrapping_add(1);
+        self.sent_messages.retain(|(pending, _)| *pending != tag);
+        tag
+    }
+
+    /// Records the outcome of a message from a messageSentHandler callback.
+    pub(super) fn track_sent_message(&mut self, callback: &Callback) {
+        let Some(Ok(sent)) = callback.parse::<MessageSentHandler>() else {
+            return;
+        };
+
+        if let Some((_, outcome)) = self.sent_messages.iter_mut().find(|(tag, _)| *tag == sent.message_tag) {
+            outcome.get_or_insert(sent.status);
+        }
+    }
+
+    fn send_unicast(
+        &mut self,
+        destination: Destination,
+        index_or_destination: u16,
+        aps_frame: ApsFrame,
+        message_tag: u8,
+        message_contents: Vec<u8>,
+    ) -> Result<u8, Error> {
+        let frame = SendUnicast {
+            message_type: destination.message_type(),
+            index_or_destination,
+            aps_frame,
+            message_tag,
+            message_contents,
+        };
+        let response = self.command(&frame)?;
+        response.status.into_result()?;
+        Ok(response.sequence)
+    }
+}
+
+fn sent_message_tag(callback: &Callback) -> Option<u8> {
+    Some(callback.parse::<MessageSentHandler>()?.ok()?.message_tag)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::tests::connect;
+    use crate::codec::EzspDecode;
+    use crate::ember::types::{NetworkStatus, Status as EmberStatus};
+    use crate::frames::Frame;
+
+    const TIMEOUT: Duration = Duration::from_millis(100);
+
+    #[test]
+    fn sends_resolve_by_tag () {
+        let mut connection = connect();
+        let message = OutgoingMessage::new(0x0104, 0x0006, [0x01, 0x00, 0x02]);
+        assert_eq!(connection.send(Destination::Direct(0x1234), &message), Err(Error::Ember(EmberStatus::NetworkDown)));
+
+        connection.ash_mut().stream_mut().set_network_status(NetworkStatus::JoinedNetwork);
+        connection.ash_mut().stream_mut().fail_delivery(0x5678);
+        let unicast = connection.send(Destination::Direct(0x1234), &message).unwrap();
+        let lost = connection.send(Destination::Direct(0x5678), &message).unwrap();
+        let multicast = connection.send(Destination::Group(0x0042), &message).unwrap();
+        let broadcast = connection.send(Destination::Broadcast(RX_ON_WHEN_IDLE_BROADCAST_ADDRESS), &message).unwrap();
+        assert_eq!([unicast.tag, lost.tag, multicast.tag, broadcast.tag], [1, 2, 3, 4]);
+        assert_eq!(broadcast.sequence, 4);
+
+        assert_eq!(connection.wait_message_sent(&broadcast, TIMEOUT), Ok(()));
+        assert_eq!(connection.wait_message_sent(&lost, TIMEOUT), Err(Error::Ember(EmberStatus::DeliveryFailed)));
+        assert_eq!(connection.wait_message_sent(&multicast, TIMEOUT), Ok(()));
+        assert_eq!(connection.wait_message_sent(&unicast, TIMEOUT), Ok(()));
+        assert_eq!(connection.wait_message_sent(&unicast, TIMEOUT), Err(Error::UnknownMessageTag(unicast.tag)));
+        assert_eq!(
+            connection.send(Destination::Broadcast(0x1234), &message),
+            Err(Error::InvalidBroadcastAddress(0x1234))
+        );
+    }
+
+    #[test]
+    fn frames_follow_the_destination () {
+        let mut connection = connect();
+        connection.ash_mut().stream_mut().set_network_status(NetworkStatus::JoinedNetwork);
+        let message = OutgoingMessage { radius: 5, ..OutgoingMessage::new(0x0104, 0x0006, [0x01]) };
+        connection.send(Destination::Binding(3), &message).unwrap();
+        connection.send(Destination::Group(0x0042), &message).unwrap();
+        connection.send_reply(0x1234, &message).unwrap();
+
+        let received = connection.ash().stream().received();
+        let unicast = received.iter().find(|command| command.header.frame_id == SendUnicast::frame_id()).unwrap();
+        let unicast = SendUnicast::decode_from_slice(&unicast.parameters).unwrap().0;
+        assert_eq!((unicast.message_type, unicast.index_or_destination), (OutgoingMessageType::ViaBinding, 3));
+        assert!(unicast.aps_frame.options.contains(ApsOptionFlags::Retry));
+
+        let multicast = received.iter().find(|command| command.header.frame_id == SendMulticast::frame_id()).unwrap();
+        let multicast = SendMulticast::decode_from_slice(&multicast.parameters).unwrap().0;
+        assert_eq!((multicast.aps_frame.group_id, multicast.hops), (0x0042, 5));
+        assert!(!multicast.aps_frame.options.contains(ApsOptionFlags::Retry));
+        assert!(received.iter().any(|command| command.header.frame_id == SendReply::frame_id()));
+    }
+}
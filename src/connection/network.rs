@@ -0,0 +1,291 @@
+//! Forming, joining and leaving networks, driven by stackStatusHandler.
+
+use std::time::{Duration, Instant};
+
+use crate::ash::ByteStream;
+use crate::codec::Recognized;
+use crate::ember::channel::{ChannelMask, check_channel};
+use crate::ember::types::{
+    NetworkInitBitmask, NetworkInitStruct, NetworkParameters, NetworkStatus, NodeType, Status as EmberStatus,
+};
+use crate::error::Error;
+use crate::frames::{
+    CallbackFrame, FindAndRejoinNetwork, FormNetwork, GetNetworkParameters, JoinNetwork, LeaveNetwork, NetworkInit,
+    NetworkState, StackStatusHandler,
+};
+
+use super::{Callback, Connection};
+
+/// How a network operation ended.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum NetworkOutcome {
+    /// The stack reported EMBER_NETWORK_UP.
+    Up,
+
+    /// The node left the network.
+    Left,
+
+    /// The stack reported why the operation failed.
+    Failed(Recognized<EmberStatus>),
+}
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+enum Operation {
+    Init,
+    Form,
+    Join,
+    Rejoin,
+    Leave,
+}
+
+/// A network operation the NCP accepted, which completes
+/// when the stack reports its new status.
+#[must_use = "the operation only completes once its stack status is received"]
+#[derive(Debug)]
+pub struct NetworkChange<'a, S> {
+    connection: &'a mut Connection<S>,
+    operation: Operation,
+}
+
+impl<S: ByteStream> NetworkChange<'_, S> {
+    /// Waits up to `timeout` for the stack status ending the operation.
+    ///
+    /// Network opened and closed notifications are skipped, as is the
+    /// network down status reported when a rejoin starts. Fails with
+    /// [`Error::Timeout`] if the operation does not end in time.
+    pub fn wait(self, timeout: Duration) -> Result<NetworkOutcome, Error> {
+        let deadline = Instant::now() + timeout;
+        let mut rejoin_started = self.operation != Operation::Rejoin;
+
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let Some(callback) = self.connection.wait_for_callback(remaining, is_stack_status)? else {
+                return Err(Error::Timeout);
+            };
+            let Some(handler) = callback.parse::<StackStatusHandler>() else {
+                continue;
+            };
+
+            let status = handler?.status;
+            match status.known() {
+                Some(EmberStatus::NetworkUp) => return Ok(NetworkOutcome::Up),
+                Some(EmberStatus::NetworkOpened | EmberStatus::NetworkClosed) => {}
+                Some(EmberStatus::NetworkDown) if !rejoin_started => rejoin_started = true,
+                Some(EmberStatus::NetworkDown) if self.operation == Operation::Leave => return Ok(NetworkOutcome::Left),
+                _ => return Ok(NetworkOutcome::Failed(status)),
+            }
+        }
+    }
+}
+
+impl<S: ByteStream> Connection<S> {
+    /// The network status, as last reported by stackStatusHandler.
+    ///
+    /// Use [`network_state`](Connection::network_state) to ask the NCP.
+    pub fn network_status(&self) -> NetworkStatus {
+        self.network_status
+    }
+
+    /// Asks the NCP whether the node is joining, joined to or leaving a network.
+    ///
+    /// A status unknown to this crate leaves
+    /// [`network_status`](Connection::network_status) unchanged.
+    pub fn network_state(&mut self) -> Result<Recognized<NetworkStatus>, Error> {
+        let status = self.command(&NetworkState {})?.status;
+        if let Recognized::Known(known) = status {
+            self.network_status = known;
+        }
+        Ok(status)
+    }
+
+    /// The node type and parameters of the current network.
+    pub fn network_parameters(&mut self) -> Result<(Recognized<NodeType>, NetworkParameters), Error> {
+        let response = self.command(&GetNetworkParameters {})?;
+        response.status.into_result()?;
+        Ok((response.node_type, response.parameters))
+    }
+
+    /// Resumes the network the node was part of before it rebooted.
+    ///
+    /// Fails with [`EmberStatus::NotJoined`] if there is none.
+    pub fn network_init(&mut self, bitmask: NetworkInitBitmask) -> Result<NetworkChange<'_, S>, Error> {
+        let frame = NetworkInit { network_init_struct: NetworkInitStruct { bitmask } };
+        self.network_change(Operation::Init, NetworkStatus::JoiningNetwork, |connection| {
+            Ok(connection.command(&frame)?.status)
+        })
+    }
+
+    /// Forms a new network as its coordinator.
+    ///
+    /// Fails with [`Error::InvalidChannel`] if the radio channel is not one of 11 to 26.
+    pub fn form_network(&mut self, parameters: NetworkParameters) -> Result<NetworkChange<'_, S>, Error> {
+        check_channel(parameters.radio_channel)?;
+        self.network_change(Operation::Form, NetworkStatus::JoiningNetwork, |connection| {
+            Ok(connection.command(&FormNetwork { parameters })?.status)
+        })
+    }
+
+    /// Joins the network described by `parameters` as `node_type`.
+    ///
+    /// Fails with [`Error::InvalidChannel`] if the radio channel is not one of 11 to 26.
+    pub fn join_network(
+        &mut self,
+        node_type: NodeType,
+        parameters: NetworkParameters,
+    ) -> Result<NetworkChange<'_, S>, Error> {
+        check_channel(parameters.radio_channel)?;
+        self.network_change(Operation::Join, NetworkStatus::JoiningNetwork, |connection| {
+            Ok(connection.command(&JoinNetwork { node_type, parameters })?.status)
+        })
+    }
+
+    /// Leaves the current network.
+    pub fn leave_network(&mut self) -> Result<NetworkChange<'_, S>, Error> {
+        self.network_change(Operation::Leave, NetworkStatus::LeavingNetowrk, |connection| {
+            Ok(connection.command(&LeaveNetwork {})?.status)
+        })
+    }
+
+    /// Finds the current network on the channels of `channel_mask`
+    /// and rejoins it, securely if `have_current_network_key`.
+    ///
+    /// An empty mask scans the current channel only.
+    pub fn find_and_rejoin_network(
+        &mut self,
+        have_current_network_key: bool,
+        channel_mask: ChannelMask,
+    ) -> Result<NetworkChange<'_, S>, Error> {
+        let frame = FindAndRejoinNetwork { have_current_network_key, channel_mask: channel_mask.bits() };
+        self.network_change(Operation::Rejoin, NetworkStatus::JoiningNetwork, |connection| {
+            Ok(connection.command(&frame)?.status)
+        })
+    }
+
+    /// Updates the network status from a stackStatusHandler callback.
+    pub(super) fn track_network_status(&mut self, callback: &Callback) {
+        let Some(Ok(StackStatusHandler { status })) = callback.parse::<StackStatusHandler>() else {
+            return;
+        };
+
+        match status.known() {
+            Some(EmberStatus::NetworkUp) => self.network_status = NetworkStatus::JoinedNetwork,
+            Some(EmberStatus::NetworkDown | EmberStatus::JoinFailed | EmberStatus::CannotJoinAsRouter) => {
+                self.network_status = NetworkStatus::NoNetwork;
+            }
+            _ => {}
+        }
+    }
+
+    /// Moves to the transitional `status` while `command` starts the
+    /// operation, so stack statuses received in the meantime win.
+    fn network_change(
+        &mut self,
+        operation: Operation,
+        status: NetworkStatus,
+        command: impl FnOnce(&mut Self) -> Result<Recognized<EmberStatus>, Error>,
+    ) -> Result<NetworkChange<'_, S>, Error> {
+        let previous = std::mem::replace(&mut self.network_status, status);
+        if let Err(error) = command(self).and_then(Recognized::<EmberStatus>::into_result) {
+            self.network_status = previous;
+            return Err(error);
+        }
+        Ok(NetworkChange { connection: self, operation })
+    }
+}
+
+fn is_stack_status(callback: &Callback) -> bool {
+    callback.frame_id() == StackStatusHandler::frame_id()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::tests::connect;
+    use crate::ember::types::{JoinMethod, NetworkInitBitmaskFlags, ZigbeeNetwork};
+    use crate::frames::Frame;
+
+    const TIMEOUT: Duration = Duration::from_millis(100);
+
+    fn parameters(channel: u8) -> NetworkParameters {
+        NetworkParameters {
+            extended_pan_id: [1, 2, 3, 4, 5, 6, 7, 8],
+            pan_id: 0x1A62,
+            radio_tx_power: 3,
+            radio_channel: channel,
+            join_method: JoinMethod::MACAssociation.into(),
+            network_manager_id: 0x0000,
+            network_update_id: 0,
+            channels: ChannelMask::ALL,
+        }
+    }
+
+    #[test]
+    fn form_and_leave () {
+        let mut connection = connect();
+        assert_eq!(connection.form_network(parameters(27)).err(), Some(Error::InvalidChannel(27)));
+
+        let change = connection.form_network(parameters(15)).unwrap();
+        assert_eq!(change.wait(TIMEOUT), Ok(NetworkOutcome::Up));
+        assert_eq!(connection.network_status(), NetworkStatus::JoinedNetwork);
+        assert_eq!(connection.network_state(), Ok(NetworkStatus::JoinedNetwork.into()));
+
+        let (node_type, network) = connection.network_parameters().unwrap();
+        assert_eq!(node_type, NodeType::Coordinator);
+        assert_eq!(network.radio_channel, 15);
+        assert_eq!(
+            connection.form_network(parameters(15)).err(),
+            Some(Error::Ember(EmberStatus::InvalidCall))
+        );
+
+        assert_eq!(connection.leave_network().unwrap().wait(TIMEOUT), Ok(NetworkOutcome::Left));
+        assert_eq!(connection.network_status(), NetworkStatus::NoNetwork);
+        assert_eq!(connection.network_parameters(), Err(Error::Ember(EmberStatus::NotJoined)));
+    }
+
+    #[test]
+    fn join_rejoin_and_init () {
+        let mut connection = connect();
+        let bitmask = NetworkInitBitmaskFlags::ParentInfoInToken.into();
+        assert_eq!(connection.network_init(bitmask).err(), Some(Error::Ember(EmberStatus::NotJoined)));
+
+        let change = connection.join_network(NodeType::Router, parameters(20)).unwrap();
+        assert_eq!(change.wait(TIMEOUT), Ok(NetworkOutcome::Failed(EmberStatus::JoinFailed.into())));
+        assert_eq!(connection.network_status(), NetworkStatus::NoNetwork);
+
+        connection.ash_mut().stream_mut().add_network(
+            ZigbeeNetwork {
+                channel: 20,
+                pan_id: 0x1A62,
+                extended_pan_id: [1, 2, 3, 4, 5, 6, 7, 8],
+                allowing_join: true,
+                stack_profile: 2,
+                network_update_id: 0,
+            },
+            0xFF,
+            -40,
+        );
+        let change = connection.join_network(NodeType::Router, parameters(20)).unwrap();
+        assert_eq!(change.wait(TIMEOUT), Ok(NetworkOutcome::Up));
+
+        let change = connection.find_and_rejoin_network(true, ChannelMask::EMPTY).unwrap();
+        assert_eq!(change.wait(TIMEOUT), Ok(NetworkOutcome::Up));
+
+        connection.connect().unwrap();
+        assert_eq!(connection.network_init(bitmask).unwrap().wait(TIMEOUT), Ok(NetworkOutcome::Up));
+        assert_eq!(connection.network_parameters().unwrap().0, NodeType::Router);
+    }
+
+    #[test]
+    fn stack_status_timeout_and_tracking () {
+        let mut connection = connect();
+        connection.ash_mut().stream_mut().on_command(FormNetwork::frame_id(), |_| vec![0x00]);
+        let change = connection.form_network(parameters(11)).unwrap();
+        assert_eq!(change.wait(TIMEOUT), Err(Error::Timeout));
+        assert_eq!(connection.network_status(), NetworkStatus::JoiningNetwork);
+
+        let up = [EmberStatus::NetworkUp as u8];
+        connection.ash_mut().stream_mut().emit_callback(StackStatusHandler::frame_id(), &up);
+        assert_eq!(connection.poll_callback().unwrap().unwrap().frame_id(), StackStatusHandler::frame_id());
+        assert_eq!(connection.network_status(), NetworkStatus::JoinedNetwork);
+    }
+}
@@ -0,0 +1,68 @@
+//! Reading and changing NCP policies.
+
+use crate::ash::ByteStream;
+use crate::codec::CodecError;
+use crate::error::Error;
+use crate::ezsp::policy::Decision;
+use crate::ezsp::types::PolicyID;
+use crate::frames::{GetPolicy, SetPolicy};
+
+use super::Connection;
+
+impl<S: ByteStream> Connection<S> {
+    /// Changes the decision the NCP makes for a policy.
+    ///
+    /// Fails with [`Error::InvalidDecision`] without sending anything
+    /// if the decision is not one the policy accepts.
+    pub fn set_policy(&mut self, policy_id: PolicyID, decision: impl Into<Decision>) -> Result<(), Error> {
+        let decision = decision.into();
+        if !policy_id.allows(decision) {
+            return Err(Error::InvalidDecision { policy_id, decision: decision.raw() });
+        }
+
+        self.command(&SetPolicy { policy_id, decision_id: decision.raw() })?.status.into_result()
+    }
+
+    /// Reads the decision the NCP makes for a policy.
+    pub fn get_policy(&mut self, policy_id: PolicyID) -> Result<Decision, Error> {
+        let response = self.command(&GetPolicy { policy_id })?;
+        response.status.into_result()?;
+
+        Decision::from_raw(policy_id, response.decision_id).ok_or(Error::Codec(CodecError::InvalidValue))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::tests::connect;
+    use crate::ezsp::types::{DecisionBitmaskFlags, DecisionId};
+
+    #[test]
+    fn set_and_get () {
+        let mut connection = connect();
+
+        connection.set_policy(PolicyID::BindingModification, DecisionId::AllowBindingModification).unwrap();
+        assert_eq!(
+            connection.get_policy(PolicyID::BindingModification),
+            Ok(Decision::Id(DecisionId::AllowBindingModification))
+        );
+
+        let joins = DecisionBitmaskFlags::AllowJoins | DecisionBitmaskFlags::JoinsUseInstallCodeKey;
+        connection.set_policy(PolicyID::TrustCenter, joins).unwrap();
+        assert_eq!(connection.ash().stream().policy(PolicyID::TrustCenter), Some(0x11));
+        assert_eq!(connection.get_policy(PolicyID::TrustCenter), Ok(Decision::TrustCenter(joins)));
+    }
+
+    #[test]
+    fn illegal_decisions_are_not_sent () {
+        let mut connection = connect();
+        let sent = connection.ash().stream().received().len();
+
+        assert_eq!(
+            connection.set_policy(PolicyID::UnicastReplies, DecisionId::PollHandlerCallback),
+            Err(Error::InvalidDecision { policy_id: PolicyID::UnicastReplies, decision: 0x31 })
+        );
+        assert_eq!(connection.ash().stream().received().len(), sent);
+    }
+}
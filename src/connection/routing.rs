@@ -0,0 +1,107 @@
+//! Concentrator support and the source routes learned from route records.
+
+use crate::ash::ByteStream;
+use crate::ember::types::{ConcentratorType, NodeId};
+use crate::error::Error;
+use crate::ezsp::routing::{Concentrator, SourceRoute, SourceRouteTable};
+use crate::frames::{IncomingRouteRecordHandler, SendManyToOneRouteRequest, SetConcentrator};
+
+use super::{Callback, Connection};
+
+impl<S: ByteStream> Connection<S> {
+    /// Makes the node a concentrator, sending many-to-one
+    /// route requests as configured by `concentrator`.
+    pub fn enable_concentrator(&mut self, concentrator: &Concentrator) -> Result<(), Error> {
+        self.command(&concentrator.to_frame())?.status.into_result()
+    }
+
+    /// Stops sending many-to-one route requests.
+    pub fn disable_concentrator(&mut self) -> Result<(), Error> {
+        let frame = SetConcentrator { on: false, ..Concentrator::new(ConcentratorType::LowRAM).to_frame() };
+        self.command(&frame)?.status.into_result()
+    }
+
+    /// Sends a many-to-one route request right away, creating
+    /// routes from every node within `radius` hops back to this one.
+    pub fn send_many_to_one_route_request(
+        &mut self,
+        concentrator_type: ConcentratorType,
+        radius: u8,
+    ) -> Result<(), Error> {
+        self.command(&SendManyToOneRouteRequest { concentrator_type, radius })?.status.into_result()
+    }
+
+    /// The source routes recorded from incomingRouteRecordHandler callbacks.
+    pub fn source_routes(&self) -> &SourceRouteTable {
+        &self.source_routes
+    }
+
+    /// The source routes, e.g. to limit their number or
+    /// forget a route after a delivery failure.
+    pub fn source_routes_mut(&mut self) -> &mut SourceRouteTable {
+        &mut self.source_routes
+    }
+
+    /// The last route recorded to `destination`.
+    pub fn source_route(&self, destination: NodeId) -> Option<&SourceRoute> {
+        self.source_routes.get(destination)
+    }
+
+    /// Records the route of an incomingRouteRecordHandler callback.
+    pub(super) fn record_source_route(&mut self, callback: &Callback) {
+        if let Some(Ok(record)) = callback.parse::<IncomingRouteRecordHandler>() {
+            self.source_routes.record(record.source, record.source_eui, record.relay_list);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::tests::connect;
+    use crate::ember::types::{NetworkStatus, Status as EmberStatus};
+    use crate::frames::{CallbackFrame, IncomingManyToOneRouteRequestHandler};
+
+    #[test]
+    fn concentrator () {
+        let mut connection = connect();
+        let concentrator = Concentrator { max_hops: 10, ..Concentrator::new(ConcentratorType::HighRAM) };
+        connection.enable_concentrator(&concentrator).unwrap();
+        assert_eq!(connection.ash().stream().concentrator(), Some(ConcentratorType::HighRAM));
+        assert_eq!(
+            connection.send_many_to_one_route_request(ConcentratorType::HighRAM, 0),
+            Err(Error::Ember(EmberStatus::NetworkDown))
+        );
+
+        connection.ash_mut().stream_mut().set_network_status(NetworkStatus::JoinedNetwork);
+        connection.send_many_to_one_route_request(ConcentratorType::HighRAM, 0).unwrap();
+        connection.disable_concentrator().unwrap();
+        assert_eq!(connection.ash().stream().concentrator(), None);
+    }
+
+    #[test]
+    fn route_records_fill_the_table () {
+        let mut connection = connect();
+        let record = IncomingRouteRecordHandler {
+            source: 0x3003,
+            source_eui: [3; 8],
+            last_hop_lqi: 0xFF,
+            last_hop_rssi: -45,
+            relay_list: vec![0x2002, 0x1001],
+        };
+        let request = IncomingManyToOneRouteRequestHandler { source: 0x0000, long_id: [0; 8], cost: 2 };
+        let ncp = connection.ash_mut().stream_mut();
+        ncp.emit_callback_with(IncomingRouteRecordHandler::frame_id(), &record);
+        ncp.emit_callback_with(IncomingManyToOneRouteRequestHandler::frame_id(), &request);
+
+        let callback = connection.poll_callback().unwrap().unwrap();
+        assert_eq!(callback.parse::<IncomingRouteRecordHandler>(), Some(Ok(record)));
+        let callback = connection.poll_callback().unwrap().unwrap();
+        assert_eq!(callback.parse::<IncomingManyToOneRouteRequestHandler>(), Some(Ok(request)));
+
+        let route = connection.source_route(0x3003).unwrap();
+        assert_eq!((route.relays.as_slice(), route.next_hop()), (&[0x2002, 0x1001][..], 0x1001));
+        connection.source_routes_mut().remove(0x3003);
+        assert!(connection.source_routes().is_empty());
+    }
+}
@@ -0,0 +1,266 @@
+//! Energy and active scans, streamed as they progress.
+
+use std::time::{Duration, Instant};
+
+use crate::ash::ByteStream;
+use crate::codec::{EzspDecode, Recognized};
+use crate::ember::channel::{ChannelMask, ChannelSurvey};
+use crate::ember::types::{Status as EmberStatus, ZigbeeNetwork};
+use crate::error::Error;
+use crate::ezsp::types::NetworkScanType;
+use crate::frames::{
+    CallbackFrame, EnergyScanResultHandler, NetworkFoundHandler, ScanCompleteHandler, StartScan, StopScan,
+};
+
+use super::{Callback, Connection};
+
+/// The longest a scan period of 960 symbols takes at 2.4 GHz.
+const SCAN_PERIOD: Duration = Duration::from_micros(15_360);
+
+/// A result reported while a scan is in progress.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum ScanEvent {
+    /// The energy measured on a channel during an energy scan.
+    Energy {
+        /// The channel that was scanned.
+        channel: u8,
+
+        /// The maximum RSSI measured on the channel, in dBm.
+        max_rssi: i8,
+    },
+
+    /// A network that answered an active scan.
+    Network {
+        /// The network's parameters.
+        network: ZigbeeNetwork,
+
+        /// The link quality of the beacon.
+        lqi: u8,
+
+        /// The signal strength of the beacon, in dBm.
+        rssi: i8,
+    },
+
+    /// The scan finished, successfully or not. Always the last event.
+    Complete {
+        /// The channel the scan failed on. Undefined on success.
+        channel: u8,
+
+        /// Whether the scan completed, or why it failed.
+        status: Recognized<EmberStatus>,
+    },
+}
+
+/// A scan in progress, yielding [`ScanEvent`]s until it completes.
+///
+/// Callbacks unrelated to the scan stay queued on the connection.
+/// If the scan does not complete in time, the last item is
+/// [`Error::Timeout`].
+#[derive(Debug)]
+pub struct Scan<'a, S> {
+    connection: &'a mut Connection<S>,
+    deadline: Instant,
+    finished: bool,
+}
+
+impl<S: ByteStream> Scan<'_, S> {
+    /// Terminates the scan.
+    pub fn stop(self) -> Result<(), Error> {
+        self.connection.stop_scan()
+    }
+}
+
+impl<S: ByteStream> Iterator for Scan<'_, S> {
+    type Item = Result<ScanEvent, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let timeout = self.deadline.saturating_duration_since(Instant::now());
+        let event = match self.connection.wait_for_callback(timeout, is_scan_callback) {
+            Ok(Some(callback)) => parse_event(&callback),
+            Ok(None) => Err(Error::Timeout),
+            Err(error) => Err(error),
+        };
+
+        self.finished = !matches!(event, Ok(ScanEvent::Energy { .. } | ScanEvent::Network { .. }));
+        Some(event)
+    }
+}
+
+impl<S: ByteStream> Connection<S> {
+    /// Starts a scan of the channels in `channel_mask`, spending
+    /// ((2^`duration`) + 1) scan periods of 15.36 ms on each.
+    ///
+    /// Energy scans yield [`ScanEvent::Energy`], active scans yield
+    /// [`ScanEvent::Network`], and both end with [`ScanEvent::Complete`].
+    pub fn start_scan(
+        &mut self,
+        scan_type: NetworkScanType,
+        channel_mask: ChannelMask,
+        duration: u8,
+    ) -> Result<Scan<'_, S>, Error> {
+        let frame = StartScan { scan_type, channel_mask: channel_mask.bits(), duration };
+        self.command(&frame)?.status.into_result()?;
+
+        let channels = (channel_mask.len() as u32).max(1);
+        let periods = 1u32.checked_shl(duration.into()).unwrap_or(u32::MAX).saturating_add(1);
+        let deadline = Instant::now() + SCAN_PERIOD * periods.saturating_mul(channels) + self.response_timeout;
+
+        Ok(Scan { connection: self, deadline, finished: false })
+    }
+
+    /// Terminates a scan in progress.
+    pub fn stop_scan(&mut self) -> Result<(), Error> {
+        self.command(&StopScan {})?.status.into_result()
+    }
+
+    /// Runs an energy scan and then an active scan of `channel_mask`,
+    /// both of `duration`, and records what they found.
+    ///
+    /// Fails with [`Error::Ember`] if either scan does not complete.
+    pub fn survey_channels(&mut self, channel_mask: ChannelMask, duration: u8) -> Result<ChannelSurvey, Error> {
+        let mut survey = ChannelSurvey::new(channel_mask);
+        for scan_type in [NetworkScanType::EnergyScan, NetworkScanType::ActiveScan] {
+            for event in self.start_scan(scan_type, channel_mask, duration)? {
+                match event? {
+                    ScanEvent::Energy { channel, max_rssi } => survey.record_energy(channel, max_rssi),
+                    ScanEvent::Network { network, .. } => survey.record_network(network.channel),
+                    ScanEvent::Complete { status, .. } => status.into_result()?,
+                }
+            }
+        }
+        Ok(survey)
+    }
+
+    /// Picks the quietest channel of `channel_mask` to form a network on,
+    /// ranked as by [`ChannelSurvey::ranked`].
+    ///
+    /// Fails with [`Error::InvalidChannelMask`] if `channel_mask` is empty.
+    pub fn select_channel(&mut self, channel_mask: ChannelMask, duration: u8) -> Result<u8, Error> {
+        self.survey_channels(channel_mask, duration)?
+            .quietest()
+            .ok_or(Error::InvalidChannelMask(channel_mask.bits()))
+    }
+}
+
+fn is_scan_callback(callback: &Callback) -> bool {
+    [EnergyScanResultHandler::frame_id(), NetworkFoundHandler::frame_id(), ScanCompleteHandler::frame_id()]
+        .contains(&callback.frame_id())
+}
+
+/// The event reported by a callback accepted by [`is_scan_callback`].
+fn parse_event(callback: &Callback) -> Result<ScanEvent, Error> {
+    if let Some(result) = callback.parse::<EnergyScanResultHandler>() {
+        let result = result?;
+        return Ok(ScanEvent::Energy { channel: result.channel, max_rssi: result.max_rssi_value });
+    }
+    if let Some(found) = callback.parse::<NetworkFoundHandler>() {
+        let found = found?;
+        return Ok(ScanEvent::Network { network: found.network_found, lqi: found.last_hop_lqi, rssi: found.last_hop_rssi });
+    }
+
+    // Any other callback is scanCompleteHandler.
+    let (complete, _) = ScanCompleteHandler::decode_from_slice(&callback.parameters)?;
+    Ok(ScanEvent::Complete { channel: complete.channel, status: complete.status })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::tests::connect;
+    use crate::frames::Frame;
+
+    fn network(channel: u8) -> ZigbeeNetwork {
+        ZigbeeNetwork {
+            channel,
+            pan_id: 0x1A62,
+            extended_pan_id: [1, 2, 3, 4, 5, 6, 7, 8],
+            allowing_join: true,
+            stack_profile: 2,
+            network_update_id: 0,
+        }
+    }
+
+    #[test]
+    fn energy_scan () {
+        let mut connection = connect();
+        connection.ash_mut().stream_mut().set_channel_energy(15, -40);
+        connection.ash_mut().stream_mut().emit_callback(0x0019, &[0x90]);
+
+        let events: Vec<_> = connection
+            .start_scan(NetworkScanType::EnergyScan, ChannelMask::from_channels([15, 20]).unwrap(), 0)
+            .unwrap()
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(events, [
+            ScanEvent::Energy { channel: 15, max_rssi: -40 },
+            ScanEvent::Energy { channel: 20, max_rssi: -90 },
+            ScanEvent::Complete { channel: 0, status: EmberStatus::Success.into() },
+        ]);
+
+        assert_eq!(connection.poll_callback().unwrap().unwrap().frame_id(), 0x0019);
+    }
+
+    #[test]
+    fn active_scan () {
+        let mut connection = connect();
+        connection.ash_mut().stream_mut().add_network(network(15), 0xFF, -50);
+        connection.ash_mut().stream_mut().add_network(network(25), 0x80, -70);
+
+        let mut scan = connection.start_scan(NetworkScanType::ActiveScan, ChannelMask::single(15).unwrap(), 3).unwrap();
+        assert_eq!(scan.next(), Some(Ok(ScanEvent::Network { network: network(15), lqi: 0xFF, rssi: -50 })));
+        assert_eq!(scan.next(), Some(Ok(ScanEvent::Complete { channel: 0, status: EmberStatus::Success.into() })));
+        assert_eq!(scan.next(), None);
+    }
+
+    #[test]
+    fn scan_errors () {
+        let mut connection = connect();
+        assert!(matches!(
+            connection.start_scan(NetworkScanType::EnergyScan, ChannelMask::PREFERRED, 15),
+            Err(Error::Ember(EmberStatus::MACBadScanDuration))
+        ));
+        assert!(matches!(
+            connection.start_scan(NetworkScanType::EnergyScan, ChannelMask::EMPTY, 1),
+            Err(Error::Ember(EmberStatus::MACInvalidChannelMask))
+        ));
+
+        connection.ash_mut().stream_mut().on_command(StartScan::frame_id(), |_| vec![0x00]);
+        let mut scan = connection.start_scan(NetworkScanType::EnergyScan, ChannelMask::PREFERRED, 0).unwrap();
+        assert_eq!(scan.next(), Some(Err(Error::Timeout)));
+        assert_eq!(scan.next(), None);
+        connection.stop_scan().unwrap();
+    }
+
+    #[test]
+    fn quiet_channel_selection () {
+        let mut connection = connect();
+        let ncp = connection.ash_mut().stream_mut();
+        for channel in [11, 15, 20, 25] {
+            ncp.set_channel_energy(channel, -95);
+        }
+        ncp.set_channel_energy(11, -60);
+        ncp.add_network(network(15), 0xFF, -50);
+
+        let survey = connection.survey_channels(ChannelMask::PREFERRED, 2).unwrap();
+        assert_eq!(survey.max_rssi(11), Some(-60));
+        assert_eq!(survey.networks(15), 1);
+        assert_eq!(survey.ranked(), [20, 25, 15, 11]);
+        assert_eq!(connection.select_channel(ChannelMask::PREFERRED, 2), Ok(20));
+        assert_eq!(connection.select_channel(ChannelMask::single(11).unwrap(), 2), Ok(11));
+    }
+
+    #[test]
+    fn empty_channel_mask () {
+        let mut connection = connect();
+        let ncp = connection.ash_mut().stream_mut();
+        ncp.on_command(StartScan::frame_id(), |_| vec![0x00]);
+        ncp.emit_callback(ScanCompleteHandler::frame_id(), &[0, 0x00]);
+        ncp.emit_callback(ScanCompleteHandler::frame_id(), &[0, 0x00]);
+
+        assert_eq!(connection.select_channel(ChannelMask::EMPTY, 0), Err(Error::InvalidChannelMask(0)));
+    }
+}
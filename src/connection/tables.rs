@@ -0,0 +1,161 @@
+//! Reading the neighbor, route and child tables.
+
+use crate::ash::ByteStream;
+use crate::codec::Recognized;
+use crate::ember::types::{ChildData, NeighborTableEntry, RouteStatus, RouteTableEntry, Status as EmberStatus};
+use crate::error::Error;
+use crate::ezsp::types::ConfigID;
+use crate::frames::{GetChildData, GetNeighbor, GetRouteTableEntry, NeighborCount};
+
+use super::Connection;
+
+/// The short ID of an unused neighbor table entry.
+const UNUSED_NEIGHBOR: u16 = 0xFFFF;
+
+/// The in-use entries of the neighbor, route and child tables.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct NetworkTables {
+    /// The active neighbors, in ascending order of node ID.
+    pub neighbors: Vec<NeighborTableEntry>,
+
+    /// The routes that are not unused, including those
+    /// whose state this crate does not recognize.
+    pub routes: Vec<RouteTableEntry>,
+
+    /// The children of this node.
+    pub children: Vec<ChildData>,
+}
+
+impl<S: ByteStream> Connection<S> {
+    /// The number of active entries in the neighbor table.
+    pub fn neighbor_count(&mut self) -> Result<u8, Error> {
+        Ok(self.command(&NeighborCount {})?.value)
+    }
+
+    /// The neighbor table entry at `index`.
+    ///
+    /// Fails with [`EmberStatus::ErrFatal`] past the active neighbors.
+    pub fn neighbor(&mut self, index: u8) -> Result<NeighborTableEntry, Error> {
+        let response = self.command(&GetNeighbor { index })?;
+        response.status.into_result()?;
+        Ok(response.value)
+    }
+
+    /// The route table entry at `index`, which may be unused.
+    ///
+    /// Fails with [`EmberStatus::ErrFatal`] past the end of the table.
+    pub fn route_table_entry(&mut self, index: u8) -> Result<RouteTableEntry, Error> {
+        let response = self.command(&GetRouteTableEntry { index })?;
+        response.status.into_result()?;
+        Ok(response.value)
+    }
+
+    /// The child at `index` of the child table, or `None` if there is none.
+    pub fn child_data(&mut self, index: u8) -> Result<Option<ChildData>, Error> {
+        let response = self.command(&GetChildData { index })?;
+        match response.status {
+            Recognized::Known(EmberStatus::NotJoined) => Ok(None),
+            status => status.into_result().map(|()| Some(response.child_data)),
+        }
+    }
+
+    /// Reads every table up to its configured size and
+    /// returns the entries that are in use.
+    pub fn dump_tables(&mut self) -> Result<NetworkTables, Error> {
+        let mut tables = NetworkTables::default();
+
+        for index in 0..self.table_size(ConfigID::NeighborTableSize)? {
+            match self.neighbor(index) {
+                Ok(entry) if entry.short_id != UNUSED_NEIGHBOR => tables.neighbors.push(entry),
+                Ok(_) | Err(Error::Ember(EmberStatus::ErrFatal)) => break,
+                Err(error) => return Err(error),
+            }
+        }
+
+        for index in 0..self.table_size(ConfigID::RouteTableSize)? {
+            match self.route_table_entry(index) {
+                Ok(entry) if entry.status != RouteStatus::Unused => tables.routes.push(entry),
+                Ok(_) => {}
+                Err(Error::Ember(EmberStatus::ErrFatal)) => break,
+                Err(error) => return Err(error),
+            }
+        }
+
+        for index in 0..self.table_size(ConfigID::MaxEndDeviceChildren)? {
+            tables.children.extend(self.child_data(index)?);
+        }
+
+        Ok(tables)
+    }
+
+    fn table_size(&mut self, config_id: ConfigID) -> Result<u8, Error> {
+        let size = self.get_configuration_value(config_id)?;
+        Ok(u8::try_from(size).unwrap_or(u8::MAX))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::tests::connect;
+    use crate::ember::types::{NetworkStatus, NodeType, RouteConcentratorType, RouteRecordState};
+
+    fn neighbor(short_id: u16) -> NeighborTableEntry {
+        NeighborTableEntry { short_id, average_lqi: 0xF0, in_cost: 1, out_cost: 3, age: 2, long_id: [short_id as u8; 8] }
+    }
+
+    fn route(destination: u16, status: RouteStatus) -> RouteTableEntry {
+        RouteTableEntry {
+            destination,
+            next_hop: 0x1001,
+            status: status.into(),
+            age: 0,
+            concentrator_type: RouteConcentratorType::NotConcentrator.into(),
+            route_record_state: RouteRecordState::NoLongerNeeded.into(),
+        }
+    }
+
+    #[test]
+    fn table_entries () {
+        let mut connection = connect();
+        connection.ash_mut().stream_mut().add_neighbor(neighbor(0x1001));
+        connection.ash_mut().stream_mut().add_route(route(0x2002, RouteStatus::Active));
+
+        assert_eq!(connection.neighbor_count(), Ok(1));
+        assert_eq!(connection.neighbor(0), Ok(neighbor(0x1001)));
+        assert_eq!(connection.neighbor(1), Err(Error::Ember(EmberStatus::ErrFatal)));
+        assert_eq!(connection.route_table_entry(0), Ok(route(0x2002, RouteStatus::Active)));
+        assert_eq!(connection.route_table_entry(1).unwrap().status, RouteStatus::Unused);
+        assert_eq!(connection.route_table_entry(16), Err(Error::Ember(EmberStatus::ErrFatal)));
+        assert_eq!(connection.child_data(0), Ok(None));
+    }
+
+    #[test]
+    fn dump_returns_entries_in_use () {
+        let mut connection = connect();
+        let ncp = connection.ash_mut().stream_mut();
+        ncp.add_neighbor(neighbor(0x1001));
+        ncp.add_neighbor(neighbor(0x3003));
+        ncp.add_route(route(0x2002, RouteStatus::Active));
+        ncp.add_route(route(0xFFFF, RouteStatus::Unused));
+        ncp.add_route(route(0x4004, RouteStatus::BeingDiscovered));
+        let future = RouteTableEntry {
+            status: Recognized::Unknown(0x07),
+            concentrator_type: Recognized::Unknown(0x03),
+            ..route(0x6006, RouteStatus::Active)
+        };
+        ncp.add_route(future);
+        ncp.set_network_status(NetworkStatus::JoinedNetwork);
+        connection.permit_joining(60, false).unwrap();
+        connection.ash_mut().stream_mut().join_device(0x5005, [5; 8], NodeType::EndDevice);
+
+        let tables = connection.dump_tables().unwrap();
+        assert_eq!(tables.neighbors, [neighbor(0x1001), neighbor(0x3003)]);
+        assert_eq!(
+            tables.routes,
+            [route(0x2002, RouteStatus::Active), route(0x4004, RouteStatus::BeingDiscovered), future]
+        );
+        assert_eq!(tables.children.len(), 1);
+        assert_eq!((tables.children[0].id, tables.children[0].node_type), (0x5005, NodeType::EndDevice.into()));
+    }
+}
@@ -0,0 +1,141 @@
+//! Utility commands: link self-tests, pending callbacks and random numbers.
+
+use crate::ash::ByteStream;
+use crate::ash::frame::{CANCEL, ESCAPE, FLAG, MAX_DATA_LENGTH, SUBSTITUTE, XOFF, XON};
+use crate::codec::Recognized;
+use crate::ember::types::EntropySource;
+use crate::error::Error;
+use crate::frames::{
+    self, CallbackFrame, Echo, GetRandomNumber, GetTrueRandomEntropySource, NoCallbacks,
+};
+
+use super::{Callback, Connection};
+
+impl<S: ByteStream> Connection<S> {
+    /// Sends `data` to the NCP and returns what it echoed back.
+    pub fn echo(&mut self, data: &[u8]) -> Result<Vec<u8>, Error> {
+        Ok(self.command(&Echo { data: data.to_vec() })?.echo)
+    }
+
+    /// Checks the link to the NCP by echoing data that needs escaping
+    /// in ASH frames and the longest data that fits in a frame.
+    ///
+    /// Fails with [`Error::EchoMismatch`] if the NCP
+    /// echoes anything other than what was sent.
+    pub fn self_test(&mut self) -> Result<(), Error> {
+        let capabilities = self.capabilities().ok_or(Error::NotNegotiated)?;
+        let longest = MAX_DATA_LENGTH - capabilities.header_format().length() - 1;
+
+        let reserved = vec![FLAG, ESCAPE, XON, XOFF, SUBSTITUTE, CANCEL];
+        let pattern = (0..longest).map(|byte| byte as u8).collect();
+        for data in [Vec::new(), reserved, pattern] {
+            if self.echo(&data)? != data {
+                return Err(Error::EchoMismatch);
+            }
+        }
+        Ok(())
+    }
+
+    /// Asks the NCP for its next pending callback.
+    ///
+    /// Returns `None` once the NCP has no callbacks left. The callback
+    /// updates the connection's state like any other received one.
+    pub fn request_callback(&mut self) -> Result<Option<Callback>, Error> {
+        let capabilities = self.capabilities().ok_or(Error::NotNegotiated)?;
+        capabilities.check_frame(frames::Callback::frame_id())?;
+
+        let callback = self.exchange_raw(frames::Callback::frame_id(), &frames::Callback {}, |_| true)?;
+        if callback.frame_id() == NoCallbacks::frame_id() {
+            return Ok(None);
+        }
+        self.observe(&callback);
+        Ok(Some(callback))
+    }
+
+    /// Returns a pseudorandom number.
+    pub fn random_number(&mut self) -> Result<u16, Error> {
+        let response = self.command(&GetRandomNumber {})?;
+        response.status.into_result()?;
+        Ok(response.value)
+    }
+
+    /// The entropy source the NCP seeds its random numbers from.
+    ///
+    /// The source is fixed by the NCP's hardware and firmware,
+    /// so it only needs to be read once.
+    pub fn entropy_source(&mut self) -> Result<Recognized<EntropySource>, Error> {
+        Ok(self.command(&GetTrueRandomEntropySource {})?.entropy_source)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::tests::connect;
+    use crate::ember::types::{EventUnits, LibraryStatusFlags, NetworkStatus};
+    use crate::frames::{
+        Frame, GetEui64, GetLibraryStatus, GetNodeId, GetTimer, Nop, SetTimer, StackStatusHandler, TimerHandler,
+    };
+
+    #[test]
+    fn echo_self_test () {
+        let mut connection = connect();
+        connection.command(&Nop {}).unwrap();
+        assert_eq!(connection.echo(&[0x7E, 0x01]), Ok(vec![0x7E, 0x01]));
+        connection.self_test().unwrap();
+
+        connection.ash_mut().stream_mut().on_command(Echo::frame_id(), |_| vec![1, 0x00]);
+        assert_eq!(connection.self_test(), Err(Error::EchoMismatch));
+    }
+
+    #[test]
+    fn pending_callbacks () {
+        let mut connection = connect();
+        assert_eq!(connection.request_callback(), Ok(None));
+
+        connection.ash_mut().stream_mut().queue_callback(TimerHandler::frame_id(), &[1]);
+        let callback = connection.request_callback().unwrap().unwrap();
+        assert_eq!(callback.parse::<TimerHandler>(), Some(Ok(TimerHandler { timer_id: 1 })));
+        assert_eq!(callback.parse::<NoCallbacks>(), None);
+        assert_eq!(connection.request_callback(), Ok(None));
+
+        connection.ash_mut().stream_mut().queue_callback(StackStatusHandler::frame_id(), &[0x90]);
+        assert!(connection.request_callback().unwrap().is_some());
+        assert_eq!(connection.network_status(), NetworkStatus::JoinedNetwork);
+    }
+
+    #[test]
+    fn node_identity_and_randomness () {
+        let mut connection = connect();
+        connection.ash_mut().stream_mut().set_node_id(0x1234);
+
+        assert_eq!(connection.command(&GetEui64 {}).unwrap().eui64, connection.ash().stream().eui64());
+        assert_eq!(connection.command(&GetNodeId {}).unwrap().node_id, 0x1234);
+
+        let sent = connection.ash().stream().received().len();
+        let first = connection.random_number().unwrap();
+        assert_eq!(connection.ash().stream().received().len(), sent + 1);
+        assert_ne!(connection.random_number(), Ok(first));
+        assert_eq!(connection.entropy_source(), Ok(EntropySource::MbedTLSTRND.into()));
+    }
+
+    #[test]
+    fn timers_and_libraries () {
+        let mut connection = connect();
+
+        let timer = SetTimer { timer_id: 1, time: 250, units: EventUnits::QSTime, repeat: true };
+        connection.command(&timer).unwrap().status.into_result().unwrap();
+        assert_eq!(connection.ash().stream().timer(1), Some((250, EventUnits::QSTime, true)));
+        assert_eq!(connection.command(&GetTimer { timer_id: 1 }).unwrap().units, EventUnits::QSTime);
+        assert!(connection.command(&SetTimer { timer_id: 2, ..timer }).unwrap().status.into_result().is_err());
+
+        connection.ash_mut().stream_mut().emit_callback_with(TimerHandler::frame_id(), &TimerHandler { timer_id: 1 });
+        let callback = connection.poll_callback().unwrap().unwrap();
+        assert_eq!(callback.parse::<TimerHandler>(), Some(Ok(TimerHandler { timer_id: 1 })));
+
+        connection.ash_mut().stream_mut().set_library_status(12, 0x81);
+        let status = connection.command(&GetLibraryStatus { library_id: 12 }).unwrap().status;
+        assert!(status.is_present() && status.contains(LibraryStatusFlags::RouterCapability));
+        assert!(!connection.command(&GetLibraryStatus { library_id: 13 }).unwrap().status.is_present());
+    }
+}
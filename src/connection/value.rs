@@ -0,0 +1,151 @@
+//! Reading and writing NCP values.
+
+use crate::ash::ByteStream;
+use crate::codec::CodecError;
+use crate::ember::types::{LeaveReason, NodeId};
+use crate::error::Error;
+use crate::ezsp::types::{EndpointFlags, ExtendedValueID, ValueID};
+use crate::ezsp::value::{ExtendedValue, SourceRouteOverhead, Value};
+use crate::frames::{GetExtendedValue, GetValue, SetValue};
+
+use super::Connection;
+
+impl<S: ByteStream> Connection<S> {
+    /// Reads a value from the NCP, decoded according to its layout.
+    pub fn get_value(&mut self, value_id: ValueID) -> Result<Value, Error> {
+        let bytes = self.get_raw_value(value_id)?;
+        Ok(Value::decode(value_id, &bytes))
+    }
+
+    /// Reads the bytes of a value from the NCP.
+    pub fn get_raw_value(&mut self, value_id: ValueID) -> Result<Vec<u8>, Error> {
+        self.check_value(value_id)?;
+
+        let response = self.command(&GetValue { value_id })?;
+        response.status.into_result()?;
+        Ok(response.value)
+    }
+
+    /// Writes a value to the NCP.
+    ///
+    /// Fails with [`Error::InvalidValue`] without sending anything
+    /// if the value does not match the layout of `value_id`. Raw bytes
+    /// are sent as they are.
+    pub fn set_value(&mut self, value_id: ValueID, value: impl Into<Value>) -> Result<(), Error> {
+        let value = value.into();
+        if let Some(kind) = value.kind().filter(|_| !value_id.accepts(&value)) {
+            return Err(Error::InvalidValue { value_id, kind });
+        }
+        self.check_value(value_id)?;
+
+        self.command(&SetValue { value_id, value: value.to_bytes() })?.status.into_result()
+    }
+
+    /// Reads an extended value from the NCP, decoded according to its layout.
+    ///
+    /// The meaning of `characteristics` depends on the value,
+    /// e.g. the endpoint for [`ExtendedValueID::EndpointFlags`].
+    pub fn get_extended_value(&mut self, value_id: ExtendedValueID, characteristics: u32) -> Result<ExtendedValue, Error> {
+        let bytes = self.get_raw_extended_value(value_id, characteristics)?;
+        Ok(ExtendedValue::decode(value_id, &bytes))
+    }
+
+    /// Reads the bytes of an extended value from the NCP.
+    pub fn get_raw_extended_value(&mut self, value_id: ExtendedValueID, characteristics: u32) -> Result<Vec<u8>, Error> {
+        let response = self.command(&GetExtendedValue { value_id, characteristics })?;
+        response.status.into_result()?;
+        Ok(response.value)
+    }
+
+    /// Reads the flags of an endpoint.
+    pub fn endpoint_flags(&mut self, endpoint: u8) -> Result<EndpointFlags, Error> {
+        match self.get_extended_value(ExtendedValueID::EndpointFlags, endpoint.into())? {
+            ExtendedValue::EndpointFlags(flags) => Ok(flags),
+            _ => Err(Error::Codec(CodecError::InvalidValue)),
+        }
+    }
+
+    /// Reads why the node last left the network, and which node told it to.
+    pub fn last_leave_reason(&mut self) -> Result<(LeaveReason, NodeId), Error> {
+        match self.get_extended_value(ExtendedValueID::LastLEaveReason, 0)? {
+            ExtendedValue::LastLeaveReason { reason, node_id } => Ok((reason, node_id)),
+            _ => Err(Error::Codec(CodecError::InvalidValue)),
+        }
+    }
+
+    /// Reads the overhead of source routing to `destination`.
+    pub fn source_route_overhead(&mut self, destination: NodeId) -> Result<SourceRouteOverhead, Error> {
+        match self.get_extended_value(ExtendedValueID::GetSourceRouteOverhead, destination.into())? {
+            ExtendedValue::SourceRouteOverhead(overhead) => Ok(overhead),
+            _ => Err(Error::Codec(CodecError::InvalidValue)),
+        }
+    }
+
+    fn check_value(&self, value_id: ValueID) -> Result<(), Error> {
+        match self.capabilities() {
+            Some(capabilities) if !capabilities.supports_value(value_id) => {
+                Err(Error::UnsupportedValue { value_id, protocol_version: capabilities.protocol_version() })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connection::tests::{connect, connect_to};
+    use crate::ember::types::UNKNOWN_NODE_ID;
+    use crate::ezsp::types::Status;
+    use crate::ezsp::value::ValueKind;
+    use crate::simulator::SimulatedNcp;
+
+    #[test]
+    fn typed_values () {
+        let mut connection = connect();
+
+        assert_eq!(connection.get_value(ValueID::FreeBuffers), Ok(Value::U8(0xF0)));
+        assert_eq!(connection.get_value(ValueID::MaximumIncomingTransferSize), Ok(Value::U16(82)));
+
+        connection.set_value(ValueID::MaximumIncomingTransferSize, 128u16).unwrap();
+        assert_eq!(connection.ash().stream().value(ValueID::MaximumIncomingTransferSize), Some(&[128, 0][..]));
+
+        let sent = connection.ash().stream().received().len();
+        assert_eq!(
+            connection.set_value(ValueID::MaximumIncomingTransferSize, 128u8),
+            Err(Error::InvalidValue { value_id: ValueID::MaximumIncomingTransferSize, kind: ValueKind::U8 })
+        );
+        assert_eq!(connection.ash().stream().received().len(), sent);
+
+        connection.ash_mut().stream_mut().set_value(ValueID::TokenStackNodeData, &[1, 2, 3]);
+        connection.set_value(ValueID::TokenStackNodeData, vec![4, 5, 6]).unwrap();
+        assert_eq!(connection.get_value(ValueID::TokenStackNodeData), Ok(Value::Raw(vec![4, 5, 6])));
+        assert_eq!(connection.get_value(ValueID::NodeShortID), Err(Error::Ezsp(Status::ErrorInvalidID)));
+    }
+
+    #[test]
+    fn values_missing_from_the_negotiated_version () {
+        let mut connection = connect_to(SimulatedNcp::new().with_version(7, 7, 0x6500));
+
+        let sent = connection.ash().stream().received().len();
+        assert_eq!(
+            connection.get_value(ValueID::TransientKeyTimeoutS),
+            Err(Error::UnsupportedValue { value_id: ValueID::TransientKeyTimeoutS, protocol_version: 7 })
+        );
+        assert_eq!(connection.ash().stream().received().len(), sent);
+    }
+
+    #[test]
+    fn typed_extended_values () {
+        let mut connection = connect();
+        let ncp = connection.ash_mut().stream_mut();
+        ncp.set_extended_value(ExtendedValueID::EndpointFlags, 1, &[1, 0]);
+        ncp.set_extended_value(ExtendedValueID::LastLEaveReason, 0, &[1, 0xFD, 0xFF]);
+        ncp.set_extended_value(ExtendedValueID::GetSourceRouteOverhead, 0x1234, &[0xFF]);
+
+        assert_eq!(connection.endpoint_flags(1), Ok(EndpointFlags::Enabled));
+        assert_eq!(connection.endpoint_flags(2), Err(Error::Ezsp(Status::ErrorInvalidID)));
+        assert_eq!(connection.last_leave_reason(), Ok((LeaveReason::NwkLeaveMessage, UNKNOWN_NODE_ID)));
+        assert_eq!(connection.source_route_overhead(0x1234), Ok(SourceRouteOverhead::Unknown));
+    }
+}
@@ -0,0 +1,280 @@
+//! Channel masks and channel selection for the 2.4 GHz band.
+
+use core::ops::{BitAnd, BitOr, Not};
+
+use crate::codec::{CodecError, EzspDecode, EzspEncode, Reader, WireSize, Writer};
+use crate::error::Error;
+
+/// The lowest 2.4 GHz channel of channel page 0.
+pub const MIN_CHANNEL: u8 = 11;
+
+/// The highest 2.4 GHz channel of channel page 0.
+pub const MAX_CHANNEL: u8 = 26;
+
+/// Energy scan results above this RSSI, in dBm, mark a channel as busy.
+pub const BUSY_CHANNEL_RSSI: i8 = -75;
+
+/// A set of channels, one bit per channel number.
+///
+/// Zigbee masks carry the channel page in bits 27 to 31 and
+/// page 0 puts its sub-GHz channels in bits 0 to 10, so only
+/// masks within [`ChannelMask::ALL`] are accepted. Masks decoded
+/// from the NCP drop any bits outside it.
+#[derive(Copy, Clone, Eq, PartialEq, Default)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct ChannelMask(u32);
+
+impl ChannelMask {
+    /// No channels.
+    pub const EMPTY: ChannelMask = ChannelMask(0);
+
+    /// Channels 11 to 26.
+    pub const ALL: ChannelMask = ChannelMask(0x07FF_F800);
+
+    /// The channels Zigbee 3.0 devices scan first: 11, 15, 20 and 25.
+    pub const PREFERRED: ChannelMask = ChannelMask(1 << 11 | 1 << 15 | 1 << 20 | 1 << 25);
+
+    /// The channels scanned after the [preferred](ChannelMask::PREFERRED) ones.
+    pub const SECONDARY: ChannelMask = ChannelMask(Self::ALL.0 & !Self::PREFERRED.0);
+
+    /// Checks that a raw mask only selects channels 11 to 26.
+    ///
+    /// Fails with [`Error::InvalidChannelMask`] otherwise.
+    pub fn new(raw: u32) -> Result<Self, Error> {
+        if raw & !Self::ALL.0 == 0 {
+            Ok(ChannelMask(raw))
+        } else {
+            Err(Error::InvalidChannelMask(raw))
+        }
+    }
+
+    /// The mask selecting a single channel.
+    ///
+    /// Fails with [`Error::InvalidChannel`] for channels outside 11 to 26.
+    pub fn single(channel: u8) -> Result<Self, Error> {
+        check_channel(channel)?;
+        Ok(ChannelMask(1 << channel))
+    }
+
+    /// The mask selecting every channel in `channels`.
+    ///
+    /// Fails with [`Error::InvalidChannel`] on the first channel outside 11 to 26.
+    pub fn from_channels(channels: impl IntoIterator<Item = u8>) -> Result<Self, Error> {
+        channels.into_iter().try_fold(Self::EMPTY, |mask, channel| Ok(mask | Self::single(channel)?))
+    }
+
+    /// The raw mask, as sent to the NCP.
+    pub fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Whether the mask selects `channel`.
+    pub fn contains(self, channel: u8) -> bool {
+        (MIN_CHANNEL..=MAX_CHANNEL).contains(&channel) && self.0 & 1 << channel != 0
+    }
+
+    /// The number of channels selected.
+    pub fn len(self) -> usize {
+        (self.0 & Self::ALL.0).count_ones() as usize
+    }
+
+    /// Whether no channel is selected.
+    pub fn is_empty(self) -> bool {
+        self.len() == 0
+    }
+
+    /// The selected channels, in ascending order.
+    pub fn iter(self) -> impl Iterator<Item = u8> {
+        (MIN_CHANNEL..=MAX_CHANNEL).filter(move |channel| self.contains(*channel))
+    }
+}
+
+impl BitOr for ChannelMask {
+    type Output = ChannelMask;
+
+    fn bitor(self, other: ChannelMask) -> ChannelMask {
+        ChannelMask(self.0 | other.0)
+    }
+}
+
+impl BitAnd for ChannelMask {
+    type Output = ChannelMask;
+
+    fn bitand(self, other: ChannelMask) -> ChannelMask {
+        ChannelMask(self.0 & other.0)
+    }
+}
+
+impl Not for ChannelMask {
+    type Output = ChannelMask;
+
+    /// The channels of [`ChannelMask::ALL`] not in the mask.
+    fn not(self) -> ChannelMask {
+        ChannelMask(!self.0 & Self::ALL.0)
+    }
+}
+
+impl EzspEncode for ChannelMask {
+    fn encode(&self, writer: &mut Writer) -> Result<(), CodecError> {
+        self.0.encode(writer)
+    }
+}
+
+impl EzspDecode for ChannelMask {
+    fn decode(reader: &mut Reader) -> Result<Self, CodecError> {
+        Ok(ChannelMask(u32::decode(reader)? & Self::ALL.0))
+    }
+}
+
+impl WireSize for ChannelMask {
+    const WIRE_SIZE: usize = u32::WIRE_SIZE;
+}
+
+impl From<ChannelMask> for u32 {
+    fn from(mask: ChannelMask) -> u32 {
+        mask.0
+    }
+}
+
+impl TryFrom<u32> for ChannelMask {
+    type Error = Error;
+
+    fn try_from(raw: u32) -> Result<Self, Error> {
+        ChannelMask::new(raw)
+    }
+}
+
+/// Checks that `channel` is one of the 2.4 GHz channels 11 to 26.
+pub fn check_channel(channel: u8) -> Result<u8, Error> {
+    if (MIN_CHANNEL..=MAX_CHANNEL).contains(&channel) {
+        Ok(channel)
+    } else {
+        Err(Error::InvalidChannel(channel))
+    }
+}
+
+/// The energy measured and the networks seen on each channel
+/// of a mask, used to pick a channel to form a network on.
+#[derive(Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct ChannelSurvey {
+    mask: ChannelMask,
+    max_rssi: [Option<i8>; 16],
+    networks: [u8; 16],
+}
+
+impl ChannelSurvey {
+    /// Starts a survey of the channels in `mask`.
+    pub fn new(mask: ChannelMask) -> Self {
+        ChannelSurvey { mask, max_rssi: [None; 16], networks: [0; 16] }
+    }
+
+    /// The channels surveyed.
+    pub fn mask(&self) -> ChannelMask {
+        self.mask
+    }
+
+    /// Records an energy scan result. Channels outside the mask are ignored.
+    pub fn record_energy(&mut self, channel: u8, max_rssi: i8) {
+        if let Some(index) = self.index(channel) {
+            self.max_rssi[index] = Some(max_rssi);
+        }
+    }
+
+    /// Records a network found on `channel`. Channels outside the mask are ignored.
+    pub fn record_network(&mut self, channel: u8) {
+        if let Some(index) = self.index(channel) {
+            self.networks[index] = self.networks[index].saturating_add(1);
+        }
+    }
+
+    /// The highest energy measured on a channel, in dBm.
+    pub fn max_rssi(&self, channel: u8) -> Option<i8> {
+        self.max_rssi[self.index(channel)?]
+    }
+
+    /// The number of networks seen on a channel.
+    pub fn networks(&self, channel: u8) -> u8 {
+        self.index(channel).map_or(0, |index| self.networks[index])
+    }
+
+    /// The surveyed channels, best first.
+    ///
+    /// Busy channels, where the energy measured is above [`BUSY_CHANNEL_RSSI`]
+    /// or was not measured at all, come last. Channels are then ranked by the
+    /// number of networks seen, then by energy, preferring the
+    /// [preferred](ChannelMask::PREFERRED) channels and then lower channels on ties.
+    pub fn ranked(&self) -> Vec<u8> {
+        let mut channels: Vec<u8> = self.mask.iter().collect();
+        channels.sort_by_key(|channel| {
+            let max_rssi = self.max_rssi(*channel).unwrap_or(i8::MAX);
+            (
+                max_rssi > BUSY_CHANNEL_RSSI,
+                self.networks(*channel),
+                max_rssi,
+                !ChannelMask::PREFERRED.contains(*channel),
+                *channel,
+            )
+        });
+        channels
+    }
+
+    /// The best channel, or `None` if the mask is empty.
+    pub fn quietest(&self) -> Option<u8> {
+        self.ranked().first().copied()
+    }
+
+    fn index(&self, channel: u8) -> Option<usize> {
+        self.mask.contains(channel).then(|| usize::from(channel - MIN_CHANNEL))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::{EzspDecode, EzspEncode};
+
+    #[test]
+    fn masks () {
+        assert_eq!(ChannelMask::ALL.len(), 16);
+        assert_eq!(ChannelMask::PREFERRED.iter().collect::<Vec<_>>(), [11, 15, 20, 25]);
+        assert_eq!(ChannelMask::SECONDARY.len(), 12);
+        assert_eq!(!ChannelMask::PREFERRED, ChannelMask::SECONDARY);
+        assert_eq!(ChannelMask::PREFERRED | ChannelMask::SECONDARY, ChannelMask::ALL);
+        assert!(ChannelMask::EMPTY.is_empty());
+
+        assert_eq!(ChannelMask::from_channels([15, 20]).unwrap().bits(), 0x0010_8000);
+        assert_eq!(ChannelMask::from_channels([15, 27]), Err(Error::InvalidChannel(27)));
+        assert_eq!(ChannelMask::single(10), Err(Error::InvalidChannel(10)));
+        assert_eq!(ChannelMask::new(0x0800_0800), Err(Error::InvalidChannelMask(0x0800_0800)));
+        assert_eq!(ChannelMask::new(0x0000_0400), Err(Error::InvalidChannelMask(0x0000_0400)));
+        assert!(!ChannelMask::ALL.contains(10));
+
+        let mut buf = [0u8; 4];
+        assert_eq!(ChannelMask::PREFERRED.encode_to_slice(&mut buf), Ok(4));
+        assert_eq!(ChannelMask::decode_from_slice(&buf), Ok((ChannelMask::PREFERRED, 4)));
+
+        // Channel page bits and sub-GHz channels are dropped when decoding.
+        let (mask, _) = ChannelMask::decode_from_slice(&[0x00, 0x8C, 0x00, 0xF8]).unwrap();
+        assert_eq!(mask, ChannelMask::from_channels([11, 15]).unwrap());
+    }
+
+    #[test]
+    fn survey_ranks_quiet_channels_first () {
+        let mut survey = ChannelSurvey::new(ChannelMask::from_channels([11, 12, 15, 20, 25]).unwrap());
+        survey.record_energy(11, -50);
+        survey.record_energy(12, -92);
+        survey.record_energy(15, -90);
+        survey.record_energy(20, -92);
+        survey.record_energy(25, -85);
+        survey.record_energy(26, -100);
+        survey.record_network(12);
+        survey.record_network(25);
+
+        assert_eq!(survey.ranked(), [20, 15, 12, 25, 11]);
+        assert_eq!(survey.quietest(), Some(20));
+        assert_eq!(survey.networks(12), 1);
+        assert_eq!(survey.max_rssi(26), None);
+        assert_eq!(ChannelSurvey::new(ChannelMask::EMPTY).quietest(), None);
+    }
+}
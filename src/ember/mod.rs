@@ -1,3 +1,3 @@
 pub mod types;
 pub mod structs;
-
+pub mod channel;
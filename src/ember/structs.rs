@@ -0,0 +1,24 @@
+use crate::codec::EzspCodec;
+use super::types::{EUI64, NodeId};
+
+/// The number of sink list entries carried by GP proxy
+/// and sink table entries (hardcoded to the spec minimum).
+/// 
+/// GP_SINK_LIST_ENTRIES
+pub const GP_SINK_LIST_ENTRIES: usize = 2;
+
+/// A sink list entry.
+/// 
+/// EmberGpSinkListEntry
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct GpSinkListEntry {
+    /// The sink list type.
+    pub sink_type: u8,
+
+    /// The EUI64 of the target sink.
+    pub sink_eui: EUI64,
+
+    /// The short address of the target sink.
+    pub sink_node_id: NodeId,
+}
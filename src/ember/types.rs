@@ -1,5 +1,12 @@
 use bitmask::bitmask;
+use crate::codec::{EzspCodec, Recognized};
+use super::channel::ChannelMask;
+use super::structs::{GpSinkListEntry, GP_SINK_LIST_ENTRIES};
 
+mod codec;
+
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 #[repr(u16)]
 pub enum ConfigTXPowerMode {
     /// Normal power mode and bi-directional RF transmitter output.
@@ -18,6 +25,8 @@ pub enum ConfigTXPowerMode {
 /// Return type for stack functions.
 /// 
 /// https://www.silabs.com/documents/public/user-guides/ug100-ezsp-reference-guide.pdf (P.g. 33)
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 #[repr(u8)]
 pub enum Status {
     /// The generic 'no error' message.
@@ -673,6 +682,8 @@ pub enum Status {
 /// Either marks an event as inactive or specifies the units for the event execution time.
 /// 
 /// https://www.silabs.com/documents/public/user-guides/ug100-ezsp-reference-guide.pdf (P.g. 38)
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 #[repr(u8)]
 pub enum EventUnits {
     /// The event is not scheduled to run.
@@ -688,6 +699,8 @@ pub enum EventUnits {
 }
 
 /// The type of the node.
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 #[repr(u8)]
 pub enum NodeType {
     /// Device is not joined.
@@ -701,7 +714,7 @@ pub enum NodeType {
     /// Will relay messages and can act as a parent to other nodes.
     /// 
     /// EMBER_ROUTER 
-    Router = 002,
+    Router = 0x02,
     /// Communicates only with its parent and will not relay messages.
     /// 
     /// EMBER_END_DEVICE 
@@ -717,6 +730,8 @@ pub enum NodeType {
 /// The possible join states for a node.
 /// 
 /// EmberNetworkStatus
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 #[repr(u8)]
 pub enum NetworkStatus {
     /// The node is not associated with a network in any way.
@@ -739,12 +754,14 @@ pub enum NetworkStatus {
     /// The node is in the process of leaving its current network.
     /// 
     /// EMBER_LEAVING_NETWORK 
-    LeavingNetowrk = 004,
+    LeavingNetowrk = 0x04,
 }
 
 /// Incoming message types.
 /// 
 /// EmberIncomingMessageType 
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 #[repr(u8)]
 pub enum IncomingMessageType  {
     /// Unicast.
@@ -780,6 +797,8 @@ pub enum IncomingMessageType  {
 /// Outgoing message types.
 /// 
 /// EmberOutgoingMessageType 
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 #[repr(u8)]
 pub enum OutgoingMessageType {
     /// Unicast sent directly to an EmberNodeId.
@@ -789,7 +808,7 @@ pub enum OutgoingMessageType {
     /// Unicast sent using an entry in the address table.
     /// 
     /// EMBER_OUTGOING_VIA_ADDRESS_TABLE 
-    ViaAddressTable = 001,
+    ViaAddressTable = 0x01,
     /// Unicast sent using an entry in the binding table.
     /// 
     /// EMBER_OUTGOING_VIA_BINDING 
@@ -815,6 +834,8 @@ pub enum OutgoingMessageType {
 /// MAC passthrough message type flags.
 /// 
 /// EmberMacPassthroughType 
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 #[repr(u8)]
 pub enum MacPassthroughType  {
     /// No MAC passthrough messages.
@@ -838,6 +859,8 @@ pub enum MacPassthroughType  {
 /// Binding types.
 /// 
 /// EmberBindingType 
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 #[repr(u8)]
 pub enum BindingType {
     /// A binding that is currently not in use.
@@ -863,7 +886,7 @@ pub enum BindingType {
 
 bitmask! {
     /// Options to use when sending a message.
-    mask ApsOption: u16 where
+    pub mask ApsOption: u16 where
     /// Options to use when sending a message.
     flags ApsOptionFlags {
         /// No options.
@@ -933,6 +956,8 @@ bitmask! {
 /// Decision made by the trust center when a node attempts to join.
 /// 
 /// EmberJoinDecision
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 #[repr(u8)]
 pub enum JoinDecision {
     /// Allow the node to join.
@@ -964,7 +989,7 @@ bitmask! {
     /// the use of various security features.
     /// 
     /// EmberInitialSecurityBitmask 
-    mask InitialSecurityBitmask: u16 where
+    pub mask InitialSecurityBitmask: u16 where
     /// This is the Initial Security Bitmask that controls
     /// the use of various security features.
     /// 
@@ -1083,7 +1108,7 @@ bitmask! {
     /// the use of various security features.
     /// 
     /// EmberCurrentSecurityBitmask 
-    mask CurrentSecurityBitmask: u16 where
+    pub mask CurrentSecurityBitmask: u16 where
     /// This is the Current Security Bitmask that details
     /// the use of various security features.
     /// 
@@ -1119,6 +1144,8 @@ bitmask! {
 /// Describes the type of ZigBee security key.
 /// 
 /// EmberKeyType 
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 #[repr(u8)]
 pub enum KeyType {
     /// A shared key between the Trust Center and a device.
@@ -1145,7 +1172,7 @@ bitmask! {
     /// the EmberKeyStruct structure.
     /// 
     /// EmberKeyStructBitmask 
-    mask KeyStructBitmask: u16 where
+    pub mask KeyStructBitmask: u16 where
     /// Describes the presence of valid data within
     /// the EmberKeyStruct structure.
     /// 
@@ -1173,6 +1200,8 @@ bitmask! {
 /// The status of the device update.
 /// 
 /// EmberDeviceUpdate
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 #[repr(u8)]
 pub enum DeviceUpdate {
     StandardSecuritySecuredRejoin = 0x0,
@@ -1184,6 +1213,8 @@ pub enum DeviceUpdate {
 /// The status of the attempt to establish a key.
 /// 
 /// EmberKeyStatus 
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 #[repr(u8)]
 pub enum KeyStatus {
     /// EMBER_APP_LINK_KEY_ESTABLISHED 
@@ -1221,6 +1252,8 @@ pub enum KeyStatus {
 }
 
 /// Defines the events reported to the application by the readAndClearCounters command.
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 #[repr(u8)]
 pub enum CounterType {
     /// The MAC received a broadcast.
@@ -1414,6 +1447,8 @@ pub enum CounterType {
 /// The type of method used for joining.
 /// 
 /// EmberJoinMethod 
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 #[repr(u8)]
 pub enum JoinMethod {
     /// Normally devices use MAC Association to join a network, which
@@ -1464,6 +1499,8 @@ pub enum JoinMethod {
 /// 
 /// EmberZdoConfigurationFlags 
 // TODO: is bitmask?
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 #[repr(u8)]
 pub enum ZdoConfigurationFlags {
     /// Set this flag in order to receive supported ZDO request
@@ -1520,6 +1557,8 @@ pub enum ZdoConfigurationFlags {
 /// Type of concentrator.
 /// 
 /// EmberConcentratorType 
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 #[repr(u16)]
 pub enum ConcentratorType {
     /// A concentrator with insufficient memory to store source
@@ -1538,41 +1577,47 @@ pub enum ConcentratorType {
     HighRAM = 0xFFF9
 }
 
-/// ZLL device state identifier.
-/// 
-/// EmberZllState 
-// TODO: is bitmask?
-#[repr(u16)]
-pub enum ZLLState {
-    /// No state.
-    /// 
-    /// EMBER_ZLL_STATE_NONE 
-    None = 0x0000,
-    /// The device is factory new.
-    /// 
-    /// EMBER_ZLL_STATE_FACTORY_NEW 
-    FactoryNew = 0x0001,
-    /// The device is capable of assigning addresses to other devices.
-    /// 
-    /// EMBER_ZLL_STATE_ADDRESS_ASSIGNMENT_CAPABLE 
-    AddressAssignmentCapable = 0x0002,
-    /// The device is initiating a link operation.
-    /// 
-    /// EMBER_ZLL_STATE_LINK_INITIATOR 
-    LinkInitiator = 0x0010,
-    /// The device is requesting link priority.
+bitmask! {
+    /// ZLL device state identifier.
     /// 
-    /// EMBER_ZLL_STATE_LINK_PRIORITY_REQUEST 
-    LinkPriorityRequest = 0x0020,
-    /// The device is on a non-ZLL network.
+    /// EmberZllState 
+    pub mask ZLLState: u16 where
+    /// ZLL device state identifier.
     /// 
-    /// EMBER_ZLL_STATE_NON_ZLL_NETWORK 
-    NonZLLNetwork = 0x0100
+    /// EmberZllState 
+    flags ZLLStateFlags {
+        /// No state.
+        /// 
+        /// EMBER_ZLL_STATE_NONE 
+        None = 0x0000,
+        /// The device is factory new.
+        /// 
+        /// EMBER_ZLL_STATE_FACTORY_NEW 
+        FactoryNew = 0x0001,
+        /// The device is capable of assigning addresses to other devices.
+        /// 
+        /// EMBER_ZLL_STATE_ADDRESS_ASSIGNMENT_CAPABLE 
+        AddressAssignmentCapable = 0x0002,
+        /// The device is initiating a link operation.
+        /// 
+        /// EMBER_ZLL_STATE_LINK_INITIATOR 
+        LinkInitiator = 0x0010,
+        /// The device is requesting link priority.
+        /// 
+        /// EMBER_ZLL_STATE_LINK_PRIORITY_REQUEST 
+        LinkPriorityRequest = 0x0020,
+        /// The device is on a non-ZLL network.
+        /// 
+        /// EMBER_ZLL_STATE_NON_ZLL_NETWORK 
+        NonZLLNetwork = 0x0100
+    }
 }
 
 /// ZLL key encryption algorithm enumeration.
 /// 
 /// EmberZllKeyIndex
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 #[repr(u8)]
 pub enum ZLLKeyIndex {
     /// Key encryption algorithm for use during development.
@@ -1582,7 +1627,7 @@ pub enum ZLLKeyIndex {
     /// Key encryption algorithm shared by all certified devices.
     /// 
     /// EMBER_ZLL_KEY_INDEX_MASTER 
-    Master = 004,
+    Master = 0x04,
     /// Key encryption algorithm for use during development and certification.
     /// 
     /// EMBER_ZLL_KEY_INDEX_CERTIFICATION 
@@ -1593,7 +1638,7 @@ bitmask! {
     /// Bitmask options for emberNetworkInit().
     /// 
     /// EmberNetworkInitBitmask 
-    mask NetworkInitBitmask: u16 where
+    pub mask NetworkInitBitmask: u16 where
     /// Bitmask options for emberNetworkInit().
     /// 
     /// EmberNetworkInitBitmask 
@@ -1619,6 +1664,8 @@ bitmask! {
 /// radio interface for multi-phy network.
 /// 
 /// EmberMultiPhyNwkConfig
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 #[repr(u8)]
 pub enum MultiPHYNWKConfig {
     /// Enable broadcast support on Routers
@@ -1630,6 +1677,8 @@ pub enum MultiPHYNWKConfig {
 /// Duty cycle states.
 /// 
 /// EmberDutyCycleState
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 #[repr(u8)]
 pub enum DutyCycleState {
     /// No Duty cycle tracking or metrics are taking place.
@@ -1660,6 +1709,8 @@ pub enum DutyCycleState {
 /// Radio power modes.
 /// 
 /// EmberRadioPowerMode
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 #[repr(u8)]
 pub enum RadioPowerMode {
     /// The radio receiver is switched on.
@@ -1675,6 +1726,8 @@ pub enum RadioPowerMode {
 /// Entropy sources.
 /// 
 /// EmberEntropySource
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 #[repr(u8)]
 pub enum EntropySource {
     /// Entropy source error.
@@ -1695,15 +1748,197 @@ pub enum EntropySource {
     MbedTLS = 3
 }
 
+bitmask! {
+    /// The presence and status of an Ember library.
+    /// 
+    /// The meaning of the low bits depends on the library,
+    /// and a status of EMBER_LIBRARY_ERROR (0xFF) means the
+    /// library ID is not known.
+    /// 
+    /// EmberLibraryStatus 
+    pub mask LibraryStatus: u8 where
+    /// The presence and status of an Ember library.
+    /// 
+    /// EmberLibraryStatus 
+    flags LibraryStatusFlags {
+        /// The library is present, otherwise only its stub is.
+        /// 
+        /// EMBER_LIBRARY_PRESENT_MASK 
+        Present = 0x80,
+        /// The ZigBee Pro, ZLL or security library supports routers,
+        /// or the packet validate library is enabled.
+        /// 
+        /// EMBER_ZIGBEE_PRO_LIBRARY_HAVE_ROUTER_CAPABILITY 
+        /// EMBER_ZLL_LIBRARY_HAVE_ROUTER_CAPABILITY 
+        /// EMBER_SECURITY_LIBRARY_HAVE_ROUTER_SUPPORT 
+        /// EMBER_PACKET_VALIDATE_LIBRARY_ENABLED 
+        RouterCapability = 0x01,
+    }
+}
+
+impl LibraryStatus {
+    /// The status reported for an unknown library.
+    /// 
+    /// EMBER_LIBRARY_ERROR 
+    pub const ERROR: u8 = 0xFF;
+
+    /// Whether the NCP did not recognize the library ID.
+    pub fn is_error(&self) -> bool {
+        **self == Self::ERROR
+    }
+
+    /// Whether the library is present rather than a stub.
+    pub fn is_present(&self) -> bool {
+        !self.is_error() && self.contains(LibraryStatusFlags::Present)
+    }
+
+    /// Whether the library supports routers, or is enabled for the packet validate library.
+    pub fn has_router_capability(&self) -> bool {
+        !self.is_error() && self.contains(LibraryStatusFlags::RouterCapability)
+    }
+}
+
+/// The reason a node left the network.
+/// 
+/// EmberLeaveReason
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
+#[repr(u8)]
+pub enum LeaveReason {
+    /// The node has not left the network.
+    /// 
+    /// EMBER_LEAVE_REASON_NONE 
+    None = 0,
+    /// A network leave message was received.
+    /// 
+    /// EMBER_LEAVE_DUE_TO_NWK_LEAVE_MESSAGE 
+    NwkLeaveMessage = 1,
+    /// An APS remove device command was received.
+    /// 
+    /// EMBER_LEAVE_DUE_TO_APS_REMOVE_MESSAGE 
+    ApsRemoveMessage = 2,
+    /// A ZDO leave request was received.
+    /// 
+    /// EMBER_LEAVE_DUE_TO_ZDO_LEAVE_MESSAGE 
+    ZdoLeaveMessage = 3,
+    /// The node was reset by a ZLL touchlink.
+    /// 
+    /// EMBER_LEAVE_DUE_TO_ZLL_TOUCHLINK 
+    ZllTouchlink = 4,
+    /// The application told the node to leave.
+    /// 
+    /// EMBER_LEAVE_DUE_TO_APP_EVENT_1 
+    AppEvent1 = 0xFF
+}
+
+/// The state of a route table entry.
+/// 
+/// EmberRouteStatus
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
+#[repr(u8)]
+pub enum RouteStatus {
+    /// The route is in use.
+    /// 
+    /// EMBER_ROUTE_ACTIVE
+    Active = 0,
+    /// A route discovery for the destination is in progress.
+    /// 
+    /// EMBER_ROUTE_BEING_DISCOVERED
+    BeingDiscovered = 1,
+    /// The entry is unused.
+    /// 
+    /// EMBER_ROUTE_UNUSED
+    Unused = 3,
+    /// The route is being validated.
+    /// 
+    /// EMBER_ROUTE_VALIDATING
+    Validating = 4,
+}
+
+/// Whether a route table destination is a concentrator, and which kind.
+/// 
+/// EmberRouteConcentratorType
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
+#[repr(u8)]
+pub enum RouteConcentratorType {
+    /// The destination is not a concentrator.
+    /// 
+    /// EMBER_ROUTE_NOT_CONCENTRATOR
+    NotConcentrator = 0,
+    /// The destination is a low RAM concentrator.
+    /// 
+    /// EMBER_ROUTE_LOW_RAM_CONCENTRATOR
+    LowRAM = 1,
+    /// The destination is a high RAM concentrator.
+    /// 
+    /// EMBER_ROUTE_HIGH_RAM_CONCENTRATOR
+    HighRAM = 2,
+}
+
+/// Whether a high RAM concentrator still needs a route record from this node.
+/// 
+/// EmberRouteRecordState
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
+#[repr(u8)]
+pub enum RouteRecordState {
+    /// A source routed message from the concentrator was received,
+    /// so no route record is needed.
+    /// 
+    /// EMBER_ROUTE_RECORD_NO_LONGER_NEEDED
+    NoLongerNeeded = 0,
+    /// A route record was sent.
+    /// 
+    /// EMBER_ROUTE_RECORD_SENT
+    Sent = 1,
+    /// A route record needs to be sent.
+    /// 
+    /// EMBER_ROUTE_RECORD_NEEDED
+    Needed = 2,
+}
+
 // TODO: sl_zb_sec_man_key_type_t
 
 // TODO: sl_zb_sec_man_derived_key_type_t
 
 // TODO: sl_zigbee_sec_man_flags_t
 
-// 16-bit ID of a node in the network.
+/// 16-bit ID of a node in the network.
 pub type NodeId = u16;
 
+/// The node ID reported when the node is not known,
+/// e.g. when it left the network due to a local API call.
+/// 
+/// EMBER_UNKNOWN_NODE_ID
+pub const UNKNOWN_NODE_ID: NodeId = 0xFFFD;
+
+/// The node ID meaning no node, e.g. when a lookup finds none.
+/// 
+/// EMBER_NULL_NODE_ID
+pub const NULL_NODE_ID: NodeId = 0xFFFF;
+
+/// The node ID of an unused address table entry.
+/// 
+/// EMBER_TABLE_ENTRY_UNUSED_NODE_ID
+pub const TABLE_ENTRY_UNUSED_NODE_ID: NodeId = 0xFFFF;
+
+/// Broadcasts to routers and the coordinator.
+/// 
+/// EMBER_BROADCAST_ADDRESS
+pub const BROADCAST_ADDRESS: NodeId = 0xFFFC;
+
+/// Broadcasts to every node whose receiver is on when idle.
+/// 
+/// EMBER_RX_ON_WHEN_IDLE_BROADCAST_ADDRESS
+pub const RX_ON_WHEN_IDLE_BROADCAST_ADDRESS: NodeId = 0xFFFD;
+
+/// Broadcasts to every node, including sleepy end devices.
+/// 
+/// EMBER_SLEEPY_BROADCAST_ADDRESS
+pub const SLEEPY_BROADCAST_ADDRESS: NodeId = 0xFFFF;
+
 /// Zigbee 802.15.4 network PAN ID.
 pub type PanId = u16;
 
@@ -1724,8 +1959,6 @@ pub type DutyCycleHectoPct = u16;
 /// A library identifier.
 pub type LibraryID  = u8;
 
-/// The presence and status of the Ember library.
-pub type LibraryStatus = u8;
 
 /// The security level of the GPD.
 pub type GpSecurityLevel  = u8;
@@ -1734,43 +1967,73 @@ pub type GpSecurityLevel  = u8;
 pub type GpKeyType = u8;
 
 /// The GPD proxy table status entry.
-pub type GpProxyTableEntryStatus  = u8;
+/// 
+/// EmberGpProxyTableEntryStatus
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
+#[repr(u8)]
+pub enum GpProxyTableEntryStatus {
+    /// The entry is in use.
+    /// 
+    /// EMBER_GP_PROXY_TABLE_ENTRY_STATUS_ACTIVE 
+    Active = 0x01,
+    /// The entry is free.
+    /// 
+    /// EMBER_GP_PROXY_TABLE_ENTRY_STATUS_UNUSED 
+    Unused = 0xFF
+}
 
 /// The GPD security frame counter.
 pub type GpSecurityFrameCounter = u32;
 
 /// The GPD sink table entry status.
-pub type GpSinkTableEntryStatus = u8;
+/// 
+/// EmberGpSinkTableEntryStatus
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
+#[repr(u8)]
+pub enum GpSinkTableEntryStatus {
+    /// The entry is in use.
+    /// 
+    /// EMBER_GP_SINK_TABLE_ENTRY_STATUS_ACTIVE 
+    Active = 0x01,
+    /// The entry is free.
+    /// 
+    /// EMBER_GP_SINK_TABLE_ENTRY_STATUS_UNUSED 
+    Unused = 0xFF
+}
 
 
-pub struct NetworkParameters  {
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct NetworkParameters {
     /// The network's extended PAN identifier.
-    extended_pan_id: [u8; 8],
+    pub extended_pan_id: [u8; 8],
 
     /// The network's PAN identifier.
-    pan_id: u16,
+    pub pan_id: u16,
 
     /// A power setting, in dBm.
-    radio_tx_power: u8,
+    pub radio_tx_power: i8,
 
     /// A radio channel.
-    radio_channel: u8,
+    pub radio_channel: u8,
 
     /// The method used to initially join the network.
-    join_method: JoinMethod,
+    pub join_method: Recognized<JoinMethod>,
 
     /// The ID of the network manager in the current network.
     /// 
     /// This may only be set at joining when using
     /// EMBER_USE_CONFIGURED_NWK_STATE as the join method.
-    network_manager_id: NodeId,
+    pub network_manager_id: NodeId,
 
     /// This is used to determine the newest instance of the
     /// network after a PAN ID or channel change.
     /// 
     /// This may only be set at joining when using
     /// EMBER_USE_CONFIGURED_NWK_STATE as the join method.
-    network_update_id: u8,
+    pub network_update_id: u8,
 
     /// The network channel mask.
     /// 
@@ -1781,221 +2044,263 @@ pub struct NetworkParameters  {
     /// This may only be set at joining when using
     /// EMBER_USE_CONFIGURED_NWK_STATE as the join
     /// method.
-    channels: u32
+    pub channels: ChannelMask
 }
 
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 pub struct MultiPhyRadioParameters {
     /// A power setting, in dBm.
-    radio_tx_power: i8,
+    pub radio_tx_power: i8,
 
     /// A radio page.
-    radio_page: u8,
+    pub radio_page: u8,
 
     /// A radio channel.
-    radio_channel: u8,
+    pub radio_channel: u8,
 }
 
-pub struct ZigbeeNetwork  {
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct ZigbeeNetwork {
     /// The 802.15.4 channel associated with the network.
-    channel: u8,
+    pub channel: u8,
 
     /// The network's PAN identifier.
-    pan_id: u16,
+    pub pan_id: u16,
 
     /// The network's extended PAN identifier.
-    extended_pan_id: [u8;8],
+    pub extended_pan_id: [u8;8],
     
     /// Whether the network is allowing MAC associations.
-    allowing_join: bool,
+    pub allowing_join: bool,
 
     /// The Stack Profile associated with the network.
-    stack_profile: u8,
+    pub stack_profile: u8,
 
     /// The instance of the Network.
-    network_update_id: u8,
+    pub network_update_id: u8,
 }
 
-pub struct ApsFrame  {
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct ApsFrame {
     /// The application profile ID that
     /// describes the format of the message.
-    profile_id: u16,
+    pub profile_id: u16,
 
     /// The cluster ID for this message.
-    cluster_id: u16,
+    pub cluster_id: u16,
 
     /// The source endpoint.
-    source_endpoint: u8,
+    pub source_endpoint: u8,
 
     /// The destination endpoint.
-    destination_endpoint: u8,
+    pub destination_endpoint: u8,
 
     /// A bitmask of options.
-    options: ApsOption,
+    pub options: ApsOption,
 
     /// The group ID for this message, if it is multicast mode.
-    group_id: u16,
+    pub group_id: u16,
 
     /// The sequence number.
-    sequence: u8,
+    pub sequence: u8,
 }
 
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 pub struct BindingTableEntry {
     /// The type of binding.
-    binding_type: BindingType,
+    pub binding_type: Recognized<BindingType>,
 
     /// The endpoint on the local node.
-    local: u8,
+    pub local: u8,
 
     /// A cluster ID that matches one from the local endpoint's simple descriptor. 
-    cluster_id: u16,
+    pub cluster_id: u16,
 
     /// The endpoint on the remote node (specified by identifier).
-    remote: u8,
+    pub remote: u8,
 
     /// A 64-bit identifier. This is either the destination EUI64
     /// (for unicasts) or the 64-bit group address (for multicasts).
-    identifier: EUI64,
+    pub identifier: EUI64,
 
     /// The index of the network the binding belongs to.
-    network_index: u8,
+    pub network_index: u8,
 }
 
-pub struct MulticastTableEntry  {
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct MulticastTableEntry {
     /// The multicast group ID.
-    multicast_id: MulticastId,
+    pub multicast_id: MulticastId,
 
     /// The endpoint that is a member, or 0 if this entry is not
     /// in use (the ZDO is not a member of any multicast groups.)
-    endpoint: u8,
+    pub endpoint: u8,
 
     /// The network index of the network the entry is related to.
-    network_index: u8,
+    pub network_index: u8,
 }
 
 /// A 128-bit key.
-pub struct KeyData([u8; 16]);
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct KeyData(pub [u8; 16]);
 
 /// The implicit certificate used in CBKE.
-pub struct CertificateData ([u8;48]);
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct CertificateData(pub [u8;48]);
 
 /// The public key data used in CBKE.
-pub struct PublicKeyData  ([u8; 22]);
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct PublicKeyData(pub [u8; 22]);
 
 /// The private key data used in CBKE.
-pub struct PrivateKeyData ([u8; 21]);
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct PrivateKeyData(pub [u8; 21]);
 
 /// The Shared Message Authentication Code data used in CBKE.
-pub struct SMACData ([u8;16]);
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct SMACData(pub [u8;16]);
 
 /// An ECDSA signature
-pub struct SignatureData ([u8;42]);
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct SignatureData(pub [u8;42]);
 
 /// The implicit certificate used in CBKE.
-pub struct Certificate283k1Data ([u8; 74]);
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Certificate283k1Data(pub [u8; 74]);
 
 /// The public key data used in CBKE.
-pub struct PublicKey283k1Data ([u8; 37]);
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct PublicKey283k1Data(pub [u8; 37]);
 
 /// The private key data used in CBKE.
-pub struct PrivateKey283k1Data ([u8; 36]);
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct PrivateKey283k1Data(pub [u8; 36]);
 
 /// An ECDSA signature
-pub struct Signature283k1Data ([u8; 72]);
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Signature283k1Data(pub [u8; 72]);
 
 /// The calculated digest of a message
-pub struct MessageDigest  ([u8; 16]);
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct MessageDigest(pub [u8; 16]);
 
 /// The hash context for an ongoing hash operation.
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 pub struct AesMmoHashContext {
     /// The result of ongoing the hash operation.
-    result: [u8; 16],
+    pub result: [u8; 16],
 
     /// The total length of the data that has been hashed so far.
-    length: u32
+    pub length: u32
 }
 
 /// Beacon data structure.
-pub struct BeaconData  {
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct BeaconData {
     /// The channel of the received beacon.
-    channel: u8,
+    pub channel: u8,
 
     /// The LQI of the received beacon.
-    lqi : u8,
+    pub lqi: u8,
 
     /// The RSSI of the received beacon.
-    rssi: i8,
+    pub rssi: i8,
 
     /// The depth of the received beacon.
-    depth: u8,
+    pub depth: u8,
 
     /// The network update ID of the received beacon.
-    network_update_id: u8,
+    pub network_update_id: u8,
 
     /// The power level of the received beacon.
     /// 
     /// This field is valid only if the beacon is an enhanced beacon.
-    power: i8,
+    pub power: i8,
 
     /// The TC connectivity and long uptime from capacity field.
-    parent_priority: i8,
+    pub parent_priority: i8,
 
     /// The PAN ID of the received beacon.
-    pan_id: PanId,
+    pub pan_id: PanId,
 
     /// The extended PAN ID of the received beacon.
-    extended_pan_id: [u8; 8],
+    pub extended_pan_id: [u8; 8],
 
     /// The sender of the received beacon.
-    sender: NodeId,
+    pub sender: NodeId,
 
     /// Whether or not the beacon is enhanced.
-    enhanced: bool,
+    pub enhanced: bool,
 
     /// Whether the beacon is advertising permit join.
-    permit_join: bool,
+    pub permit_join: bool,
 
     /// Whether the beacon is advertising capacity.
-    has_capacity: bool,
+    pub has_capacity: bool,
 }
 
 /// Defines an iterator that is used to loop over cached beacons.
 /// 
 /// Do not write to fields denoted as Private.
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 pub struct BeaconIterator {
     /// The retrieved beacon.
-    beacon: BeaconData,
+    pub beacon: BeaconData,
 
     /// (Private) The index of the retrieved beacon.
-    index: u8
+    pub index: u8
 }
 
 /// Parameters related to beacon prioritization.
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 pub struct BeaconClassificationParams {
     /// The minimum RSSI value for receiving packets that
     /// is used in some beacon prioritization algorithms.
-    min_rssi_for_receiving_pkts: i8,
+    pub min_rssi_for_receiving_pkts: i8,
 
     /// The beacon classification mask that identifies which
     /// beacon prioritization algorithm to pick and defines
     /// the relevant parameters.
-    beacon_classification_mask: u16,
+    pub beacon_classification_mask: u16,
 }
 
 /// A neighbor table entry stores information about the
 /// reliability of RF links to and from neighboring nodes.
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 pub struct NeighborTableEntry {
     /// The neighbor's two-byte network id
-    short_id: u16,
+    pub short_id: u16,
 
     /// An exponentially weighted moving average of the
     /// link quality values of incoming packets from this
     /// neighbor as reported by the PHY.
-    average_lqi: u8,
+    pub average_lqi: u8,
 
     /// The incoming cost for this neighbor, computed from
     /// the average LQI. Values range from 1 for a good link
     /// to 7 for a bad link.
-    in_cost: u8,
+    pub in_cost: u8,
 
     /// The outgoing cost for this neighbor, obtained from
     /// the most recently received neighbor exchange
@@ -2004,63 +2309,67 @@ pub struct NeighborTableEntry {
     /// neighbor has not been received recently enough, or
     /// that our id was not present in the most recently
     /// received one.
-    out_cost: u8,
+    pub out_cost: u8,
 
     /// The number of aging periods elapsed since a link
     /// status message was last received from this
     /// neighbor. The aging period is 16 seconds.
-    age: u8,
+    pub age: u8,
 
     /// The 8-byte EUI64 of the neighbor.
-    long_id: EUI64
+    pub long_id: EUI64
 }
 
 /// A route table entry stores information about the next
 /// hop along the route to the destination.
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 pub struct RouteTableEntry {
     /// The short id of the destination.
     /// 
     /// A value of 0xFFFF indicates the entry is unused.
-    destination: u16,
+    pub destination: u16,
 
     /// The short id of the next hop to this destination.
-    next_hop: u16,
+    pub next_hop: u16,
 
-    /// Indicates whether this entry is active (0), being
-    /// discovered (1), unused (3), or validating (4).
-    status: u8,
+    /// Indicates whether this entry is active, being
+    /// discovered, unused, or validating.
+    pub status: Recognized<RouteStatus>,
 
     /// The number of seconds since this route entry was
     /// last used to send a packet.
-    age: u8,
+    pub age: u8,
 
     /// Indicates whether this destination is a High RAM
-    /// Concentrator (2), a Low RAM Concentrator (1), or
-    /// not a concentrator (0).
-    concentrator_type: u8,
+    /// Concentrator, a Low RAM Concentrator, or
+    /// not a concentrator.
+    pub concentrator_type: Recognized<RouteConcentratorType>,
 
     /// For a High RAM Concentrator, indicates whether a
-    /// route record is needed (2), has been sent (1), or is
-    /// no long needed (0) because a source routed
+    /// route record is needed, has been sent, or is
+    /// no long needed because a source routed
     /// message from the concentrator has been received.
-    route_record_state: u8,
+    pub route_record_state: Recognized<RouteRecordState>,
 }
 
 /// The security data used to set the configuration for
 /// the stack, or the retrieved configuration currently in
 /// use.
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 pub struct InitialSecurityState {
     /// A bitmask indicating the security state used to
     /// indicate what the security configuration will be when
     /// the device forms or joins the network.
-    bitmask : InitialSecurityBitmask,
+    pub bitmask: InitialSecurityBitmask,
 
     /// The pre-configured Key data that should be used
     /// when forming or joining the network. The security
     /// bitmask must be set with the
     /// EMBER_HAVE_PRECONFIGURED_KEY bit to
     /// indicate that the key contains valid data.
-    preconfigured_key: KeyData,
+    pub preconfigured_key: KeyData,
 
     /// The Network Key that should be used by the Trust
     /// Center when it forms the network, or the Network
@@ -2069,13 +2378,13 @@ pub struct InitialSecurityState {
     /// The security bitmask must be set with
     /// EMBER_HAVE_NETWORK_KEY to indicate that
     /// the key contains valid data.
-    network_key: KeyData,
+    pub network_key: KeyData,
 
     /// The sequence number associated with the network key.
     /// 
     /// This is only valid if the EMBER_HAVE_NETWORK_KEY has
     /// been set in the security bitmask.
-    network_key_sequence_number: u8,
+    pub network_key_sequence_number: u8,
 
     /// This is the long address of the trust center on the
     /// network that will be joined.
@@ -2089,195 +2398,215 @@ pub struct InitialSecurityState {
     /// Most devices should clear that bit and leave this field alone.
     /// 
     /// This field must be set when using commissioning mode.
-    preconfigured_trust_center_eui: EUI64
+    pub preconfigured_trust_center_eui: EUI64
 } 
 
 /// The security options and information currently used by the stack.
-pub struct CurrentSecurityState  {
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct CurrentSecurityState {
     /// A bitmask indicating the security options currently in
     /// use by a device joined in the network.
-    bitmask: CurrentSecurityBitmask,
+    pub bitmask: CurrentSecurityBitmask,
 
     /// The IEEE Address of the Trust Center device.
-    trust_center_long_address: EUI64,
+    pub trust_center_long_address: EUI64,
 }
 
 /// A structure containing a key and its associated data.
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 pub struct KeyStruct {
     /// A bitmask indicating the presence of data within the 
     /// various fields in the structure.
-    bitmask : KeyStructBitmask,
+    pub bitmask: KeyStructBitmask,
 
     /// The type of the key.
-    key_type: KeyType,
+    pub key_type: Recognized<KeyType>,
 
     /// The actual key data.
-    key: KeyData,
+    pub key: KeyData,
 
     /// The outgoing frame counter associated with the key.
-    outgoing_frame_counter: u32,
+    pub outgoing_frame_counter: u32,
 
     /// The frame counter of the partner device associated with the key.
-    incoming_frame_counter: u32,
+    pub incoming_frame_counter: u32,
 
     /// The sequence number associated with the key.
-    sequence_number: u8,
+    pub sequence_number: u8,
 
     /// The IEEE address of the partner device also in
     /// possession of the key.
-    partner_eui64: EUI64
+    pub partner_eui64: EUI64
 }
 
 /// Network Initialization parameters.
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 pub struct NetworkInitStruct {
     /// Configuration options for network init.
-    bitmask: NetworkInitBitmask,
+    pub bitmask: NetworkInitBitmask,
 }
 
 /// Data associated with the ZLL security algorithm.
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 pub struct ZllSecurityAlgorithmData {
     /// Transaction identifier.
-    transaction_id: u32,
+    pub transaction_id: u32,
 
     /// Response identifier.
-    response_id: u32,
+    pub response_id: u32,
 
     /// Bitmask.
-    bitmask: u16,
+    pub bitmask: u16,
 }
 
 /// The parameters of a ZLL network.
-pub struct ZllNetwork  {
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct ZllNetwork {
     /// The parameters of a ZigBee network.
-    zigbee_network: ZigbeeNetwork,
+    pub zigbee_network: ZigbeeNetwork,
 
     /// Data associated with the ZLL security algorithm.
-    security_algorithm: ZllSecurityAlgorithmData,
+    pub security_algorithm: ZllSecurityAlgorithmData,
 
     /// Associated EUI64.
-    eui64: EUI64,
+    pub eui64: EUI64,
 
     /// The node id.
-    node_id: NodeId,
+    pub node_id: NodeId,
 
     /// The ZLL state.
-    state: ZLLState,
+    pub state: ZLLState,
 
     /// The node type.
-    node_type : NodeType,
+    pub node_type: Recognized<NodeType>,
 
     /// The number of sub devices.
-    number_sub_devices: u8,
+    pub number_sub_devices: u8,
 
     /// The total number of group identifiers.
-    total_group_identifiers: u8,
+    pub total_group_identifiers: u8,
 
     /// RSSI correction value.
-    rssi_correction: u8,
+    pub rssi_correction: u8,
 }
 
 /// Describes the initial security features and
 /// requirements that will be used when forming or
 /// joining ZLL networks.
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 pub struct ZllInitialSecurityState {
     /// Unused bitmask; reserved for future use.
-    bitmask: u32,
+    pub bitmask: u32,
 
     /// The key encryption algorithm advertised by the
     /// application.
-    key_index: ZLLKeyIndex,
+    pub key_index: ZLLKeyIndex,
 
     /// The encryption key for use by algorithms that
     /// require it.
-    encryption_key: KeyData,
+    pub encryption_key: KeyData,
 
     /// The pre-configured link key used during classical
     // ZigBee commissioning.
-    preconfigured_key: KeyData,
+    pub preconfigured_key: KeyData,
 }
 
 /// Information about a specific ZLL Device.
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 pub struct ZllDeviceInfoRecord {
     /// EUI64 associated with the device.
-    ieee_address: EUI64,
+    pub ieee_address: EUI64,
     
     /// Endpoint id.
-    endpoint_id: u8,
+    pub endpoint_id: u8,
     
     /// Profile id.
-    profile_id: u16,
+    pub profile_id: u16,
 
     /// Device id.
-    device_id: u16,
+    pub device_id: u16,
 
     /// Associated version.
-    version: u8,
+    pub version: u8,
 
     /// Number of relevant group ids.
-    group_id_count: u8,
+    pub group_id_count: u8,
 }
 
 /// ZLL address assignment data.
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 pub struct ZllAddressAssignment {
     /// Relevant node id.
-    node_id : NodeId,
+    pub node_id: NodeId,
 
     /// Minimum free node id.
-    free_node_id_min: NodeId,
+    pub free_node_id_min: NodeId,
 
     /// Maximum free node id.
-    free_node_id_max: NodeId,
+    pub free_node_id_max: NodeId,
 
     /// Minimum group id.
-    group_id_min: MulticastId,
+    pub group_id_min: MulticastId,
 
     /// Maximum group id.
-    group_id_max: MulticastId,
+    pub group_id_max: MulticastId,
 
     /// Minimum free group id.
-    free_group_id_min: MulticastId,
+    pub free_group_id_min: MulticastId,
 
     /// Maximum free group id.
-    free_group_id_max: MulticastId,
+    pub free_group_id_max: MulticastId,
 }
 
 /// Public API for ZLL stack data token.
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 pub struct TokTypeStackZllData {
     /// Token bitmask.
-    bitmask: u32,
+    pub bitmask: u32,
 
     /// Minimum free node id.
-    free_node_id_min: u16,
+    pub free_node_id_min: u16,
 
     /// Maximum free node id.
-    free_node_id_max: u16,
+    pub free_node_id_max: u16,
 
     /// Local minimum group id.
-    my_group_id_min: u16,
+    pub my_group_id_min: u16,
 
     /// Minimum free group id.
-    free_group_id_min: u16,
+    pub free_group_id_min: u16,
 
     /// Maximum free group id.
-    free_group_id_max: u16,
+    pub free_group_id_max: u16,
 
     /// RSSI correction value.
-    rssi_correction: u8,
+    pub rssi_correction: u8,
 }
 
 /// Public API for ZLL stack security token.
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 pub struct TokTypeStackZllSecurity {
     /// Token bitmask.
-    bitmask: u32,
+    pub bitmask: u32,
 
     /// Key index.
-    key_index: u8,
+    pub key_index: u8,
 
     /// Encryption key.
-    encryption_key: [u8; 16],
+    pub encryption_key: [u8; 16],
 
     /// Preconfigured key.
-    preconfigured_key: [u8; 16],
+    pub preconfigured_key: [u8; 16],
 }
 
 /// A structure containing duty cycle limit configurations.
@@ -2286,185 +2615,206 @@ pub struct TokTypeStackZllSecurity {
 ///  suspLimit > critThresh > limitThresh For
 /// example:
 ///  suspLimit = 250 (2.5%), critThresh = 180 (1.8%), limitThresh 100 (1.00%).
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 pub struct DutyCycleLimits {
-    /// The vendor identifier of the node.
-    vendor_id: u16,
+    /// The Limited Threshold in % * 100.
+    pub limit_thresh: DutyCycleHectoPct,
+
+    /// The Critical Threshold in % * 100.
+    pub crit_thresh: DutyCycleHectoPct,
 
-    /// The vendor string of the node.
-    vendor_string: [u8; 7]
+    /// The Suspended Limit (LBT) in % * 100.
+    pub susp_limit: DutyCycleHectoPct,
 }
 
 /// A structure containing per device overall duty
 /// cycle consumed (up to the suspend limit).
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 pub struct PerDeviceDutyCycle {
     /// Node Id of device whose duty cycle is reported.
-    node_id: NodeId,
+    pub node_id: NodeId,
 
     /// Amount of overall duty cycle consumed (up to suspend limit).
-    duty_cycle_consumed: DutyCycleHectoPct,
+    pub duty_cycle_consumed: DutyCycleHectoPct,
 }
 
 /// The transient key data structure.
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 pub struct TransientKeyData {
     /// The IEEE address paired with the transient link key.
-    eui64: EUI64,
+    pub eui64: EUI64,
 
     /// The key data structure matching the transient key.
-    key_data: KeyData,
+    pub key_data: KeyData,
 
     /// This bitmask indicates whether various fields
     /// in the structure contain valid data.
-    bitmask: KeyStructBitmask,
+    pub bitmask: KeyStructBitmask,
 
     /// The number of seconds remaining before the key is
     /// automatically timed out of the transient key table.
-    remaining_time_seconds: u16,
+    pub remaining_time_seconds: u16,
 
     /// The network index indicates which NWK uses this key.
-    network_index: u8,
+    pub network_index: u8,
 }
 
 /// A structure containing a child node's data.
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 pub struct ChildData {
     /// The EUI64 of the child.
-    eui64: EUI64,
+    pub eui64: EUI64,
 
     /// The node type of the child.
-    node_type : NodeType,
+    pub node_type: Recognized<NodeType>,
 
     /// The short address of the child.
-    id: NodeId,
+    pub id: NodeId,
 
     /// The phy of the child.
-    phy: u8,
+    pub phy: u8,
 
     /// The power of the child.
-    power: u8,
+    pub power: u8,
 
     /// The timeout of the child.
-    timeout: u8,
+    pub timeout: u8,
     
     /// The GPD's EUI64.
-    gpd_ieee_address: EUI64,
+    pub gpd_ieee_address: EUI64,
 
     /// The GPD's source ID.
-    source_id: u32,
+    pub source_id: u32,
 
     /// The GPD Application ID.
-    application_id: u8,
+    pub application_id: u8,
 
     /// The GPD endpoint.
-    endpoint: u8,
+    pub endpoint: u8,
 }
 
 // TODO: sl_zb_sec_man_key_t and related
 
 /// A GP address structure.
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 pub struct GpAddress {
     /// Contains either a 4-byte source ID or an 8-byte
     /// IEEE address, as indicated by the value of the  
     /// application_id field.
-    id: [u8; 8],
+    pub id: [u8; 8],
 
     /// The GPD Application ID specifying either source ID
     // (0x00) or IEEE address (0x02).
-    application_id: u8, // TODO: convert to enum
+    pub application_id: u8, // TODO: convert to enum
 
     /// The GPD endpoint.
-    endpoint: u8,
+    pub endpoint: u8,
 }
 
 /// The internal representation of a proxy table entry
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 pub struct GpProxyTableEntry {
     /// Internal status of the proxy table entry.
-    status: GpProxyTableEntryStatus,
+    pub status: Recognized<GpProxyTableEntryStatus>,
 
     /// The tunneling options (this contains both options
     /// and extendedOptions from the spec).
-    options: u32,
+    pub options: u32,
 
     /// The addressing info of the GPD.
-    gpd: GpAddress,
+    pub gpd: GpAddress,
     
     /// The assigned alias for the GPD.
-    assigned_alias: NodeId,
+    pub assigned_alias: NodeId,
 
     /// The security options field.
-    security_options: u8,
+    pub security_options: u8,
 
     /// The security frame counter of the GPD.
-    gpd_security_frame_counter: GpSecurityFrameCounter,
+    pub gpd_security_frame_counter: GpSecurityFrameCounter,
 
     /// The key to use for GPD.
-    gpd_key: KeyData,
+    pub gpd_key: KeyData,
 
     /// The list of sinks (hardcoded to 2 which is the spec minimum).
-    sink_list: [GpSinkListEntry; GP_SINK_LIST_ENTRIES],
+    pub sink_list: [GpSinkListEntry; GP_SINK_LIST_ENTRIES],
 
     /// The groupcast radius.
-    groupcast_radius: u8,
+    pub groupcast_radius: u8,
 
     /// The search counter.
-    search_counter: u8,
+    pub search_counter: u8,
 }
 
 /// The internal representation of a sink table entry.
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 pub struct GpSinkTableEntry {
     /// Internal status of the sink table entry.
-    status: GpSinkTableEntryStatus,
+    pub status: Recognized<GpSinkTableEntryStatus>,
 
     /// The tunneling options (this contains both options
     /// and extendedOptions from the spec).
-    options: u32,
+    pub options: u32,
 
     /// The addressing info of the GPD.
-    gpd: GpAddress,
+    pub gpd: GpAddress,
 
     /// The device id for the GPD.
-    device_id: u8,
+    pub device_id: u8,
 
     /// The list of sinks (hardcoded to 2 which is the spec minimum).
-    sink_list: [GpSinkListEntry; GP_SINK_LIST_ENTRIES],
+    pub sink_list: [GpSinkListEntry; GP_SINK_LIST_ENTRIES],
 
     /// The assigned alias for the GPD.
-    assigned_alias: NodeId,
+    pub assigned_alias: NodeId,
 
     /// The groupcast radius.
-    groupcast_radius: u8,
+    pub groupcast_radius: u8,
 
     /// The security options field.
-    security_options: u8,
+    pub security_options: u8,
 
     /// The security frame counter of the GPD.
-    gpd_security_frame_counter: GpSecurityFrameCounter,
+    pub gpd_security_frame_counter: GpSecurityFrameCounter,
 
     /// The key to use for GPD.
-    gpd_key: KeyData,
+    pub gpd_key: KeyData,
 }
 
 /// Information of a token in the token table.
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 pub struct TokenInfo {
     /// NVM3 key of the token
-    nvm3_key: u32,
+    pub nvm3_key: u32,
 
     /// Token is a counter type
-    is_counter: bool,
+    pub is_counter: bool,
 
     /// Token is an indexed token
-    is_index: bool,
+    pub is_index: bool,
 
     /// Size of the token
-    size: u8,
+    pub size: u8,
 
     /// Array size of the token
-    array_size: u8,
+    pub array_size: u8,
 }
 
 /// Token Data.
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 pub struct TokenData {
     /// Token data size in bytes.
-    size: u32,
+    pub size: u32,
     
     /// Token data pointer.
-    data: [u8; 64]
+    pub data: [u8; 64]
 }
@@ -0,0 +1,113 @@
+//! Wire encoding for the `bitmask!` types in [`crate::ember::types`].
+//!
+//! The remaining types derive [`crate::codec::EzspCodec`], which encodes
+//! fields in declaration order matching the structure layouts in UG100.
+
+use super::*;
+
+impl_bitmask_codec!(
+    ApsOption: u16,
+    InitialSecurityBitmask: u16,
+    CurrentSecurityBitmask: u16,
+    KeyStructBitmask: u16,
+    NetworkInitBitmask: u16,
+    LibraryStatus: u8,
+    ZLLState: u16,
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::{CodecError, EzspDecode, EzspEncode, Recognized, WireSize};
+    use crate::ember::structs::GpSinkListEntry;
+
+    #[test]
+    fn network_parameters () {
+        let parameters = NetworkParameters {
+            extended_pan_id: [0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08],
+            pan_id: 0x1A62,
+            radio_tx_power: -3,
+            radio_channel: 15,
+            join_method: JoinMethod::MACAssociation.into(),
+            network_manager_id: 0x0000,
+            network_update_id: 0,
+            channels: ChannelMask::ALL,
+        };
+
+        let mut buf = [0u8; 20];
+        assert_eq!(parameters.encode_to_slice(&mut buf), Ok(20));
+        assert_eq!(buf, [
+            0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08,
+            0x62, 0x1A,
+            0xFD,
+            0x0F,
+            0x00,
+            0x00, 0x00,
+            0x00,
+            0x00, 0xF8, 0xFF, 0x07,
+        ]);
+        assert_eq!(NetworkParameters::decode_from_slice(&buf), Ok((parameters, 20)));
+    }
+
+    #[test]
+    fn aps_frame () {
+        let buf = [0x04, 0x01, 0x06, 0x00, 0x01, 0x02, 0x40, 0x01, 0x00, 0x00, 0x2C];
+        let (frame, consumed) = ApsFrame::decode_from_slice(&buf).unwrap();
+
+        assert_eq!(consumed, 11);
+        assert_eq!(frame.profile_id, 0x0104);
+        assert_eq!(frame.cluster_id, 0x0006);
+        assert_eq!(frame.source_endpoint, 1);
+        assert_eq!(frame.destination_endpoint, 2);
+        assert!(frame.options.contains(ApsOptionFlags::Retry));
+        assert!(frame.options.contains(ApsOptionFlags::EnableRouteDiscovery));
+        assert_eq!(frame.sequence, 0x2C);
+
+        let mut encoded = [0u8; 11];
+        frame.encode_to_slice(&mut encoded).unwrap();
+        assert_eq!(encoded, buf);
+    }
+
+    #[test]
+    fn gp_sink_table_entry_size () {
+        let entry = GpSinkTableEntry {
+            status: GpSinkTableEntryStatus::Active.into(),
+            options: 0,
+            gpd: GpAddress { id: [0; 8], application_id: 0, endpoint: 0 },
+            device_id: 0,
+            sink_list: [GpSinkListEntry { sink_type: 0xFF, sink_eui: [0; 8], sink_node_id: 0xFFFF }; 2],
+            assigned_alias: 0,
+            groupcast_radius: 0,
+            security_options: 0,
+            gpd_security_frame_counter: 0,
+            gpd_key: KeyData([0; 16]),
+        };
+
+        let mut buf = [0u8; 64];
+        assert_eq!(GpSinkTableEntry::WIRE_SIZE, 1 + 4 + 10 + 1 + 22 + 2 + 1 + 1 + 4 + 16);
+        assert_eq!(entry.encode_to_slice(&mut buf), Ok(GpSinkTableEntry::WIRE_SIZE));
+        assert_eq!(GpSinkTableEntry::decode_from_slice(&buf).map(|(entry, _)| entry), Ok(entry));
+    }
+
+    #[test]
+    fn zll_state_flags () {
+        let (state, _) = ZLLState::decode_from_slice(&[0x03, 0x00]).unwrap();
+        assert!(state.contains(ZLLStateFlags::FactoryNew));
+        assert!(state.contains(ZLLStateFlags::AddressAssignmentCapable));
+        assert!(!state.contains(ZLLStateFlags::LinkInitiator));
+    }
+
+    #[test]
+    fn unknown_binding_type () {
+        let mut buf = [0u8; BindingTableEntry::WIRE_SIZE];
+        buf[0] = 0x07;
+        let (entry, _) = BindingTableEntry::decode_from_slice(&buf).unwrap();
+        assert_eq!(entry.binding_type, Recognized::Unknown(0x07));
+    }
+
+    #[test]
+    fn invalid_enum_value () {
+        assert_eq!(NodeType::decode_from_slice(&[0x05]), Err(CodecError::InvalidValue));
+        assert_eq!(NodeType::decode_from_slice(&[0x02]), Ok((NodeType::Router, 1)));
+    }
+}
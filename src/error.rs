@@ -0,0 +1,528 @@
+//! Errors returned by EZSP commands.
+//!
+//! EZSP reports two kinds of status. [`ezsp::types::Status`](EzspStatus)
+//! describes the transport and the EZSP protocol itself, e.g. ASH link
+//! failures or commands sent before the version was set, while
+//! [`ember::types::Status`](EmberStatus) is the result of the stack
+//! operation a command asked for. Both end up in [`Error`].
+
+use core::fmt;
+
+use crate::codec::{CodecError, EzspEnum, Recognized};
+use crate::ember::types::{EUI64, NodeId, Status as EmberStatus};
+use crate::ezsp::types::{ConfigID, PolicyID, Status as EzspStatus, ValueID};
+use crate::ezsp::value::ValueKind;
+use crate::{FrameID, HeaderError};
+
+#[cfg(feature = "std")]
+use crate::ash::AshError;
+
+/// The error returned by EZSP commands.
+#[derive(Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum Error {
+    /// The NCP or the host EZSP layer reported a transport or protocol status.
+    Ezsp(EzspStatus),
+
+    /// The stack reported an unsuccessful result.
+    Ember(EmberStatus),
+
+    /// The NCP reported an EZSP status unknown to this crate.
+    UnknownEzspStatus(u8),
+
+    /// The stack reported a status unknown to this crate.
+    UnknownEmberStatus(u8),
+
+    /// The ASH link to the NCP failed.
+    #[cfg(feature = "std")]
+    Ash(AshError),
+
+    /// The NCP echoed back different data than was sent,
+    /// so the link corrupts frames.
+    EchoMismatch,
+
+    /// A frame header could not be encoded or decoded.
+    Header(HeaderError),
+
+    /// Frame parameters could not be encoded or decoded.
+    Codec(CodecError),
+
+    /// The NCP does not support a protocol version known to this crate.
+    UnsupportedVersion(u8),
+
+    /// The command is not available in the protocol version negotiated with the NCP.
+    UnsupportedCommand {
+        /// The frame ID of the command.
+        frame_id: FrameID,
+
+        /// The protocol version negotiated with the NCP.
+        protocol_version: u8,
+    },
+
+    /// The configuration value is not available in the protocol version negotiated with the NCP.
+    UnsupportedConfiguration {
+        /// The configuration value being read or written.
+        config_id: ConfigID,
+
+        /// The protocol version negotiated with the NCP.
+        protocol_version: u8,
+    },
+
+    /// The value is not available in the protocol version negotiated with the NCP.
+    UnsupportedValue {
+        /// The value being read or written.
+        value_id: ValueID,
+
+        /// The protocol version negotiated with the NCP.
+        protocol_version: u8,
+    },
+
+    /// A command was sent before a protocol version was negotiated.
+    NotNegotiated,
+
+    /// The NCP did not report the outcome of an operation in time.
+    Timeout,
+
+    /// The decision cannot be set for the policy.
+    InvalidDecision {
+        /// The policy being changed.
+        policy_id: PolicyID,
+
+        /// The decision ID byte that was rejected.
+        decision: u8,
+    },
+
+    /// The value written does not match the layout of the value ID.
+    InvalidValue {
+        /// The value being written.
+        value_id: ValueID,
+
+        /// The layout of the value that was rejected.
+        kind: ValueKind,
+    },
+
+    /// The endpoint ID is not one applications can register,
+    /// or no endpoint with the ID has been registered.
+    InvalidEndpoint(u8),
+
+    /// An endpoint with the ID has already been registered.
+    DuplicateEndpoint(u8),
+
+    /// An endpoint lists more clusters than fit in an addEndpoint frame.
+    TooManyClusters(usize),
+
+    /// The channel is not a 2.4 GHz channel of channel page 0.
+    InvalidChannel(u8),
+
+    /// The channel mask selects channels outside channel page 0's 2.4 GHz band.
+    InvalidChannelMask(u32),
+
+    /// A broadcast was addressed to a node ID that is not a broadcast address.
+    InvalidBroadcastAddress(NodeId),
+
+    /// Neither the host nor the NCP knows the node ID of a device.
+    UnknownEui64(EUI64),
+
+    /// No outcome is pending for the message tag, as it was already
+    /// returned or the tag was reused for a later message.
+    UnknownMessageTag(u8),
+}
+
+/// Broad groups of statuses and errors, for handling
+/// related failures together.
+#[derive(Copy, Clone, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum Category {
+    /// The operation succeeded.
+    Success,
+
+    /// Generic failures and invalid arguments or calls.
+    General,
+
+    /// The NCP's serial ports.
+    Serial,
+
+    /// The SPI link to the NCP.
+    Spi,
+
+    /// The ASH link to the NCP.
+    Ash,
+
+    /// EZSP framing, versioning and encoding.
+    Protocol,
+
+    /// Tokens, flash and simulated EEPROM.
+    Flash,
+
+    /// The boot loader.
+    Bootloader,
+
+    /// The radio and its physical layer.
+    Radio,
+
+    /// The MAC layer.
+    Mac,
+
+    /// ADC and sleep hardware.
+    Hardware,
+
+    /// Joining, leaving and the state of the network.
+    Network,
+
+    /// Keys and security configuration.
+    Security,
+
+    /// Sending messages and routing them.
+    Messaging,
+
+    /// Binding, address and other stack tables.
+    Table,
+
+    /// Application defined errors.
+    Application,
+}
+
+impl EmberStatus {
+    /// Whether the status reports success.
+    pub fn is_success(self) -> bool {
+        self == EmberStatus::Success
+    }
+
+    /// Turns the status into `Ok(())` if it reports success, or an [`Error::Ember`].
+    pub fn into_result(self) -> Result<(), Error> {
+        if self.is_success() { Ok(()) } else { Err(Error::Ember(self)) }
+    }
+
+    /// The group the status belongs to.
+    pub fn category(self) -> Category {
+        use EmberStatus::*;
+
+        match self {
+            Success => Category::Success,
+            ErrFatal | BadArgument | NoBuffers | InvalidCall | LibraryNotPresent
+            | StackAndHardwareMismatch | OperationInProgress => Category::General,
+            SerialInvalidBaudRate | SerialInvalidPort | SerialTXOverflow | SerialRXOverflow
+            | SerialRXFrameError | SerialRXParityError | SerialRXEmpty | SerialRXOverrunError => Category::Serial,
+            EEPROMMfgStackVersionMismatch | EEPROMMfgVersionMismatch | EEPROMStackVersionMismatch
+            | SimulatedEEPROMErasePageGreen | SimulatedEEPROMErasePageRed | SimulatedEEPROMFull
+            | ErrorFlashWriteInhibited | ErrorFlashVerifyFailed | SimulatedEEPROMInit1Failed
+            | SimulatedEEPROMInit2Failed | SimulatedEEPORMInit3Failed | ErrorFlashProgFail
+            | ErrorFlashEraseFail => Category::Flash,
+            ErrorBootloaderTrapTableBad | ErrorBootloaderTrapUknown | ErrorBootloaderNoImage => Category::Bootloader,
+            MACTransmitQueueFull | MACUnknownHeaderType | MACScanning | MACNoData | MACJoinedNetwork
+            | MACBadScanDuration | MACIncorrectScanType | MACInvalidChannelMask
+            | MACCommandTransmitFailure | MACNoACKReceived | MACIndirectTimeout => Category::Mac,
+            PHYTXUnderflow | PHYTXIncomplete | PHYInvalidChannel | PHYInvalidPower | PHYTXBusy
+            | PHYTXCAAFail | PHYOcillatorCheckFailed | PHYACKReceived => Category::Radio,
+            ADCConversionDone | ADCConversionBusy | ADCConversionDeferrred | ADCNoConversionPending
+            | SleepInterruped => Category::Hardware,
+            NetworkUp | NetworkDown | JoinFailed | MoveFailed | CannotJoinAsRouter | NodeIDChanged
+            | PANIDChanged | NetworkOpened | NetworkClosed | NoBeacons | NotJoined | NetworkBusy => Category::Network,
+            ReceivedKeyInTheClear | NoNetworkKeyReceived | NoLinkKeyReceived | PreconfiguredKeyRequired
+            | InvalidSecurityLevel | InsufficientRandomData | APSEncrpytionError | SecurityStateNotSet
+            | KeyTableInvalidAddress | SecurityConfiguraitonInvalid | TooSoonForSwitchKey
+            | KeyNotAuthotized | SecurityDataInvalid => Category::Security,
+            DeliveryFailed | CostNotKnown | MaxMessageLimitReached | MessageTooLong | InvalidEndpoint
+            | SourceRouteFailure | ManyToOneRouteFaulure => Category::Messaging,
+            BindingIndexOutOfRange | AddressTableIndexOutOfRange | InvalidBindingIndex | BindingIsActive
+            | AddressTableEntryIsActive | BindingHasChanged | IndexOutOfRange | TableFull
+            | TableEntryErased => Category::Table,
+            ApplicationError0 | ApplicationError1 | ApplicationError2 | ApplicationError3
+            | ApplicationError4 | ApplicationError5 | ApplicationError6 | ApplicationError7
+            | ApplicationError8 | ApplicationError9 | ApplicationError10 | ApplicationError11
+            | ApplicationError12 | ApplicationError13 | ApplicationError14
+            | ApplicationError15 => Category::Application,
+        }
+    }
+}
+
+impl EzspStatus {
+    /// Whether the status reports success.
+    pub fn is_success(self) -> bool {
+        matches!(self, EzspStatus::Success | EzspStatus::NoError)
+    }
+
+    /// Turns the status into `Ok(())` if it reports success, or an [`Error::Ezsp`].
+    pub fn into_result(self) -> Result<(), Error> {
+        if self.is_success() { Ok(()) } else { Err(Error::Ezsp(self)) }
+    }
+
+    /// The group the status belongs to.
+    pub fn category(self) -> Category {
+        use EzspStatus::*;
+
+        match self {
+            Success | NoError => Category::Success,
+            ErrorSecurityKeyAlreadySet | ErrorSecurityTypeInvalid | ErrorSecurityParametersInvalid
+            | ErrorSecurityParametersAlreadySet | ErrorSecurityKeyNotSet | ErrorSecurityParametersNotSet
+            | ErrorUnsecureFrame => Category::Security,
+            ErrorSerialInit => Category::Serial,
+            _ => match self as u8 {
+                0x10..=0x1F => Category::Spi,
+                0x20..=0x2F | 0x50..=0x85 => Category::Ash,
+                _ => Category::Protocol,
+            },
+        }
+    }
+}
+
+impl Recognized<EmberStatus> {
+    /// Whether the status reports success.
+    pub fn is_success(self) -> bool {
+        self.known().is_some_and(EmberStatus::is_success)
+    }
+
+    /// Turns the status into `Ok(())` if it reports success, or an
+    /// [`Error::Ember`] or [`Error::UnknownEmberStatus`].
+    pub fn into_result(self) -> Result<(), Error> {
+        match self {
+            Recognized::Known(status) => status.into_result(),
+            Recognized::Unknown(raw) => Err(Error::UnknownEmberStatus(raw)),
+        }
+    }
+}
+
+impl Recognized<EzspStatus> {
+    /// Whether the status reports success.
+    pub fn is_success(self) -> bool {
+        self.known().is_some_and(EzspStatus::is_success)
+    }
+
+    /// Turns the status into `Ok(())` if it reports success, or an
+    /// [`Error::Ezsp`] or [`Error::UnknownEzspStatus`].
+    pub fn into_result(self) -> Result<(), Error> {
+        match self {
+            Recognized::Known(status) => status.into_result(),
+            Recognized::Unknown(raw) => Err(Error::UnknownEzspStatus(raw)),
+        }
+    }
+}
+
+impl Error {
+    /// The group the error belongs to.
+    pub fn category(&self) -> Category {
+        match self {
+            Error::Ezsp(status) => status.category(),
+            Error::Ember(status) => status.category(),
+            #[cfg(feature = "std")]
+            Error::Ash(_) => Category::Ash,
+            Error::EchoMismatch => Category::Ash,
+            Error::UnknownEmberStatus(_) => Category::General,
+            Error::UnknownEzspStatus(_)
+            | Error::Header(_)
+            | Error::Codec(_)
+            | Error::UnsupportedVersion(_)
+            | Error::UnsupportedCommand { .. }
+            | Error::UnsupportedConfiguration { .. }
+            | Error::UnsupportedValue { .. }
+            | Error::NotNegotiated => Category::Protocol,
+            Error::InvalidDecision { .. }
+            | Error::InvalidValue { .. }
+            | Error::InvalidEndpoint(_)
+            | Error::DuplicateEndpoint(_)
+            | Error::TooManyClusters(_)
+            | Error::Timeout => Category::General,
+            Error::InvalidChannel(_) | Error::InvalidChannelMask(_) => Category::Radio,
+            Error::InvalidBroadcastAddress(_) | Error::UnknownEui64(_) | Error::UnknownMessageTag(_) => {
+                Category::Messaging
+            }
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Ezsp(status) => write!(f, "EZSP status {} (0x{:02X})", status.name(), *status as u8),
+            Error::Ember(status) => write!(f, "ember status {} (0x{:02X})", status.name(), *status as u8),
+            Error::UnknownEzspStatus(status) => write!(f, "unknown EZSP status 0x{status:02X}"),
+            Error::UnknownEmberStatus(status) => write!(f, "unknown ember status 0x{status:02X}"),
+            #[cfg(feature = "std")]
+            Error::Ash(error) => write!(f, "ASH link error: {error}"),
+            Error::EchoMismatch => f.write_str("the NCP echoed back different data than was sent"),
+            Error::Header(error) => write!(f, "invalid frame header: {error}"),
+            Error::Codec(error) => write!(f, "invalid frame parameters: {error}"),
+            Error::UnsupportedVersion(version) => write!(f, "unsupported EZSP protocol version {version}"),
+            Error::UnsupportedCommand { frame_id, protocol_version } => write!(
+                f,
+                "frame 0x{frame_id:04X} is not supported by EZSP protocol version {protocol_version}"
+            ),
+            Error::UnsupportedConfiguration { config_id, protocol_version } => write!(
+                f,
+                "configuration value {} is not supported by EZSP protocol version {protocol_version}",
+                config_id.name()
+            ),
+            Error::UnsupportedValue { value_id, protocol_version } => write!(
+                f,
+                "value {} is not supported by EZSP protocol version {protocol_version}",
+                value_id.name()
+            ),
+            Error::NotNegotiated => f.write_str("the EZSP protocol version has not been negotiated"),
+            Error::InvalidDecision { policy_id, decision } => {
+                write!(f, "decision 0x{decision:02X} cannot be set for policy {}", policy_id.name())
+            }
+            Error::InvalidValue { value_id, .. } => {
+                write!(f, "the value written does not match the layout of value {}", value_id.name())
+            }
+            Error::InvalidEndpoint(endpoint) => write!(f, "endpoint {endpoint} is not a valid application endpoint"),
+            Error::DuplicateEndpoint(endpoint) => write!(f, "endpoint {endpoint} is already registered"),
+            Error::Timeout => f.write_str("timed out waiting for the NCP"),
+            Error::TooManyClusters(count) => write!(f, "{count} clusters do not fit in an addEndpoint frame"),
+            Error::InvalidChannel(channel) => write!(f, "channel {channel} is not a 2.4 GHz channel"),
+            Error::InvalidChannelMask(mask) => write!(f, "channel mask 0x{mask:08X} selects channels outside 11 to 26"),
+            Error::InvalidBroadcastAddress(node_id) => write!(f, "0x{node_id:04X} is not a broadcast address"),
+            Error::UnknownEui64(eui64) => {
+                f.write_str("no node ID is known for EUI64 ")?;
+                eui64.iter().rev().try_for_each(|byte| write!(f, "{byte:02X}"))
+            }
+            Error::UnknownMessageTag(tag) => write!(f, "no outcome is pending for message tag {tag}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+impl fmt::Display for HeaderError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            HeaderError::BufferTooShort { needed, available } => {
+                write!(f, "needed {needed} bytes but only {available} were available")
+            }
+            HeaderError::InvalidFrameControl(byte) => write!(f, "invalid frame control byte 0x{byte:02X}"),
+            HeaderError::ReservedFrameFormatVersion(_) => f.write_str("reserved frame format version"),
+            HeaderError::FrameIdOutOfRange(frame_id) => {
+                write!(f, "frame ID 0x{frame_id:04X} does not fit in a legacy header")
+            }
+        }
+    }
+}
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            CodecError::BufferTooSmall => "the buffer is too small for the value",
+            CodecError::UnexpectedEnd => "the frame ended before the value was read",
+            CodecError::InvalidValue => "the bytes do not represent a valid value",
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl fmt::Display for AshError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        use crate::ash::frame::describe_reset_code;
+
+        match self {
+            AshError::Stream(kind) => write!(f, "byte stream failed: {kind}"),
+            AshError::ResetTimeout => f.write_str("the NCP did not acknowledge the reset"),
+            AshError::Version(version) => write!(f, "unsupported ASH version {version}"),
+            AshError::NcpReset(code) => write!(f, "the NCP reset ({})", describe_reset_code(*code)),
+            AshError::NcpError(code) => write!(f, "the NCP failed ({})", describe_reset_code(*code)),
+            AshError::AckTimeout => f.write_str("sent frames were not acknowledged"),
+            AshError::NotConnected => f.write_str("the link is not connected"),
+            AshError::DataFrameTooLong(length) => write!(f, "a {length} byte frame is too long to send"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl AshError {
+    /// The EZSP status the host library reports for the failure.
+    pub fn status(&self) -> EzspStatus {
+        match self {
+            AshError::Stream(_) => EzspStatus::HostFatalError,
+            AshError::ResetTimeout => EzspStatus::ASHErrorResetFail,
+            AshError::Version(_) => EzspStatus::ASHErrorVersion,
+            AshError::NcpReset(_) => EzspStatus::ASHErrorNCPReset,
+            AshError::NcpError(_) => EzspStatus::ASHNCPFatalError,
+            AshError::AckTimeout => EzspStatus::ASHErrorTimeouts,
+            AshError::NotConnected => EzspStatus::NotConnected,
+            AshError::DataFrameTooLong(_) => EzspStatus::DataFrameTooLong,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for AshError {}
+
+impl From<EzspStatus> for Error {
+    fn from(status: EzspStatus) -> Self {
+        Error::Ezsp(status)
+    }
+}
+
+impl From<EmberStatus> for Error {
+    fn from(status: EmberStatus) -> Self {
+        Error::Ember(status)
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<AshError> for Error {
+    fn from(error: AshError) -> Self {
+        Error::Ash(error)
+    }
+}
+
+impl From<HeaderError> for Error {
+    fn from(error: HeaderError) -> Self {
+        Error::Header(error)
+    }
+}
+
+impl From<CodecError> for Error {
+    fn from(error: CodecError) -> Self {
+        Error::Codec(error)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_results () {
+        assert_eq!(EmberStatus::Success.into_result(), Ok(()));
+        assert_eq!(EmberStatus::NotJoined.into_result(), Err(Error::Ember(EmberStatus::NotJoined)));
+        assert_eq!(EzspStatus::NoError.into_result(), Ok(()));
+        assert_eq!(EzspStatus::ErrorVersionNotSet.into_result(), Err(Error::Ezsp(EzspStatus::ErrorVersionNotSet)));
+        assert_eq!(Recognized::Known(EmberStatus::Success).into_result(), Ok(()));
+        assert_eq!(Recognized::<EmberStatus>::Unknown(0xFE).into_result(), Err(Error::UnknownEmberStatus(0xFE)));
+    }
+
+    #[test]
+    fn categories () {
+        assert_eq!(EmberStatus::NetworkDown.category(), Category::Network);
+        assert_eq!(EmberStatus::KeyNotAuthotized.category(), Category::Security);
+        assert_eq!(EmberStatus::PHYTXCAAFail.category(), Category::Radio);
+        assert_eq!(EmberStatus::ErrorFlashProgFail.category(), Category::Flash);
+        assert_eq!(EmberStatus::ApplicationError7.category(), Category::Application);
+
+        assert_eq!(EzspStatus::SPIErrorFatal.category(), Category::Spi);
+        assert_eq!(EzspStatus::ASHErrorTimeouts.category(), Category::Ash);
+        assert_eq!(EzspStatus::ErrorVersionNotSet.category(), Category::Protocol);
+        assert_eq!(EzspStatus::ErrorSecurityKeyNotSet.category(), Category::Security);
+
+        assert_eq!(Error::Ash(AshError::AckTimeout).category(), Category::Ash);
+        assert_eq!(Error::NotNegotiated.category(), Category::Protocol);
+    }
+
+    #[test]
+    fn display () {
+        assert_eq!(Error::Ember(EmberStatus::NotJoined).to_string(), "ember status NotJoined (0x93)");
+        assert_eq!(Error::Ezsp(EzspStatus::ErrorInvalidID).to_string(), "EZSP status ErrorInvalidID (0x37)");
+        assert_eq!(Error::UnknownEmberStatus(0xFE).to_string(), "unknown ember status 0xFE");
+        assert_eq!(
+            Error::UnsupportedCommand { frame_id: 0x006A, protocol_version: 13 }.to_string(),
+            "frame 0x006A is not supported by EZSP protocol version 13"
+        );
+        assert_eq!(
+            Error::Ash(AshError::NcpReset(crate::ash::frame::ResetCode::Watchdog.into())).to_string(),
+            "ASH link error: the NCP reset (watchdog)"
+        );
+
+        let error: Box<dyn std::error::Error> = Box::new(Error::NotNegotiated);
+        assert_eq!(error.to_string(), "the EZSP protocol version has not been negotiated");
+    }
+}
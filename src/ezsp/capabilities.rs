@@ -0,0 +1,161 @@
+//! What an NCP supports at a given EZSP protocol version.
+//!
+//! Between the versions this crate supports, frames only differ in
+//! whether the legacy header can address their frame ID, and a few
+//! configuration and value IDs were moved or added.
+
+use crate::error::Error;
+use crate::ezsp::types::{ConfigID, ValueID};
+use crate::{FrameID, HeaderFormat};
+
+/// The oldest protocol version this crate can talk to.
+pub const MIN_PROTOCOL_VERSION: u8 = 4;
+
+/// The newest protocol version this crate can talk to,
+/// and the version requested when connecting.
+///
+/// Version 13 changed the layout of the messaging and address
+/// table frames, which are only encoded in their older form.
+pub const MAX_PROTOCOL_VERSION: u8 = 12;
+
+/// The range of protocol versions an item is available in.
+#[derive(Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+struct Availability {
+    /// The first protocol version the item is available in.
+    introduced: u8,
+
+    /// The first protocol version the item is no longer available in.
+    removed: Option<u8>,
+}
+
+impl Availability {
+    /// Available from `version` onwards.
+    const fn since(version: u8) -> Self {
+        Availability { introduced: version, removed: None }
+    }
+
+    /// Available in versions before `version`.
+    const fn until(version: u8) -> Self {
+        Availability { introduced: MIN_PROTOCOL_VERSION, removed: Some(version) }
+    }
+
+    /// Whether the item is available in the protocol version.
+    fn contains(&self, protocol_version: u8) -> bool {
+        protocol_version >= self.introduced
+            && self.removed.is_none_or(|removed| protocol_version < removed)
+    }
+}
+
+/// Configuration values whose availability differs from the default.
+const CONFIGURATION: &[(ConfigID, Availability)] = &[
+    // Replaced by ValueID::TransientKeyTimeoutS.
+    #[allow(deprecated)]
+    (ConfigID::TransientKeyTimeoutS, Availability::until(8)),
+];
+
+/// Values whose availability differs from the default.
+const VALUES: &[(ValueID, Availability)] = &[
+    (ValueID::TransientKeyTimeoutS, Availability::since(8)),
+];
+
+/// What the NCP supports at the negotiated protocol version.
+#[derive(Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Capabilities {
+    protocol_version: u8,
+}
+
+impl Capabilities {
+    /// The capabilities of an NCP using `protocol_version`,
+    /// or `None` if this crate does not support the version.
+    pub fn for_version(protocol_version: u8) -> Option<Self> {
+        Self::is_supported_version(protocol_version).then_some(Capabilities { protocol_version })
+    }
+
+    /// Whether this crate can talk to an NCP using `protocol_version`.
+    pub fn is_supported_version(protocol_version: u8) -> bool {
+        (MIN_PROTOCOL_VERSION..=MAX_PROTOCOL_VERSION).contains(&protocol_version)
+    }
+
+    /// The negotiated protocol version.
+    pub fn protocol_version(&self) -> u8 {
+        self.protocol_version
+    }
+
+    /// The frame header layout used at the negotiated version.
+    pub fn header_format(&self) -> HeaderFormat {
+        HeaderFormat::for_protocol_version(self.protocol_version)
+    }
+
+    /// Whether the command with `frame_id` can be sent.
+    ///
+    /// Only the legacy header limits this, as it cannot address frame IDs above 0xFF.
+    pub fn supports_frame(&self, frame_id: FrameID) -> bool {
+        self.header_format() != HeaderFormat::Legacy || frame_id <= 0xFF
+    }
+
+    /// Whether the configuration value can be read and written.
+    pub fn supports_configuration(&self, id: ConfigID) -> bool {
+        lookup(CONFIGURATION, |entry| *entry == id).is_none_or(|availability| availability.contains(self.protocol_version))
+    }
+
+    /// Whether the value can be read and written.
+    pub fn supports_value(&self, id: ValueID) -> bool {
+        lookup(VALUES, |entry| *entry == id).is_none_or(|availability| availability.contains(self.protocol_version))
+    }
+
+    /// Fails with [`Error::UnsupportedCommand`] if the command with `frame_id` cannot be sent.
+    pub fn check_frame(&self, frame_id: FrameID) -> Result<(), Error> {
+        if self.supports_frame(frame_id) {
+            Ok(())
+        } else {
+            Err(Error::UnsupportedCommand { frame_id, protocol_version: self.protocol_version })
+        }
+    }
+}
+
+fn lookup<K>(table: &[(K, Availability)], matches: impl Fn(&K) -> bool) -> Option<Availability> {
+    table.iter().find(|(key, _)| matches(key)).map(|(_, availability)| *availability)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn supported_versions () {
+        assert_eq!(Capabilities::for_version(3), None);
+        assert_eq!(Capabilities::for_version(13), None);
+        assert_eq!(Capabilities::for_version(12).unwrap().header_format(), HeaderFormat::Extended);
+        assert_eq!(Capabilities::for_version(7).unwrap().header_format(), HeaderFormat::Legacy);
+    }
+
+    #[test]
+    fn frame_availability () {
+        let legacy = Capabilities::for_version(7).unwrap();
+        assert!(legacy.supports_frame(0x0052));
+        assert!(!legacy.supports_frame(0x0100));
+        assert_eq!(
+            legacy.check_frame(0x0100),
+            Err(Error::UnsupportedCommand { frame_id: 0x0100, protocol_version: 7 })
+        );
+
+        let current = Capabilities::for_version(12).unwrap();
+        assert!(current.supports_frame(0x0100));
+        assert_eq!(current.check_frame(0x0100), Ok(()));
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn id_availability () {
+        let legacy = Capabilities::for_version(7).unwrap();
+        assert!(legacy.supports_configuration(ConfigID::TransientKeyTimeoutS));
+        assert!(!legacy.supports_value(ValueID::TransientKeyTimeoutS));
+
+        let current = Capabilities::for_version(12).unwrap();
+        assert!(!current.supports_configuration(ConfigID::TransientKeyTimeoutS));
+        assert!(current.supports_value(ValueID::TransientKeyTimeoutS));
+        assert!(current.supports_configuration(ConfigID::StackProfile));
+    }
+}
@@ -0,0 +1,119 @@
+//! Snapshots of the stack counters kept by the NCP.
+
+use core::ops::Index;
+use core::time::Duration;
+
+use crate::codec::EzspEnum;
+use crate::ember::types::CounterType;
+
+/// The number of counters the NCP reports.
+///
+/// EMBER_COUNTER_TYPE_COUNT
+pub const COUNTER_TYPE_COUNT: usize = CounterType::TypeCount as usize;
+
+/// The values of every counter at one point in time,
+/// as read with readCounters or readAndClearCounters.
+///
+/// Counters are 16 bits wide and wrap around, the NCP sends
+/// a counterRolloverHandler callback when one does.
+#[derive(Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Counters {
+    values: [u16; COUNTER_TYPE_COUNT],
+}
+
+impl Counters {
+    /// Wraps the values reported by the NCP, ordered by [`CounterType`].
+    pub fn new(values: [u16; COUNTER_TYPE_COUNT]) -> Self {
+        Counters { values }
+    }
+
+    /// The value of a counter.
+    ///
+    /// [`CounterType::TypeCount`] is not a counter and reads as 0.
+    pub fn get(&self, counter: CounterType) -> u16 {
+        self.values.get(counter as usize).copied().unwrap_or(0)
+    }
+
+    /// The raw values, ordered by [`CounterType`].
+    pub fn values(&self) -> &[u16; COUNTER_TYPE_COUNT] {
+        &self.values
+    }
+
+    /// Every counter and its value.
+    pub fn iter(&self) -> impl Iterator<Item = (CounterType, u16)> + '_ {
+        self.values.iter().enumerate().filter_map(|(index, value)| {
+            let counter = CounterType::from_raw(index as u8)?;
+            Some((counter, *value))
+        })
+    }
+
+    /// How much each counter grew since `earlier`, allowing
+    /// for counters that wrapped around once in between.
+    pub fn since(&self, earlier: &Counters) -> Counters {
+        Counters { values: core::array::from_fn(|index| self.values[index].wrapping_sub(earlier.values[index])) }
+    }
+
+    /// The rate a counter grew at, treating its value as the
+    /// change over `elapsed`, e.g. a delta from [`since`](Counters::since)
+    /// or a snapshot returned by readAndClearCounters.
+    ///
+    /// Returns 0 if no time has elapsed.
+    pub fn per_second(&self, counter: CounterType, elapsed: Duration) -> f64 {
+        let seconds = elapsed.as_secs_f64();
+        if seconds == 0.0 { 0.0 } else { f64::from(self.get(counter)) / seconds }
+    }
+}
+
+impl Default for Counters {
+    fn default() -> Self {
+        Counters { values: [0; COUNTER_TYPE_COUNT] }
+    }
+}
+
+impl Index<CounterType> for Counters {
+    type Output = u16;
+
+    /// Panics for [`CounterType::TypeCount`], which is not a counter.
+    fn index(&self, counter: CounterType) -> &u16 {
+        &self.values[counter as usize]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn counters(entries: &[(CounterType, u16)]) -> Counters {
+        let mut values = [0; COUNTER_TYPE_COUNT];
+        for (counter, value) in entries {
+            values[*counter as usize] = *value;
+        }
+        Counters::new(values)
+    }
+
+    #[test]
+    fn indexed_by_counter_type () {
+        let counters = counters(&[(CounterType::MACTXUnicastRetry, 12), (CounterType::PTXHIPRITXAborted, 3)]);
+
+        assert_eq!(counters[CounterType::MACTXUnicastRetry], 12);
+        assert_eq!(counters.get(CounterType::PTXHIPRITXAborted), 3);
+        assert_eq!(counters.get(CounterType::TypeCount), 0);
+        assert_eq!(counters.iter().count(), COUNTER_TYPE_COUNT);
+        assert_eq!(counters.iter().last(), Some((CounterType::PTXHIPRITXAborted, 3)));
+    }
+
+    #[test]
+    fn deltas_and_rates () {
+        let earlier = counters(&[(CounterType::MACTXUnicastRetry, 10), (CounterType::APSDataTXUnicastFailed, 0xFFFE)]);
+        let later = counters(&[(CounterType::MACTXUnicastRetry, 40), (CounterType::APSDataTXUnicastFailed, 2)]);
+
+        let delta = later.since(&earlier);
+        assert_eq!(delta[CounterType::MACTXUnicastRetry], 30);
+        assert_eq!(delta[CounterType::APSDataTXUnicastFailed], 4);
+        assert_eq!(delta[CounterType::MACRXUnicast], 0);
+
+        assert_eq!(delta.per_second(CounterType::MACTXUnicastRetry, Duration::from_secs(10)), 3.0);
+        assert_eq!(delta.per_second(CounterType::MACTXUnicastRetry, Duration::ZERO), 0.0);
+    }
+}
@@ -0,0 +1,178 @@
+//! Application endpoints registered on the NCP.
+
+use crate::error::Error;
+use crate::frames::AddEndpoint;
+
+/// The first endpoint ID available to applications.
+pub const MIN_ENDPOINT: u8 = 0x01;
+
+/// The last endpoint ID available to applications.
+///
+/// Endpoint 0 is the ZDO, 0xF1 to 0xFE are reserved
+/// and 0xFF addresses every endpoint.
+pub const MAX_ENDPOINT: u8 = 0xF0;
+
+/// The most clusters an endpoint can list, input and output combined.
+///
+/// The addEndpoint command must fit in one EZSP frame of 200 bytes,
+/// which leaves room for this many cluster IDs after the 5 byte
+/// extended header and 8 bytes of fixed parameters.
+pub const MAX_CLUSTERS: usize = (200 - 5 - 8) / 2;
+
+/// The simple descriptor of an application endpoint.
+///
+/// Built with [`Endpoint::builder`], which checks that
+/// the endpoint can be registered with addEndpoint.
+#[derive(Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Endpoint {
+    id: u8,
+    profile_id: u16,
+    device_id: u16,
+    app_flags: u8,
+    input_clusters: Vec<u16>,
+    output_clusters: Vec<u16>,
+}
+
+impl Endpoint {
+    /// Starts building endpoint `id` implementing `device_id` of the `profile_id` profile.
+    pub fn builder(id: u8, profile_id: u16, device_id: u16) -> EndpointBuilder {
+        EndpointBuilder {
+            endpoint: Endpoint {
+                id,
+                profile_id,
+                device_id,
+                app_flags: 0,
+                input_clusters: Vec::new(),
+                output_clusters: Vec::new(),
+            },
+        }
+    }
+
+    /// The endpoint ID.
+    pub fn id(&self) -> u8 {
+        self.id
+    }
+
+    /// The application profile.
+    pub fn profile_id(&self) -> u16 {
+        self.profile_id
+    }
+
+    /// The device ID within the application profile.
+    pub fn device_id(&self) -> u16 {
+        self.device_id
+    }
+
+    /// The device version and flags indicating description availability.
+    pub fn app_flags(&self) -> u8 {
+        self.app_flags
+    }
+
+    /// The clusters the endpoint accepts.
+    pub fn input_clusters(&self) -> &[u16] {
+        &self.input_clusters
+    }
+
+    /// The clusters the endpoint may send.
+    pub fn output_clusters(&self) -> &[u16] {
+        &self.output_clusters
+    }
+
+    /// The addEndpoint command registering the endpoint.
+    pub fn to_frame(&self) -> AddEndpoint {
+        AddEndpoint {
+            endpoint: self.id,
+            profile_id: self.profile_id,
+            device_id: self.device_id,
+            app_flags: self.app_flags,
+            input_clusters: self.input_clusters.clone(),
+            output_clusters: self.output_clusters.clone(),
+        }
+    }
+}
+
+/// Builds an [`Endpoint`].
+#[derive(Clone)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct EndpointBuilder {
+    endpoint: Endpoint,
+}
+
+impl EndpointBuilder {
+    /// Sets the device version and description flags.
+    pub fn app_flags(mut self, app_flags: u8) -> Self {
+        self.endpoint.app_flags = app_flags;
+        self
+    }
+
+    /// Adds a cluster the endpoint accepts.
+    pub fn input_cluster(mut self, cluster_id: u16) -> Self {
+        self.endpoint.input_clusters.push(cluster_id);
+        self
+    }
+
+    /// Adds clusters the endpoint accepts.
+    pub fn input_clusters(mut self, cluster_ids: &[u16]) -> Self {
+        self.endpoint.input_clusters.extend_from_slice(cluster_ids);
+        self
+    }
+
+    /// Adds a cluster the endpoint may send.
+    pub fn output_cluster(mut self, cluster_id: u16) -> Self {
+        self.endpoint.output_clusters.push(cluster_id);
+        self
+    }
+
+    /// Adds clusters the endpoint may send.
+    pub fn output_clusters(mut self, cluster_ids: &[u16]) -> Self {
+        self.endpoint.output_clusters.extend_from_slice(cluster_ids);
+        self
+    }
+
+    /// Finishes the endpoint.
+    ///
+    /// Fails with [`Error::InvalidEndpoint`] if the ID is outside
+    /// [`MIN_ENDPOINT`]..=[`MAX_ENDPOINT`], or [`Error::TooManyClusters`]
+    /// if more than [`MAX_CLUSTERS`] clusters are listed.
+    pub fn build(self) -> Result<Endpoint, Error> {
+        let endpoint = self.endpoint;
+        if !(MIN_ENDPOINT..=MAX_ENDPOINT).contains(&endpoint.id) {
+            return Err(Error::InvalidEndpoint(endpoint.id));
+        }
+
+        let clusters = endpoint.input_clusters.len() + endpoint.output_clusters.len();
+        if clusters > MAX_CLUSTERS {
+            return Err(Error::TooManyClusters(clusters));
+        }
+
+        Ok(endpoint)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builder_validates () {
+        let endpoint = Endpoint::builder(1, 0x0104, 0x0005)
+            .input_clusters(&[0x0000, 0x0003])
+            .output_cluster(0x0019)
+            .build()
+            .unwrap();
+        let frame = endpoint.to_frame();
+        assert_eq!(frame.input_clusters, [0x0000, 0x0003]);
+        assert_eq!(frame.output_clusters, [0x0019]);
+
+        assert_eq!(Endpoint::builder(0, 0x0104, 0x0005).build(), Err(Error::InvalidEndpoint(0)));
+        assert_eq!(Endpoint::builder(0xF2, 0xA1E0, 0x0061).build(), Err(Error::InvalidEndpoint(0xF2)));
+
+        let clusters: Vec<u16> = (0..=MAX_CLUSTERS as u16).collect();
+        assert_eq!(
+            Endpoint::builder(1, 0x0104, 0x0005).input_clusters(&clusters).build(),
+            Err(Error::TooManyClusters(MAX_CLUSTERS + 1))
+        );
+        assert!(Endpoint::builder(1, 0x0104, 0x0005).input_clusters(&clusters[1..]).build().is_ok());
+    }
+}
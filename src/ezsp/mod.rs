@@ -1,5 +1,7 @@
 pub mod types;
-
-
-
-
+pub mod capabilities;
+pub mod counters;
+pub mod endpoint;
+pub mod policy;
+pub mod routing;
+pub mod value;
@@ -0,0 +1,159 @@
+//! Policy decisions and which of them each policy accepts.
+
+use crate::codec::EzspEnum;
+use super::types::{DecisionBitmask, DecisionBitmaskFlags, DecisionId, PolicyID};
+
+/// A decision that can be set for a policy.
+#[derive(Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum Decision {
+    /// One of the decisions identified by [`DecisionId`].
+    Id(DecisionId),
+
+    /// A combination of trust center decisions, used by [`PolicyID::TrustCenter`].
+    TrustCenter(DecisionBitmask),
+
+    /// Whether a feature is enabled, used by [`PolicyID::ZLL`]
+    /// and [`PolicyID::TCRejoinsUsingWellKnownKey`].
+    Enabled(bool),
+}
+
+impl Decision {
+    /// The decision ID byte sent to the NCP.
+    pub fn raw(self) -> u8 {
+        match self {
+            Decision::Id(id) => id.into_raw(),
+            // Every trust center decision flag fits in the low byte.
+            Decision::TrustCenter(bitmask) => *bitmask as u8,
+            Decision::Enabled(enabled) => enabled as u8,
+        }
+    }
+
+    /// Interprets a decision ID byte read from the NCP for `policy_id`.
+    pub fn from_raw(policy_id: PolicyID, raw: u8) -> Option<Decision> {
+        let decision = match policy_id {
+            PolicyID::TrustCenter => {
+                let bitmask = trust_center_bitmask(raw)?;
+                Decision::TrustCenter(bitmask)
+            }
+            PolicyID::ZLL | PolicyID::TCRejoinsUsingWellKnownKey => match raw {
+                0 | 1 => Decision::Enabled(raw == 1),
+                _ => return None,
+            },
+            _ => Decision::Id(DecisionId::from_raw(raw)?),
+        };
+
+        policy_id.allows(decision).then_some(decision)
+    }
+}
+
+impl From<DecisionId> for Decision {
+    fn from(id: DecisionId) -> Self {
+        Decision::Id(id)
+    }
+}
+
+impl From<DecisionBitmask> for Decision {
+    fn from(bitmask: DecisionBitmask) -> Self {
+        Decision::TrustCenter(bitmask)
+    }
+}
+
+impl From<DecisionBitmaskFlags> for Decision {
+    fn from(flag: DecisionBitmaskFlags) -> Self {
+        Decision::TrustCenter(flag.into())
+    }
+}
+
+impl PolicyID {
+    /// The decision IDs the policy accepts.
+    ///
+    /// Empty for policies whose decision is not a [`DecisionId`].
+    pub fn decision_ids(self) -> &'static [DecisionId] {
+        use DecisionId::*;
+
+        match self {
+            PolicyID::TrustCenter | PolicyID::ZLL | PolicyID::TCRejoinsUsingWellKnownKey => &[],
+            PolicyID::BindingModification => &[
+                DisallowBindingModification,
+                AllowBindingModification,
+                CheckBindingModificaitonsAreValidEndpointClusters,
+            ],
+            PolicyID::UnicastReplies => &[HostWillNotSupplyReply, HostWillSupplyReply],
+            PolicyID::PollHandler => &[PollHandlerIgnore, PollHandlerCallback],
+            PolicyID::MessageContentsInCallback => &[MessageTagOnlyInCallback, MessageTagAndContentsInCallback],
+            PolicyID::TCKeyRequest => &[
+                DenyTCKeyRequests,
+                AllowTCKeyRequestsAndSendCurrentKey,
+                AllowTCKeyRequestAndGenerateNewKey,
+            ],
+            PolicyID::AppKeyRequest => &[DenyAppKeyRquests, AllowAppKeyRequests],
+            PolicyID::PacketValidatedLubrary => &[
+                PacketValidateLibraryChecksEnabled,
+                PacketValidateLibraryChecksDisabled,
+            ],
+        }
+    }
+
+    /// Whether `decision` can be set for the policy.
+    pub fn allows(self, decision: Decision) -> bool {
+        match decision {
+            Decision::Id(id) => self.decision_ids().contains(&id),
+            Decision::TrustCenter(bitmask) => {
+                self == PolicyID::TrustCenter && trust_center_bitmask(*bitmask as u8) == Some(bitmask)
+            }
+            Decision::Enabled(_) => matches!(self, PolicyID::ZLL | PolicyID::TCRejoinsUsingWellKnownKey),
+        }
+    }
+}
+
+/// Builds a trust center bitmask from a decision byte, if it only sets known flags.
+fn trust_center_bitmask(raw: u8) -> Option<DecisionBitmask> {
+    use DecisionBitmaskFlags::*;
+
+    let mut bitmask = DecisionBitmask::none();
+    for flag in [AllowJoins, AllowUnsecuredRejoins, SendKeyInClear, IgnoreUnsecuredRejoins, JoinsUseInstallCodeKey, DeferJoins] {
+        if raw as u16 & *flag != 0 {
+            bitmask.set(flag);
+        }
+    }
+
+    (*bitmask == raw as u16).then_some(bitmask)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decisions_are_validated_per_policy () {
+        assert!(PolicyID::PollHandler.allows(DecisionId::PollHandlerCallback.into()));
+        assert!(!PolicyID::UnicastReplies.allows(DecisionId::PollHandlerCallback.into()));
+        assert!(!PolicyID::TrustCenter.allows(DecisionId::PollHandlerCallback.into()));
+
+        let joins = DecisionBitmaskFlags::AllowJoins | DecisionBitmaskFlags::JoinsUseInstallCodeKey;
+        assert!(PolicyID::TrustCenter.allows(joins.into()));
+        assert!(!PolicyID::PollHandler.allows(joins.into()));
+
+        assert!(PolicyID::ZLL.allows(Decision::Enabled(true)));
+        assert!(!PolicyID::AppKeyRequest.allows(Decision::Enabled(true)));
+    }
+
+    #[test]
+    fn raw_decisions () {
+        let decision = Decision::from(
+            DecisionBitmaskFlags::AllowJoins | DecisionBitmaskFlags::JoinsUseInstallCodeKey | DecisionBitmaskFlags::DeferJoins,
+        );
+        assert_eq!(decision.raw(), 0x31);
+        assert_eq!(Decision::from_raw(PolicyID::TrustCenter, 0x31), Some(decision));
+        assert_eq!(Decision::from_raw(PolicyID::TrustCenter, 0x00), Some(Decision::TrustCenter(DecisionBitmask::none())));
+        assert_eq!(Decision::from_raw(PolicyID::TrustCenter, 0x40), None);
+
+        assert_eq!(
+            Decision::from_raw(PolicyID::PollHandler, 0x31),
+            Some(Decision::Id(DecisionId::PollHandlerCallback))
+        );
+        assert_eq!(Decision::from_raw(PolicyID::PollHandler, 0x21), None);
+        assert_eq!(Decision::from_raw(PolicyID::TCRejoinsUsingWellKnownKey, 1), Some(Decision::Enabled(true)));
+    }
+}
@@ -0,0 +1,179 @@
+//! Many-to-one routing and the source routes learned from route records.
+
+use crate::ember::types::{ConcentratorType, EUI64, NodeId};
+use crate::frames::SetConcentrator;
+
+/// How a concentrator advertises itself with many-to-one route requests.
+///
+/// The defaults match the EmberZNet concentrator plugin.
+#[derive(Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Concentrator {
+    /// Whether the host keeps source routes for the whole network.
+    pub concentrator_type: ConcentratorType,
+
+    /// The minimum number of seconds between route requests.
+    pub min_time: u16,
+
+    /// The maximum number of seconds between route requests.
+    pub max_time: u16,
+
+    /// The number of route errors that trigger a new route request.
+    pub route_error_threshold: u8,
+
+    /// The number of APS delivery failures that trigger a new route request.
+    pub delivery_failure_threshold: u8,
+
+    /// The radius of route requests, or 0 for the stack's maximum.
+    pub max_hops: u8,
+}
+
+impl Concentrator {
+    /// A concentrator of `concentrator_type` with the default timing.
+    pub fn new(concentrator_type: ConcentratorType) -> Self {
+        Concentrator {
+            concentrator_type,
+            min_time: 10,
+            max_time: 60,
+            route_error_threshold: 3,
+            delivery_failure_threshold: 1,
+            max_hops: 0,
+        }
+    }
+
+    /// The setConcentrator command enabling the concentrator.
+    pub fn to_frame(&self) -> SetConcentrator {
+        SetConcentrator {
+            on: true,
+            concentrator_type: self.concentrator_type,
+            min_time: self.min_time,
+            max_time: self.max_time,
+            route_error_threshold: self.route_error_threshold,
+            delivery_failure_threshold: self.delivery_failure_threshold,
+            max_hops: self.max_hops,
+        }
+    }
+}
+
+/// The path to a node, from the last route record it sent.
+#[derive(Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct SourceRoute {
+    /// The node the route leads to.
+    pub destination: NodeId,
+
+    /// The EUI64 of the destination.
+    pub eui64: EUI64,
+
+    /// The relays between the concentrator and the destination, as
+    /// listed in the route record, starting with the one nearest
+    /// the destination. Empty for neighbors.
+    pub relays: Vec<NodeId>,
+}
+
+impl SourceRoute {
+    /// The node to hand a frame for the destination to.
+    pub fn next_hop(&self) -> NodeId {
+        self.relays.last().copied().unwrap_or(self.destination)
+    }
+
+    /// The number of hops to the destination.
+    pub fn hops(&self) -> usize {
+        self.relays.len() + 1
+    }
+}
+
+/// Source routes kept by the host of a high RAM concentrator,
+/// one per destination, most recently recorded first.
+#[derive(Clone, Eq, PartialEq, Default)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct SourceRouteTable {
+    routes: Vec<SourceRoute>,
+    capacity: Option<usize>,
+}
+
+impl SourceRouteTable {
+    /// An empty table without a size limit.
+    pub fn new() -> Self {
+        SourceRouteTable::default()
+    }
+
+    /// An empty table that forgets the least recently recorded
+    /// route once it holds `capacity` routes.
+    pub fn with_capacity(capacity: usize) -> Self {
+        SourceRouteTable { routes: Vec::with_capacity(capacity), capacity: Some(capacity) }
+    }
+
+    /// Records the route of a route record, replacing
+    /// any route known to the same destination.
+    pub fn record(&mut self, destination: NodeId, eui64: EUI64, relays: Vec<NodeId>) {
+        self.remove(destination);
+        if let Some(capacity) = self.capacity {
+            if capacity == 0 {
+                return;
+            }
+            self.routes.truncate(capacity - 1);
+        }
+        self.routes.insert(0, SourceRoute { destination, eui64, relays });
+    }
+
+    /// The route to `destination`, if one was recorded.
+    pub fn get(&self, destination: NodeId) -> Option<&SourceRoute> {
+        self.routes.iter().find(|route| route.destination == destination)
+    }
+
+    /// The route to the node with `eui64`, if one was recorded.
+    pub fn find_by_eui64(&self, eui64: &EUI64) -> Option<&SourceRoute> {
+        self.routes.iter().find(|route| route.eui64 == *eui64)
+    }
+
+    /// Forgets the route to `destination`, e.g. after a route error.
+    pub fn remove(&mut self, destination: NodeId) -> Option<SourceRoute> {
+        let index = self.routes.iter().position(|route| route.destination == destination)?;
+        Some(self.routes.remove(index))
+    }
+
+    /// Forgets every route.
+    pub fn clear(&mut self) {
+        self.routes.clear();
+    }
+
+    /// The number of routes known.
+    pub fn len(&self) -> usize {
+        self.routes.len()
+    }
+
+    /// Whether no routes are known.
+    pub fn is_empty(&self) -> bool {
+        self.routes.is_empty()
+    }
+
+    /// Every route, most recently recorded first.
+    pub fn iter(&self) -> impl Iterator<Item = &SourceRoute> {
+        self.routes.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn records_replace_and_evict () {
+        let mut table = SourceRouteTable::with_capacity(2);
+        table.record(0x1001, [1; 8], vec![]);
+        table.record(0x2002, [2; 8], vec![0x1001]);
+        assert_eq!(table.get(0x1001).unwrap().next_hop(), 0x1001);
+        assert_eq!(table.get(0x2002).unwrap().next_hop(), 0x1001);
+
+        table.record(0x2002, [2; 8], vec![0x3003, 0x4004]);
+        assert_eq!(table.len(), 2);
+        assert_eq!(table.find_by_eui64(&[2; 8]).unwrap().hops(), 3);
+        assert_eq!(table.get(0x2002).unwrap().next_hop(), 0x4004);
+
+        table.record(0x5005, [5; 8], vec![]);
+        assert_eq!(table.iter().map(|route| route.destination).collect::<Vec<_>>(), [0x5005, 0x2002]);
+        assert_eq!(table.remove(0x2002).map(|route| route.relays), Some(vec![0x3003, 0x4004]));
+        assert!(table.get(0x1001).is_none());
+    }
+}
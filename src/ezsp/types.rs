@@ -1,4 +1,9 @@
+use bitmask::bitmask;
+use crate::codec::EzspCodec;
+
 /// Identifies a configuration value.
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 #[repr(u8)]
 pub enum ConfigID {
     /// The NCP no longer supports configuration of packet
@@ -364,6 +369,8 @@ pub enum ConfigID {
 }
 
 /// Identifies a value.
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 #[repr(u8)]
 pub enum ValueID {
     /// The contents of the node data stack token.
@@ -756,6 +763,8 @@ pub enum ValueID {
 }
 
 /// Identifies a value based on specified characteristics.
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 #[repr(u8)]
 pub enum ExtendedValueID {
     /// The flags field associated with the specified endpoint.
@@ -785,6 +794,8 @@ pub enum ExtendedValueID {
 }
 
 /// Flags associated with the endpoint data configured on the NCP.
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 #[repr(u16)]
 pub enum EndpointFlags {
     /// Indicates that the endpoint is disabled and NOT discoverable via ZDO.
@@ -795,6 +806,8 @@ pub enum EndpointFlags {
 }
 
 /// Identifies a policy.
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 #[repr(u8)]
 pub enum PolicyID {
     /// Controls trust center behavior.
@@ -858,47 +871,57 @@ pub enum PolicyID {
     TCRejoinsUsingWellKnownKey = 0x09
 }
 
-/// The policy decision bitmask that controls the trust center decision strategies.
-#[repr(u16)]
-// TODO: bitmask types
-pub enum DecisionBitmask {
-    /// Disallow joins and rejoins.
-    /// 
-    /// EZSP_DECISION_BITMASK_DEFAULT_CONFIGURATION 
-    DefaultConfiguration = 0x0000,
-
-    /// Send the network key to all joining devices.
-    /// 
-    /// EZSP_DECISION_ALLOW_JOINS 
-    AllowJoins = 0x0001,
-    
-    /// Send the network key to all rejoining devices.
-    /// 
-    /// EZSP_DECISION_ALLOW_UNSECURED_REJOINS 
-    AllowUnsecuredRejoins = 0x0002,
-    
-    /// Send the network key in the clear.
-    /// 
-    /// EZSP_DECISION_SEND_KEY_IN_CLEAR 
-    SendKeyInClear = 0x0004,
-    
-    /// Do nothing for unsecured rejoins.
-    /// 
-    /// EZSP_DECISION_IGNORE_UNSECURED_REJOINS 
-    IgnoreUnsecuredRejoins = 0x0008,
-    
-    /// Allow joins if there is an entry in the transient key table.
-    /// 
-    /// EZSP_DECISION_JOINS_USE_INSTALL_CODE_KEY 
-    JoinsUseInstallCodeKey = 0x0010,
-
-    /// Delay sending the network key to a new joining device.
-    /// 
-    /// EZSP_DECISION_DEFER_JOINS 
-    DeferJoins = 0x0020
+bitmask! {
+    /// The policy decision bitmask that controls the trust center decision strategies.
+    /// 
+    /// EzspDecisionBitmask
+    pub mask DecisionBitmask: u16 where
+    /// The policy decision bitmask that controls the trust center decision strategies.
+    /// 
+    /// EzspDecisionBitmask
+    flags DecisionBitmaskFlags {
+        /// Disallow joins and rejoins.
+        /// 
+        /// EZSP_DECISION_BITMASK_DEFAULT_CONFIGURATION 
+        DefaultConfiguration = 0x0000,
+
+        /// Send the network key to all joining devices.
+        /// 
+        /// EZSP_DECISION_ALLOW_JOINS 
+        AllowJoins = 0x0001,
+
+        /// Send the network key to all rejoining devices.
+        /// 
+        /// EZSP_DECISION_ALLOW_UNSECURED_REJOINS 
+        AllowUnsecuredRejoins = 0x0002,
+
+        /// Send the network key in the clear.
+        /// 
+        /// EZSP_DECISION_SEND_KEY_IN_CLEAR 
+        SendKeyInClear = 0x0004,
+
+        /// Do nothing for unsecured rejoins.
+        /// 
+        /// EZSP_DECISION_IGNORE_UNSECURED_REJOINS 
+        IgnoreUnsecuredRejoins = 0x0008,
+
+        /// Allow joins if there is an entry in the transient key table.
+        /// 
+        /// EZSP_DECISION_JOINS_USE_INSTALL_CODE_KEY 
+        JoinsUseInstallCodeKey = 0x0010,
+
+        /// Delay sending the network key to a new joining device.
+        /// 
+        /// EZSP_DECISION_DEFER_JOINS 
+        DeferJoins = 0x0020
+    }
 }
 
+impl_bitmask_codec!(DecisionBitmask: u16);
+
 /// Identifies a policy decision.
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 #[repr(u8)]
 pub enum DecisionId {
     /// Delay sending the network key to a new joining device.
@@ -1033,6 +1056,8 @@ pub enum DecisionId {
 }
 
 /// Manufacturing token ID.
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 #[repr(u8)]
 pub enum  MaunfacturingTokenID {
     /// Custom version (2 bytes).
@@ -1117,6 +1142,8 @@ pub enum  MaunfacturingTokenID {
 }
 
 /// Status values used by EZSP.
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 #[repr(u8)]
 pub enum Status {
     /// Success.
@@ -1311,7 +1338,7 @@ pub enum Status {
     /// EZSP Security Key is not set
     /// 
     /// EZSP_ERROR_SECURITY_KEY_NOT_SET 
-    ErrorSecurityKeyNotSet = 047,
+    ErrorSecurityKeyNotSet = 0x47,
     /// EZSP Security Parameters are not set.
     /// 
     /// EZSP_ERROR_SECURITY_PARAMETERS_NOT_SET 
@@ -1459,6 +1486,8 @@ pub enum Status {
 /// Network scan types.
 /// 
 /// EzspNetworkScanType 
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 #[repr(u8)]
 pub enum NetworkScanType {
     /// An energy scan scans each channel for its RSSI value.
@@ -1474,6 +1503,8 @@ pub enum NetworkScanType {
 /// Differentiates ZLL network operations.
 /// 
 /// EzspZllNetworkOperation
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 #[repr(u8)]
 pub enum ZLLNetworkOperation {
     /// ZLL form network command.
@@ -1489,6 +1520,8 @@ pub enum ZLLNetworkOperation {
 /// Validates Source Route Overhead Information cached.
 /// 
 /// EzspSourceRouteOverheadInformation 
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
 #[repr(u8)]
 pub enum SourceRouteOverheadInformation {
     /// EZSP source route overhead unknown.
@@ -1497,3 +1530,35 @@ pub enum SourceRouteOverheadInformation {
     OverheadUnknown = 0xFF
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::{EzspDecode, Recognized, UnknownValue};
+    use crate::ember::types::Status as EmberStatus;
+
+    #[test]
+    fn status_try_from () {
+        assert_eq!(Status::try_from(0x47), Ok(Status::ErrorSecurityKeyNotSet));
+        assert_eq!(Status::try_from(0x2F), Err(UnknownValue(0x2F)));
+        assert_eq!(u8::from(Status::NoError), 0xFF);
+
+        assert_eq!(EmberStatus::try_from(0x90), Ok(EmberStatus::NetworkUp));
+        assert_eq!(EmberStatus::try_from(0x03), Err(UnknownValue(0x03)));
+    }
+
+    #[test]
+    fn u16_enums_try_from () {
+        assert_eq!(EndpointFlags::try_from(0x0001u16), Ok(EndpointFlags::Enabled));
+        assert_eq!(EndpointFlags::try_from(0x0100u16), Err(UnknownValue(0x0100)));
+    }
+
+    #[test]
+    fn unknown_values_are_preserved () {
+        let (config, _) = Recognized::<ConfigID>::decode_from_slice(&[0x7E]).unwrap();
+        assert_eq!(config, Recognized::Unknown(0x7E));
+        assert_eq!(config.known(), None);
+
+        let (config, _) = Recognized::<ConfigID>::decode_from_slice(&[0x02]).unwrap();
+        assert_eq!(config.known(), Some(ConfigID::NeighborTableSize));
+    }
+}
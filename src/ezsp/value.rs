@@ -0,0 +1,329 @@
+//! Typed forms of the values read and written with getValue,
+//! setValue and getExtendedValue.
+//!
+//! The NCP sends every value as a byte array. Values whose layout is
+//! known are decoded into a typed variant, anything else (or anything
+//! with an unexpected length) is kept as raw bytes.
+
+use crate::codec::{EzspCodec, EzspDecode, EzspEncode, EzspEnum, WireSize};
+use crate::ember::types::{LeaveReason, NodeId};
+use super::types::{EndpointFlags, ExtendedValueID, SourceRouteOverheadInformation, ValueID};
+
+/// The layout of a value's bytes.
+#[derive(Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum ValueKind {
+    /// A single byte that is either 0 or 1.
+    Bool,
+
+    /// A single byte.
+    U8,
+
+    /// A little endian 16-bit integer.
+    U16,
+
+    /// A little endian 32-bit integer.
+    U32,
+
+    /// The stack version, see [`VersionInfo`].
+    VersionInfo,
+}
+
+impl ValueKind {
+    /// The number of bytes the value is sent as.
+    pub fn length(self) -> usize {
+        match self {
+            ValueKind::Bool | ValueKind::U8 => 1,
+            ValueKind::U16 => 2,
+            ValueKind::U32 => 4,
+            ValueKind::VersionInfo => VersionInfo::WIRE_SIZE,
+        }
+    }
+}
+
+/// The version of the stack running on the NCP.
+///
+/// EZSP_VALUE_VERSION_INFO
+#[derive(Copy, Clone, Eq, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct VersionInfo {
+    /// The build number.
+    pub build: u16,
+
+    /// The major version.
+    pub major: u8,
+
+    /// The minor version.
+    pub minor: u8,
+
+    /// The patch version.
+    pub patch: u8,
+
+    /// The special version.
+    pub special: u8,
+
+    /// The release type, e.g. 0xAA for GA releases.
+    pub version_type: u8,
+}
+
+/// A value read from or written to the NCP.
+#[derive(Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum Value {
+    /// A flag.
+    Bool(bool),
+
+    /// A single byte.
+    U8(u8),
+
+    /// A 16-bit integer.
+    U16(u16),
+
+    /// A 32-bit integer.
+    U32(u32),
+
+    /// The stack version.
+    VersionInfo(VersionInfo),
+
+    /// The bytes of a value without a known layout.
+    Raw(Vec<u8>),
+}
+
+impl Value {
+    /// Decodes the bytes of the value identified by `value_id`.
+    ///
+    /// Falls back to [`Value::Raw`] if the layout of the
+    /// value is not known or the bytes do not match it.
+    pub fn decode(value_id: ValueID, bytes: &[u8]) -> Value {
+        let Some(kind) = value_id.kind().filter(|kind| kind.length() == bytes.len()) else {
+            return Value::Raw(bytes.to_vec());
+        };
+
+        match kind {
+            ValueKind::Bool if bytes[0] <= 1 => Value::Bool(bytes[0] == 1),
+            ValueKind::Bool => Value::Raw(bytes.to_vec()),
+            ValueKind::U8 => Value::U8(bytes[0]),
+            ValueKind::U16 => Value::U16(u16::from_le_bytes([bytes[0], bytes[1]])),
+            ValueKind::U32 => Value::U32(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])),
+            ValueKind::VersionInfo => match VersionInfo::decode_from_slice(bytes) {
+                Ok((version, _)) => Value::VersionInfo(version),
+                Err(_) => Value::Raw(bytes.to_vec()),
+            },
+        }
+    }
+
+    /// The layout of the value, or `None` for raw bytes.
+    pub fn kind(&self) -> Option<ValueKind> {
+        match self {
+            Value::Bool(_) => Some(ValueKind::Bool),
+            Value::U8(_) => Some(ValueKind::U8),
+            Value::U16(_) => Some(ValueKind::U16),
+            Value::U32(_) => Some(ValueKind::U32),
+            Value::VersionInfo(_) => Some(ValueKind::VersionInfo),
+            Value::Raw(_) => None,
+        }
+    }
+
+    /// The bytes the value is sent as.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        match self {
+            Value::Bool(value) => vec![*value as u8],
+            Value::U8(value) => vec![*value],
+            Value::U16(value) => value.to_le_bytes().to_vec(),
+            Value::U32(value) => value.to_le_bytes().to_vec(),
+            Value::VersionInfo(version) => {
+                let mut bytes = [0u8; VersionInfo::WIRE_SIZE];
+                let length = version.encode_to_slice(&mut bytes).unwrap_or_default();
+                bytes[..length].to_vec()
+            }
+            Value::Raw(bytes) => bytes.clone(),
+        }
+    }
+}
+
+impl From<bool> for Value {
+    fn from(value: bool) -> Self {
+        Value::Bool(value)
+    }
+}
+
+impl From<u8> for Value {
+    fn from(value: u8) -> Self {
+        Value::U8(value)
+    }
+}
+
+impl From<u16> for Value {
+    fn from(value: u16) -> Self {
+        Value::U16(value)
+    }
+}
+
+impl From<u32> for Value {
+    fn from(value: u32) -> Self {
+        Value::U32(value)
+    }
+}
+
+impl From<VersionInfo> for Value {
+    fn from(version: VersionInfo) -> Self {
+        Value::VersionInfo(version)
+    }
+}
+
+impl From<Vec<u8>> for Value {
+    fn from(bytes: Vec<u8>) -> Self {
+        Value::Raw(bytes)
+    }
+}
+
+impl ValueID {
+    /// The layout of the value, or `None` if it is only
+    /// available as raw bytes.
+    pub fn kind(self) -> Option<ValueKind> {
+        use ValueID::*;
+
+        let kind = match self {
+            UARTSynchCallbacks | StackTokenWriting | StackIsPerformingRejoin | RadioHoldOff
+            | EnableR21Behavior | EnablePTA | UseNegotiatedPowerByLPD | DelayedJoinActivation => ValueKind::Bool,
+            MACPassthroughFlags | FreeBuffers | DecsriptionCapability | StackDeviceRequesetSequenceNumber
+            | NextHostRejoinReason | LastRejoinReason | NextZigbeeSequenceNumber | CAAThreshold
+            | RetryDeviceType | AntennaMode | MGFLibOptions | PTADirectionalPriorityPulseWidth
+            | PTAPHYSelectTimeout | AntennaRXMode | NetworkKeyTimeout | ForceTXAfterFailedCCAAttempts
+            | MaxBeaconsToStore | EndDeviceKeepAliceSupportMode | ActiveRadioCnfig | KeyStorageVersion => ValueKind::U8,
+            EmberNetPassthroughSourceAddress | MaximumIncomingTransferSize | MaximumOutgoingTransferSize
+            | ExtendedSecurityBitmask | NodeShortID | TransientKeyTimeoutS | EndDeviceTimeoutOptionsMask
+            | TransientDeviceTimeout => ValueKind::U16,
+            NWKFramecounter | APSFrmaEcounter | PTAOptions => ValueKind::U32,
+            VerisonInfo => ValueKind::VersionInfo,
+            _ => return None,
+        };
+
+        Some(kind)
+    }
+
+    /// Whether `value` can be written to the value.
+    ///
+    /// Raw bytes are always accepted.
+    pub fn accepts(self, value: &Value) -> bool {
+        value.kind().is_none_or(|kind| self.kind() == Some(kind))
+    }
+}
+
+/// The overhead of source routing to a destination.
+#[derive(Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum SourceRouteOverhead {
+    /// The number of bytes source routing adds to the network frame.
+    Bytes(u8),
+
+    /// The NCP has no source route to the destination cached.
+    ///
+    /// EZSP_SOURCE_ROUTE_OVERHEAD_UNKNOWN
+    Unknown,
+}
+
+/// An extended value read from the NCP.
+#[derive(Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum ExtendedValue {
+    /// The flags of an endpoint.
+    EndpointFlags(EndpointFlags),
+
+    /// Why the node last left the network.
+    LastLeaveReason {
+        /// The reason the node left.
+        reason: LeaveReason,
+
+        /// The node that told it to leave, or [`UNKNOWN_NODE_ID`](crate::ember::types::UNKNOWN_NODE_ID)
+        /// if the leave was caused by an API call.
+        node_id: NodeId,
+    },
+
+    /// The source routing overhead to a destination.
+    SourceRouteOverhead(SourceRouteOverhead),
+
+    /// The bytes of a value that could not be decoded.
+    Raw(Vec<u8>),
+}
+
+impl ExtendedValue {
+    /// Decodes the bytes of the extended value identified by `value_id`.
+    ///
+    /// Falls back to [`ExtendedValue::Raw`] if the bytes do not match
+    /// the layout of the value.
+    pub fn decode(value_id: ExtendedValueID, bytes: &[u8]) -> ExtendedValue {
+        let value = match (value_id, bytes) {
+            (ExtendedValueID::EndpointFlags, &[low, high]) => {
+                EndpointFlags::from_raw(u16::from_le_bytes([low, high])).map(ExtendedValue::EndpointFlags)
+            }
+            (ExtendedValueID::LastLEaveReason, &[reason, low, high]) => {
+                LeaveReason::from_raw(reason).map(|reason| ExtendedValue::LastLeaveReason {
+                    reason,
+                    node_id: u16::from_le_bytes([low, high]),
+                })
+            }
+            (ExtendedValueID::GetSourceRouteOverhead, &[overhead]) => {
+                let overhead = if overhead == SourceRouteOverheadInformation::OverheadUnknown.into_raw() {
+                    SourceRouteOverhead::Unknown
+                } else {
+                    SourceRouteOverhead::Bytes(overhead)
+                };
+                Some(ExtendedValue::SourceRouteOverhead(overhead))
+            }
+            _ => None,
+        };
+
+        value.unwrap_or_else(|| ExtendedValue::Raw(bytes.to_vec()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ember::types::UNKNOWN_NODE_ID;
+
+    #[test]
+    fn values_decode_by_kind () {
+        assert_eq!(Value::decode(ValueID::FreeBuffers, &[0xF0]), Value::U8(0xF0));
+        assert_eq!(Value::decode(ValueID::NodeShortID, &[0x34, 0x12]), Value::U16(0x1234));
+        assert_eq!(Value::decode(ValueID::StackTokenWriting, &[1]), Value::Bool(true));
+        assert_eq!(Value::decode(ValueID::StackTokenWriting, &[2]), Value::Raw(vec![2]));
+        assert_eq!(Value::decode(ValueID::NodeShortID, &[0x34]), Value::Raw(vec![0x34]));
+        assert_eq!(Value::decode(ValueID::TokenStackNodeData, &[1, 2, 3]), Value::Raw(vec![1, 2, 3]));
+
+        let bytes = [0x10, 0x02, 7, 4, 3, 0, 0xAA];
+        let version = VersionInfo { build: 0x0210, major: 7, minor: 4, patch: 3, special: 0, version_type: 0xAA };
+        assert_eq!(Value::decode(ValueID::VerisonInfo, &bytes), Value::VersionInfo(version));
+        assert_eq!(Value::VersionInfo(version).to_bytes(), bytes);
+    }
+
+    #[test]
+    fn accepted_values () {
+        assert!(ValueID::NodeShortID.accepts(&Value::U16(1)));
+        assert!(!ValueID::NodeShortID.accepts(&Value::U8(1)));
+        assert!(ValueID::NodeShortID.accepts(&Value::Raw(vec![1])));
+        assert!(!ValueID::TokenStackNodeData.accepts(&Value::Bool(true)));
+    }
+
+    #[test]
+    fn extended_values () {
+        assert_eq!(
+            ExtendedValue::decode(ExtendedValueID::EndpointFlags, &[1, 0]),
+            ExtendedValue::EndpointFlags(EndpointFlags::Enabled)
+        );
+        assert_eq!(
+            ExtendedValue::decode(ExtendedValueID::LastLEaveReason, &[3, 0xFD, 0xFF]),
+            ExtendedValue::LastLeaveReason { reason: LeaveReason::ZdoLeaveMessage, node_id: UNKNOWN_NODE_ID }
+        );
+        assert_eq!(
+            ExtendedValue::decode(ExtendedValueID::GetSourceRouteOverhead, &[0xFF]),
+            ExtendedValue::SourceRouteOverhead(SourceRouteOverhead::Unknown)
+        );
+        assert_eq!(
+            ExtendedValue::decode(ExtendedValueID::GetSourceRouteOverhead, &[6]),
+            ExtendedValue::SourceRouteOverhead(SourceRouteOverhead::Bytes(6))
+        );
+        assert_eq!(ExtendedValue::decode(ExtendedValueID::LastLEaveReason, &[3]), ExtendedValue::Raw(vec![3]));
+    }
+}
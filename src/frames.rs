@@ -1,171 +1,173 @@
+use crate::codec::{EzspDecode, EzspEncode};
 
 /// Typing for the frame codes used to identify a frame type.
 pub type FrameID = u16;
 
-pub trait Frame {
+/// An EZSP command frame and the response the NCP replies with.
+///
+/// The implementing struct holds the command parameters.
+pub trait Frame: EzspEncode + EzspDecode {
+    /// The parameters returned by the NCP.
+    type Response: EzspEncode + EzspDecode;
+
     fn frame_id () -> FrameID;
     fn frame_name () -> &'static str;
 }
 
+/// An EZSP callback the NCP sends without being asked.
+///
+/// The implementing struct holds the callback parameters.
+pub trait CallbackFrame: EzspEncode + EzspDecode {
+    fn frame_id () -> FrameID;
+    fn frame_name () -> &'static str;
+}
+
+/// Declares an EZSP frame in the style of the UG100 reference.
+///
+/// Generates a struct named after the frame holding the
+/// command parameters, a struct holding the response
+/// parameters, their wire encoding in declaration order,
+/// and the [`Frame`] implementation tying them together.
+///
+/// ```ignore
+/// frame! {
+///     /// Frame documentation.
+///     Version: 0x0000
+///
+///     /// Parameters documentation.
+///     parameters {
+///         /// Field documentation.
+///         desired_protocol_version = u8,
+///     }
+///
+///     /// Response documentation.
+///     response VersionResponse {
+///         protocol_version = u8,
+///     }
+/// }
+/// ```
 macro_rules! frame {
-    // 1. Parse struct meta attributes, its name and its type.
     (
-        $(#[$frame_attr: meta])* $frame_name: ident : $T: tt
-        $(#[$parameters_attr: meta])* parameters { $($parameters_token: tt)+ }
-    ) => {
-        frame! {
-            frame_meta: [ $(#[$frame_attr])* ],
-            frame_name: $frame_name,
-            frame_id: $T,
-            parameters_meta: [ $(#[$parameters_attr])* ],
-            flags: [
-                []
-            ],
-            $($parameters_token)+
+        $(#[$frame_attr: meta])* $frame_name: ident : $frame_id: literal
+
+        $(#[$parameters_attr: meta])* parameters {
+            $( $(#[$parameter_attr: meta])* $parameter_name: ident = $parameter_type: ty ),* $(,)?
         }
-    };
 
-    // 2. Parse flag meta attributes.
-    (
-        frame_meta: [ $(#[$frame_attr: meta])* ],
-        frame_name: $frame_name: ident,
-        frame_id: $T: tt,
-        parameters_meta: [ $(#[$parameters_attr: meta])* ],
-        flags: [
+        $(#[$response_attr: meta])* response $response_name: ident {
+            $( $(#[$field_attr: meta])* $field_name: ident = $field_type: ty ),* $(,)?
+        }
+    ) => {
+        $(#[$frame_attr])*
+        ///
+        $(#[$parameters_attr])*
+        #[derive(Clone, PartialEq, $crate::codec::EzspCodec)]
+        #[cfg_attr(feature = "std", derive(Debug))]
+        pub struct $frame_name {
             $(
-                meta: [ $(#[$flag_attr: meta])* ]
-                flag: $flag_name: ident = $flag_value: tt;
+                $(#[$parameter_attr])*
+                pub $parameter_name: $parameter_type,
             )*
-            [ $(#[$prev_attr: meta])* ]
-        ],
-        #[$next_attr: meta] $($parameters_token: tt)*
-    ) => {
-        frame! {
-            frame_meta: [ $(#[$frame_attr])* ],
-            frame_name: $frame_name,
-            frame_id: $T,
-            parameters_meta: [ $(#[$parameters_attr])* ],
-            flags: [
-                $(
-                    meta: [ $(#[$flag_attr])* ]
-                    flag: $flag_name = $flag_value;
-                )*
-                [ $(#[$prev_attr])* #[$next_attr] ]
-            ],
-            $($parameters_token)*
         }
-    };
 
-    // 3.A Parse the flag itself.
-    //
-    // Handles the case with trailing comma.
-    (
-        frame_meta: [ $(#[$frame_attr: meta])* ],
-        frame_name: $frame_name: ident,
-        frame_id: $T: tt,
-        parameters_meta: [ $(#[$parameters_attr: meta])* ],
-        flags: [
+        $(#[$response_attr])*
+        #[derive(Clone, PartialEq, $crate::codec::EzspCodec)]
+        #[cfg_attr(feature = "std", derive(Debug))]
+        pub struct $response_name {
             $(
-                meta: [ $(#[$flag_attr: meta])* ]
-                flag: $flag_name: ident = $flag_value: tt;
+                $(#[$field_attr])*
+                pub $field_name: $field_type,
             )*
-            [ $(#[$next_attr: meta])* ]
-        ],
-        $next_name: ident = $next_value: tt, $($parameters_token: tt)*
-    ) => {
-        frame! {
-            frame_meta: [ $(#[$frame_attr])* ],
-            frame_name: $frame_name,
-            frame_id: $T,
-            parameters_meta: [ $(#[$parameters_attr])* ],
-            flags: [
-                $(
-                    meta: [ $(#[$flag_attr])* ]
-                    flag: $flag_name = $flag_value;
-                )*
-                meta: [ $(#[$next_attr])* ]
-                flag: $next_name = $next_value;
-                []
-            ],
-            $($parameters_token)*
+        }
+
+        impl $crate::frames::Frame for $frame_name {
+            type Response = $response_name;
+
+            fn frame_id () -> $crate::frames::FrameID {
+                $frame_id
+            }
+
+            fn frame_name () -> &'static str {
+                stringify!($frame_name)
+            }
         }
     };
+}
 
-    // 3.B Parse the last flag if missing trailing comma.
+/// Declares an EZSP callback in the style of the UG100 reference.
+///
+/// Generates a struct named after the callback holding its
+/// parameters, their wire encoding in declaration order and
+/// the [`CallbackFrame`] implementation.
+///
+/// ```ignore
+/// callback! {
+///     /// Callback documentation.
+///     TimerHandler: 0x000F
+///
+///     /// Parameters documentation.
+///     parameters {
+///         /// Field documentation.
+///         timer_id = u8,
+///     }
+/// }
+/// ```
+macro_rules! callback {
     (
-        frame_meta: [ $(#[$frame_attr: meta])* ],
-        frame_name: $frame_name: ident,
-        frame_id: $T: tt,
-        parameters_meta: [ $(#[$parameters_attr: meta])* ],
-        en_name: $en_name: ident,
-        flags: [
+        $(#[$callback_attr: meta])* $callback_name: ident : $frame_id: literal
+
+        $(#[$parameters_attr: meta])* parameters {
+            $( $(#[$parameter_attr: meta])* $parameter_name: ident = $parameter_type: ty ),* $(,)?
+        }
+    ) => {
+        $(#[$callback_attr])*
+        ///
+        $(#[$parameters_attr])*
+        #[derive(Clone, PartialEq, $crate::codec::EzspCodec)]
+        #[cfg_attr(feature = "std", derive(Debug))]
+        pub struct $callback_name {
             $(
-                meta: [ $(#[$flag_attr: meta])* ]
-                flag: $flag_name: ident = $flag_value: tt;
+                $(#[$parameter_attr])*
+                pub $parameter_name: $parameter_type,
             )*
-            [ $(#[$next_attr: meta])* ]
-        ],
-        $next_name: ident = $next_value: tt
-    ) => {
-        bitmask! {
-            frame_meta: [ $(#[$frame_attr])* ],
-            frame_name: $frame_name,
-            frame_id: $T,
-            parameters_meta: [ $(#[$parameters_attr])* ],
-            flags: [
-                $(
-                    meta: [ $(#[$flag_attr])* ]
-                    flag: $flag_name = $flag_value;
-                )*
-                meta: [ $(#[$next_attr])* ]
-                flag: $next_name = $next_value;
-                []
-            ],
         }
-    };
 
-    // 4. End of the line. Time to declare the struct and enum.
-    (
-        frame_meta: [ $(#[$frame_attr: meta])* ],
-        frame_name: $frame_name: ident,
-        frame_id: $T: tt,
-        parameters_meta: [ $(#[$parameters_attr: meta])* ],
-        flags: [
-            $(
-                meta: [ $(#[$flag_attr: meta])* ]
-                flag: $flag_name: ident = $flag_value: tt;
-            )+
-            []
-        ],
-    ) => {
-        // #[repr($T)]
-        // #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
-        // #[cfg_attr(feature = "std", derive(Debug, Hash))]
-        // #[allow(dead_code)]
-        // $(#[$parameters_attr])*
-        // enum $en_name {
-        //     $(
-        //         $(#[$flag_attr])*
-        //         $flag_name = $flag_value
-        //     ),+
-        // }
-        #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd)]
-        #[cfg_attr(feature = "std", derive(Debug, Hash))]
-        #[allow(dead_code)]
-        $(#[$frame_attr])*
-        struct $frame_name {
-            
+        impl $crate::frames::CallbackFrame for $callback_name {
+            fn frame_id () -> $crate::frames::FrameID {
+                $frame_id
+            }
+
+            fn frame_name () -> &'static str {
+                stringify!($callback_name)
+            }
         }
-        // frame!(@IMPL $frame_name $T $en_name, {
-        //     $($flag_name = $flag_value),+
-        // });
     };
 }
 
+pub mod configuration;
+pub mod counters;
+pub mod endpoint;
+pub mod messaging;
+pub mod networking;
+pub mod policy;
+pub mod trust_center;
+pub mod utilities;
+pub mod value;
+
+pub use configuration::*;
+pub use counters::*;
+pub use endpoint::*;
+pub use messaging::*;
+pub use networking::*;
+pub use policy::*;
+pub use trust_center::*;
+pub use utilities::*;
+pub use value::*;
+
 frame! {
     /// This command allows the Host to specify the desired EZSP
     /// version and must be sent before any other command.
-    /// 
+    ///
     /// The response provides information about the firmware running on the NCP.
     Version: 0x0000
 
@@ -177,4 +179,59 @@ frame! {
         /// EZSP_PROTOCOL_VERSION.
         desired_protocol_version = u8,
     }
+
+    /// Information about the firmware running on the NCP.
+    response VersionResponse {
+        /// The EZSP version the NCP is using.
+        protocol_version = u8,
+
+        /// The type of stack running on the NCP (2).
+        stack_type = u8,
+
+        /// The version number of the stack.
+        stack_version = u16,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::{CodecError, Reader, Writer};
+
+    #[test]
+    fn version_frame () {
+        assert_eq!(Version::frame_id(), 0x0000);
+        assert_eq!(Version::frame_name(), "Version");
+
+        let mut buf = [0u8; 1];
+        let command = Version { desired_protocol_version: 13 };
+        assert_eq!(command.encode_to_slice(&mut buf), Ok(1));
+        assert_eq!(buf, [13]);
+    }
+
+    #[test]
+    fn version_response () {
+        let (response, consumed) = VersionResponse::decode_from_slice(&[0x0D, 0x02, 0x30, 0x74]).unwrap();
+
+        assert_eq!(consumed, 4);
+        assert_eq!(response, VersionResponse {
+            protocol_version: 0x0D,
+            stack_type: 0x02,
+            stack_version: 0x7430,
+        });
+
+        let mut reader = Reader::new(&[0x0D, 0x02]);
+        assert_eq!(VersionResponse::decode(&mut reader), Err(CodecError::UnexpectedEnd));
+    }
+
+    #[test]
+    fn frame_round_trip () {
+        let mut buf = [0u8; 4];
+        let mut writer = Writer::new(&mut buf);
+        let response = VersionResponse { protocol_version: 8, stack_type: 2, stack_version: 0x6700 };
+
+        response.encode(&mut writer).unwrap();
+        assert_eq!(writer.position(), 4);
+        assert_eq!(VersionResponse::decode_from_slice(&buf), Ok((response, 4)));
+    }
 }
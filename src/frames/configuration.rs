@@ -0,0 +1,79 @@
+//! Configuration frames.
+
+use crate::codec::Recognized;
+use crate::ezsp::types::{ConfigID, Status};
+
+frame! {
+    /// Reads a configuration value from the NCP.
+    GetConfigurationValue: 0x0052
+
+    /// Supplies parameters for the getConfigurationValue command frame.
+    parameters {
+        /// Identifies which configuration value to read.
+        config_id = ConfigID,
+    }
+
+    /// The configuration value read from the NCP.
+    response GetConfigurationValueResponse {
+        /// EZSP_SUCCESS if the value was read successfully,
+        /// EZSP_ERROR_INVALID_ID if the NCP does not recognize configId.
+        status = Recognized<Status>,
+
+        /// The configuration value.
+        value = u16,
+    }
+}
+
+frame! {
+    /// Writes a configuration value to the NCP.
+    ///
+    /// Configuration values can be modified by the Host after the
+    /// NCP has reset. Once the status of the stack changes to
+    /// EMBER_NETWORK_UP, configuration values can no longer be
+    /// modified and this command will respond with
+    /// EZSP_ERROR_INVALID_CALL.
+    SetConfigurationValue: 0x0053
+
+    /// Supplies parameters for the setConfigurationValue command frame.
+    parameters {
+        /// Identifies which configuration value to change.
+        config_id = ConfigID,
+
+        /// The new configuration value.
+        value = u16,
+    }
+
+    /// The result of writing the configuration value.
+    response SetConfigurationValueResponse {
+        /// EZSP_SUCCESS if the configuration value was changed,
+        /// EZSP_ERROR_OUT_OF_MEMORY if the new value exceeded the
+        /// available memory, EZSP_ERROR_INVALID_VALUE if the new
+        /// value was out of bounds, EZSP_ERROR_INVALID_ID if the NCP
+        /// does not recognize configId, EZSP_ERROR_INVALID_CALL if
+        /// configuration values can no longer be modified.
+        status = Recognized<Status>,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::{EzspDecode, EzspEncode};
+    use crate::frames::Frame;
+
+    #[test]
+    fn configuration_frames () {
+        assert_eq!(GetConfigurationValue::frame_id(), 0x0052);
+        assert_eq!(SetConfigurationValue::frame_id(), 0x0053);
+
+        let mut buf = [0u8; 3];
+        let command = SetConfigurationValue { config_id: ConfigID::IndirectTransmissionTimeout, value: 7680 };
+        assert_eq!(command.encode_to_slice(&mut buf), Ok(3));
+        assert_eq!(buf, [0x12, 0x00, 0x1E]);
+
+        assert_eq!(
+            GetConfigurationValueResponse::decode_from_slice(&[0x00, 0x10, 0x00]),
+            Ok((GetConfigurationValueResponse { status: Status::Success.into(), value: 16 }, 3))
+        );
+    }
+}
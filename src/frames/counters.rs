@@ -0,0 +1,48 @@
+//! Counter frames.
+
+use crate::codec::Recognized;
+use crate::ember::types::CounterType;
+use crate::ezsp::counters::COUNTER_TYPE_COUNT;
+
+frame! {
+    /// Retrieves Ember counters. See the EmberCounterType
+    /// enumeration for the counter types.
+    ReadCounters: 0x00F1
+
+    /// Supplies parameters for the readCounters command frame.
+    parameters {}
+
+    /// The counters on the NCP.
+    response ReadCountersResponse {
+        /// A list of all counter values ordered according
+        /// to the EmberCounterType enumeration.
+        values = [u16; COUNTER_TYPE_COUNT],
+    }
+}
+
+frame! {
+    /// Retrieves and clears Ember counters. See the
+    /// EmberCounterType enumeration for the counter types.
+    ReadAndClearCounters: 0x0065
+
+    /// Supplies parameters for the readAndClearCounters command frame.
+    parameters {}
+
+    /// The counters on the NCP before they were cleared.
+    response ReadAndClearCountersResponse {
+        /// A list of all counter values ordered according
+        /// to the EmberCounterType enumeration.
+        values = [u16; COUNTER_TYPE_COUNT],
+    }
+}
+
+callback! {
+    /// This call is fired when a counter exceeds its threshold.
+    CounterRolloverHandler: 0x00F2
+
+    /// Supplies parameters for the counterRolloverHandler callback.
+    parameters {
+        /// Type of Counter.
+        counter_type = Recognized<CounterType>,
+    }
+}
@@ -0,0 +1,124 @@
+//! Endpoint frames.
+//!
+//! The addEndpoint command sends both cluster counts before either
+//! cluster list, so unlike the other frames its parameters cannot be
+//! declared with the frame macro and are encoded by hand.
+
+use crate::codec::{CodecError, EzspCodec, EzspDecode, EzspEncode, Reader, Recognized, Writer};
+use crate::ezsp::types::Status;
+use crate::frames::{Frame, FrameID};
+
+/// Configures endpoint information on the NCP. The NCP does not
+/// remember these settings after a reset. Endpoints can be added
+/// by the Host after the NCP has reset. Once the status of the
+/// stack changes to EMBER_NETWORK_UP, endpoints can no longer be
+/// added and this command will respond with EZSP_ERROR_INVALID_CALL.
+///
+/// Supplies parameters for the addEndpoint command frame.
+#[derive(Clone, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct AddEndpoint {
+    /// The application endpoint to be added.
+    pub endpoint: u8,
+
+    /// The endpoint's application profile.
+    pub profile_id: u16,
+
+    /// The endpoint's device ID within the application profile.
+    pub device_id: u16,
+
+    /// The device version and flags indicating description
+    /// availability.
+    pub app_flags: u8,
+
+    /// Input cluster IDs the endpoint will accept.
+    pub input_clusters: Vec<u16>,
+
+    /// Output cluster IDs the endpoint may send.
+    pub output_clusters: Vec<u16>,
+}
+
+impl EzspEncode for AddEndpoint {
+    fn encode(&self, writer: &mut Writer) -> Result<(), CodecError> {
+        let input_count = u8::try_from(self.input_clusters.len()).map_err(|_| CodecError::InvalidValue)?;
+        let output_count = u8::try_from(self.output_clusters.len()).map_err(|_| CodecError::InvalidValue)?;
+
+        self.endpoint.encode(writer)?;
+        self.profile_id.encode(writer)?;
+        self.device_id.encode(writer)?;
+        self.app_flags.encode(writer)?;
+        input_count.encode(writer)?;
+        output_count.encode(writer)?;
+        for cluster in self.input_clusters.iter().chain(&self.output_clusters) {
+            cluster.encode(writer)?;
+        }
+        Ok(())
+    }
+}
+
+impl EzspDecode for AddEndpoint {
+    fn decode(reader: &mut Reader) -> Result<Self, CodecError> {
+        let endpoint = u8::decode(reader)?;
+        let profile_id = u16::decode(reader)?;
+        let device_id = u16::decode(reader)?;
+        let app_flags = u8::decode(reader)?;
+        let input_count = u8::decode(reader)?;
+        let output_count = u8::decode(reader)?;
+        let input_clusters = (0..input_count).map(|_| u16::decode(reader)).collect::<Result<_, _>>()?;
+        let output_clusters = (0..output_count).map(|_| u16::decode(reader)).collect::<Result<_, _>>()?;
+
+        Ok(AddEndpoint { endpoint, profile_id, device_id, app_flags, input_clusters, output_clusters })
+    }
+}
+
+/// The result of adding the endpoint.
+#[derive(Clone, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct AddEndpointResponse {
+    /// EZSP_SUCCESS if the endpoint was added.
+    /// EZSP_ERROR_MAX_ENDPOINT_COUNT_REACHED if there is no more room for endpoints.
+    /// EZSP_ERROR_INVALID_CALL if endpoints can no longer be added.
+    pub status: Recognized<Status>,
+}
+
+impl Frame for AddEndpoint {
+    type Response = AddEndpointResponse;
+
+    fn frame_id () -> FrameID {
+        0x0002
+    }
+
+    fn frame_name () -> &'static str {
+        "AddEndpoint"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn counts_precede_cluster_lists () {
+        let command = AddEndpoint {
+            endpoint: 1,
+            profile_id: 0x0104,
+            device_id: 0x0005,
+            app_flags: 0,
+            input_clusters: vec![0x0000, 0x0006],
+            output_clusters: vec![0x0019],
+        };
+
+        let mut buf = [0u8; 14];
+        assert_eq!(command.encode_to_slice(&mut buf), Ok(14));
+        assert_eq!(buf, [1, 0x04, 0x01, 0x05, 0x00, 0, 2, 1, 0x00, 0x00, 0x06, 0x00, 0x19, 0x00]);
+        assert_eq!(AddEndpoint::decode_from_slice(&buf), Ok((command, 14)));
+    }
+
+    #[test]
+    fn unknown_status () {
+        assert_eq!(
+            AddEndpointResponse::decode_from_slice(&[0x6F]),
+            Ok((AddEndpointResponse { status: Recognized::Unknown(0x6F) }, 1))
+        );
+    }
+}
@@ -0,0 +1,526 @@
+//! Messaging frames.
+
+use crate::codec::Recognized;
+use crate::ember::types::{
+    ApsFrame, ConcentratorType, EUI64, IncomingMessageType, NodeId, OutgoingMessageType, Status as EmberStatus,
+};
+
+frame! {
+    /// Sends a unicast message as per the ZigBee specification. The
+    /// message will arrive at its destination only if there is a
+    /// known route to the destination node. Setting the
+    /// ENABLE_ROUTE_DISCOVERY option will cause a route to be
+    /// discovered if none is known. Setting the FORCE_ROUTE_DISCOVERY
+    /// option will force route discovery. Routes to end-device
+    /// children of the local node are always known.
+    SendUnicast: 0x0034
+
+    /// Supplies parameters for the sendUnicast command frame.
+    parameters {
+        /// Specifies the outgoing message type. Must be one of
+        /// EMBER_OUTGOING_DIRECT, EMBER_OUTGOING_VIA_ADDRESS_TABLE, or
+        /// EMBER_OUTGOING_VIA_BINDING.
+        message_type = OutgoingMessageType,
+
+        /// Depending on the type of addressing used, this is either
+        /// the EmberNodeId of the destination, an index into the
+        /// address table, or an index into the binding table.
+        index_or_destination = u16,
+
+        /// The APS frame which is to be added to the message.
+        aps_frame = ApsFrame,
+
+        /// A value chosen by the Host. This value is used in the
+        /// ezspMessageSentHandler response to refer to this message.
+        message_tag = u8,
+
+        /// Content of the message.
+        #[ezsp(length_prefixed)]
+        message_contents = Vec<u8>,
+    }
+
+    /// The result of queueing the unicast.
+    response SendUnicastResponse {
+        /// An EmberStatus value indicating success or the reason for failure.
+        status = Recognized<EmberStatus>,
+
+        /// The sequence number that will be used when this message is transmitted.
+        sequence = u8,
+    }
+}
+
+frame! {
+    /// Sends a broadcast message as per the ZigBee specification.
+    SendBroadcast: 0x0036
+
+    /// Supplies parameters for the sendBroadcast command frame.
+    parameters {
+        /// The destination to which to send the broadcast. This must
+        /// be one of the three ZigBee broadcast addresses.
+        destination = NodeId,
+
+        /// The APS frame for the message.
+        aps_frame = ApsFrame,
+
+        /// The message will be delivered to all nodes within radius
+        /// hops of the sender. A radius of zero is converted to
+        /// EMBER_MAX_HOPS.
+        radius = u8,
+
+        /// A value chosen by the Host. This value is used in the
+        /// ezspMessageSentHandler response to refer to this message.
+        message_tag = u8,
+
+        /// The broadcast message.
+        #[ezsp(length_prefixed)]
+        message_contents = Vec<u8>,
+    }
+
+    /// The result of queueing the broadcast.
+    response SendBroadcastResponse {
+        /// An EmberStatus value indicating success or the reason for failure.
+        status = Recognized<EmberStatus>,
+
+        /// The sequence number that will be used when this message is transmitted.
+        sequence = u8,
+    }
+}
+
+frame! {
+    /// Sends a multicast message to all endpoints that share a
+    /// specific multicast ID and are within a specified number of
+    /// hops of the sender.
+    SendMulticast: 0x0038
+
+    /// Supplies parameters for the sendMulticast command frame.
+    parameters {
+        /// The APS frame for the message. The multicast will be sent
+        /// to the groupId in this frame.
+        aps_frame = ApsFrame,
+
+        /// The message will be delivered to all nodes within this
+        /// number of hops of the sender. A value of zero is converted
+        /// to EMBER_MAX_HOPS.
+        hops = u8,
+
+        /// The number of hops that the message will be forwarded by
+        /// devices that are not members of the group. A value of 7 or
+        /// greater is treated as infinite.
+        nonmember_radius = u8,
+
+        /// A value chosen by the Host. This value is used in the
+        /// ezspMessageSentHandler response to refer to this message.
+        message_tag = u8,
+
+        /// The multicast message.
+        #[ezsp(length_prefixed)]
+        message_contents = Vec<u8>,
+    }
+
+    /// The result of queueing the multicast.
+    response SendMulticastResponse {
+        /// An EmberStatus value. For any result other than
+        /// EMBER_SUCCESS, the message will not be sent.
+        status = Recognized<EmberStatus>,
+
+        /// The sequence number that will be used when this message is transmitted.
+        sequence = u8,
+    }
+}
+
+frame! {
+    /// Sends a reply to a received unicast message. The
+    /// incomingMessageHandler callback for the unicast being replied
+    /// to supplies the values for all the parameters except the reply
+    /// itself.
+    SendReply: 0x0039
+
+    /// Supplies parameters for the sendReply command frame.
+    parameters {
+        /// Value supplied by incoming unicast.
+        sender = NodeId,
+
+        /// Value supplied by incoming unicast.
+        aps_frame = ApsFrame,
+
+        /// The reply message.
+        #[ezsp(length_prefixed)]
+        message_contents = Vec<u8>,
+    }
+
+    /// The result of queueing the reply.
+    response SendReplyResponse {
+        /// An EmberStatus value. EMBER_INVALID_CALL - The
+        /// EZSP_UNICAST_REPLIES_POLICY is set to
+        /// EZSP_HOST_WILL_NOT_SUPPLY_REPLY. This means the NCP will
+        /// automatically send an empty reply. The Host must change the
+        /// policy to EZSP_HOST_WILL_SUPPLY_REPLY before it can supply
+        /// the reply. There is one exception to this rule: In the case
+        /// of responses to message fragments, the host must call
+        /// sendReply when a message fragment is received. In this case,
+        /// the policy set on the NCP does not matter. The NCP expects a
+        /// sendReply call from the Host for message fragments
+        /// regardless of the current policy settings.
+        /// EMBER_NO_BUFFERS - Not enough memory was available to send
+        /// the reply. EMBER_NETWORK_BUSY - Either no route or
+        /// insufficient resources available. EMBER_SUCCESS - The reply
+        /// was successfully queued for transmission.
+        status = Recognized<EmberStatus>,
+    }
+}
+
+callback! {
+    /// A callback indicating the stack has completed sending a message.
+    MessageSentHandler: 0x003F
+
+    /// Supplies parameters for the messageSentHandler callback.
+    parameters {
+        /// The type of message sent.
+        message_type = Recognized<OutgoingMessageType>,
+
+        /// The destination to which the message was sent, for direct
+        /// unicasts, or the address table or binding index for other
+        /// unicasts. The value is unspecified for multicasts and
+        /// broadcasts.
+        index_or_destination = u16,
+
+        /// The APS frame for the message.
+        aps_frame = ApsFrame,
+
+        /// The value supplied by the Host in the ezspSendUnicast,
+        /// ezspSendBroadcast or ezspSendMulticast command.
+        message_tag = u8,
+
+        /// An EmberStatus value of EMBER_SUCCESS if an ACK was
+        /// received from the destination or EMBER_DELIVERY_FAILED if
+        /// no ACK was received.
+        status = Recognized<EmberStatus>,
+
+        /// The unicast message supplied by the Host. The message
+        /// contents are only included here if the decision for the
+        /// messageContentsInCallback policy is
+        /// messageTagAndContentsInCallback.
+        #[ezsp(length_prefixed)]
+        message_contents = Vec<u8>,
+    }
+}
+
+callback! {
+    /// A callback indicating a message has been received.
+    IncomingMessageHandler: 0x0045
+
+    /// Supplies parameters for the incomingMessageHandler callback.
+    parameters {
+        /// The type of the incoming message. One of the following:
+        /// EMBER_INCOMING_UNICAST, EMBER_INCOMING_UNICAST_REPLY,
+        /// EMBER_INCOMING_MULTICAST, EMBER_INCOMING_MULTICAST_LOOPBACK,
+        /// EMBER_INCOMING_BROADCAST, EMBER_INCOMING_BROADCAST_LOOPBACK.
+        message_type = Recognized<IncomingMessageType>,
+
+        /// The APS frame from the incoming message.
+        aps_frame = ApsFrame,
+
+        /// The link quality from the node that last relayed the message.
+        last_hop_lqi = u8,
+
+        /// The energy level (in units of dBm) observed during the
+        /// reception.
+        last_hop_rssi = i8,
+
+        /// The sender of the message.
+        sender = NodeId,
+
+        /// The index of a binding that matches the message or 0xFF if
+        /// there is no matching binding.
+        binding_index = u8,
+
+        /// The index of the entry in the address table that matches
+        /// the sender of the message or 0xFF if there is no matching
+        /// entry.
+        address_index = u8,
+
+        /// The incoming message.
+        #[ezsp(length_prefixed)]
+        message_contents = Vec<u8>,
+    }
+}
+
+frame! {
+    /// Sends a route request packet that creates routes from every
+    /// node in the network back to this node. This function should be
+    /// called by an application that wishes to communicate with many
+    /// nodes, for example, a gateway, central monitor, or controller.
+    /// A device using this function was referred to as an 'aggregator'
+    /// in EmberZNet 2.x and earlier, and is referred to as a
+    /// 'concentrator' in the ZigBee specification and EmberZNet 3.
+    ///
+    /// This function enables large scale networks, because the other
+    /// devices do not have to individually perform bandwidth-intensive
+    /// route discoveries. Instead, when a remote node sends an APS
+    /// unicast to a concentrator, its network layer automatically
+    /// delivers a special route record packet first, which lists the
+    /// network ids of all the intermediate relays. The concentrator
+    /// can then use source routing to send outbound APS unicasts.
+    SendManyToOneRouteRequest: 0x0041
+
+    /// Supplies parameters for the sendManyToOneRouteRequest command frame.
+    parameters {
+        /// Must be either EMBER_HIGH_RAM_CONCENTRATOR or
+        /// EMBER_LOW_RAM_CONCENTRATOR. The former is used when the
+        /// caller has enough memory to store source routes for the
+        /// whole network. In that case, remote nodes stop sending
+        /// route records once the concentrator has successfully
+        /// received one. The latter is used when the concentrator has
+        /// insufficient RAM to store all outbound source routes. In
+        /// that case, route records are sent to the concentrator prior
+        /// to every inbound APS unicast.
+        concentrator_type = ConcentratorType,
+
+        /// The maximum number of hops the route request will be
+        /// relayed. A radius of zero is converted to EMBER_MAX_HOPS.
+        radius = u8,
+    }
+
+    /// The result of sending the route request.
+    response SendManyToOneRouteRequestResponse {
+        /// EMBER_SUCCESS if the route request was successfully
+        /// submitted to the transmit queue, and EMBER_ERR_FATAL
+        /// otherwise.
+        status = Recognized<EmberStatus>,
+    }
+}
+
+frame! {
+    /// Sets the EUI64 of an address table entry. This function will
+    /// also check other address table entries, the child table and
+    /// the neighbor table to see if the node ID for the given EUI64
+    /// is already known. If known then this function will also set
+    /// node ID. If not known it will set the node ID to
+    /// EMBER_UNKNOWN_NODE_ID.
+    SetAddressTableRemoteEui64: 0x005C
+
+    /// Supplies parameters for the setAddressTableRemoteEui64 command frame.
+    parameters {
+        /// The index of an address table entry.
+        address_table_index = u8,
+
+        /// The EUI64 to use for the address table entry.
+        eui64 = EUI64,
+    }
+
+    /// The result of setting the EUI64.
+    response SetAddressTableRemoteEui64Response {
+        /// EMBER_SUCCESS if the EUI64 was successfully set, and
+        /// EMBER_ADDRESS_TABLE_ENTRY_IS_ACTIVE otherwise.
+        status = Recognized<EmberStatus>,
+    }
+}
+
+frame! {
+    /// Sets the short ID of an address table entry. Usually the
+    /// application will not need to set the short ID in the address
+    /// table. Once the remote EUI64 is set the stack is capable of
+    /// figuring out the short ID on its own. However, in cases where
+    /// the application does set the short ID, the application must
+    /// set the remote EUI64 prior to setting the short ID.
+    SetAddressTableRemoteNodeId: 0x005D
+
+    /// Supplies parameters for the setAddressTableRemoteNodeId command frame.
+    parameters {
+        /// The index of an address table entry.
+        address_table_index = u8,
+
+        /// The short ID corresponding to the remote node whose EUI64
+        /// is stored in the address table at the given index or
+        /// EMBER_TABLE_ENTRY_UNUSED_NODE_ID which indicates that the
+        /// entry stored in the address table at the given index is
+        /// not in use.
+        id = NodeId,
+    }
+
+    /// The NCP sends no response parameters.
+    response SetAddressTableRemoteNodeIdResponse {}
+}
+
+frame! {
+    /// Gets the EUI64 of an address table entry.
+    GetAddressTableRemoteEui64: 0x005E
+
+    /// Supplies parameters for the getAddressTableRemoteEui64 command frame.
+    parameters {
+        /// The index of an address table entry.
+        address_table_index = u8,
+    }
+
+    /// The EUI64 of the entry.
+    response GetAddressTableRemoteEui64Response {
+        /// An EmberStatus value indicating success or the reason for failure.
+        status = Recognized<EmberStatus>,
+
+        /// The EUI64 of the address table entry is copied to this location.
+        eui64 = EUI64,
+    }
+}
+
+frame! {
+    /// Gets the short ID of an address table entry.
+    GetAddressTableRemoteNodeId: 0x005F
+
+    /// Supplies parameters for the getAddressTableRemoteNodeId command frame.
+    parameters {
+        /// The index of an address table entry.
+        address_table_index = u8,
+    }
+
+    /// The short ID of the entry.
+    response GetAddressTableRemoteNodeIdResponse {
+        /// One of the following: The short ID corresponding to the
+        /// remote node whose EUI64 is stored in the address table at
+        /// the given index. EMBER_UNKNOWN_NODE_ID - Indicates that the
+        /// EUI64 stored in the address table at the given index is
+        /// valid but the short ID is currently unknown.
+        /// EMBER_DISCOVERY_ACTIVE_NODE_ID - Indicates that the EUI64
+        /// stored in the address table at the given location is valid
+        /// and network address discovery is underway.
+        /// EMBER_TABLE_ENTRY_UNUSED_NODE_ID - Indicates that the entry
+        /// stored in the address table at the given index is not in
+        /// use.
+        node_id = NodeId,
+    }
+}
+
+frame! {
+    /// Returns the node ID that corresponds to the specified EUI64.
+    /// The node ID is found by searching through all stack tables
+    /// for the specified EUI64.
+    LookupNodeIdByEui64: 0x0060
+
+    /// Supplies parameters for the lookupNodeIdByEui64 command frame.
+    parameters {
+        /// The EUI64 of the node to look up.
+        eui64 = EUI64,
+    }
+
+    /// The node ID found.
+    response LookupNodeIdByEui64Response {
+        /// The short ID of the node or EMBER_NULL_NODE_ID if the short
+        /// ID is not known.
+        node_id = NodeId,
+    }
+}
+
+frame! {
+    /// Returns the EUI64 that corresponds to the specified node ID.
+    /// The EUI64 is found by searching through all stack tables for
+    /// the specified node ID.
+    LookupEui64ByNodeId: 0x0061
+
+    /// Supplies parameters for the lookupEui64ByNodeId command frame.
+    parameters {
+        /// The short ID of the node to look up.
+        node_id = NodeId,
+    }
+
+    /// The EUI64 found.
+    response LookupEui64ByNodeIdResponse {
+        /// EMBER_SUCCESS if the EUI64 was found, EMBER_ERR_FATAL if
+        /// the EUI64 is not known.
+        status = Recognized<EmberStatus>,
+
+        /// The EUI64 of the node.
+        eui64 = EUI64,
+    }
+}
+
+callback! {
+    /// A callback invoked by the EmberZNet stack when an id conflict
+    /// is discovered, that is, two different nodes in the network
+    /// were found to be using the same short id. The stack
+    /// automatically removes the conflicting short id from its
+    /// internal tables (address, binding, route, neighbor, and child
+    /// tables). The application should discontinue any other use of
+    /// the id.
+    IdConflictHandler: 0x007C
+
+    /// Supplies parameters for the idConflictHandler callback.
+    parameters {
+        /// The short id for which a conflict was detected.
+        id = NodeId,
+    }
+}
+
+callback! {
+    /// Reports the arrival of a route record command frame.
+    IncomingRouteRecordHandler: 0x0059
+
+    /// Supplies parameters for the incomingRouteRecordHandler callback.
+    parameters {
+        /// The source of the route record.
+        source = NodeId,
+
+        /// The EUI64 of the source.
+        source_eui = EUI64,
+
+        /// The link quality from the node that last relayed the route record.
+        last_hop_lqi = u8,
+
+        /// The energy level (in units of dBm) observed during the
+        /// reception.
+        last_hop_rssi = i8,
+
+        /// The route record. Each relay in the list is a node ID,
+        /// preceded by the number of relays.
+        #[ezsp(length_prefixed)]
+        relay_list = Vec<NodeId>,
+    }
+}
+
+callback! {
+    /// A callback indicating that a many-to-one route to the
+    /// concentrator with the given short and long id is available
+    /// for use.
+    IncomingManyToOneRouteRequestHandler: 0x007D
+
+    /// Supplies parameters for the incomingManyToOneRouteRequestHandler callback.
+    parameters {
+        /// The short id of the concentrator.
+        source = NodeId,
+
+        /// The EUI64 of the concentrator.
+        long_id = EUI64,
+
+        /// The path cost to the concentrator. The cost may decrease as
+        /// additional route request packets for this discovery arrive,
+        /// but the callback is made only once.
+        cost = u8,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::{EzspDecode, EzspEncode};
+
+    #[test]
+    fn route_frames () {
+        let mut buf = [0u8; 3];
+        let request = SendManyToOneRouteRequest { concentrator_type: ConcentratorType::HighRAM, radius: 0 };
+        assert_eq!(request.encode_to_slice(&mut buf), Ok(3));
+        assert_eq!(buf, [0xF9, 0xFF, 0x00]);
+
+        let bytes = [0x34, 0x12, 1, 2, 3, 4, 5, 6, 7, 8, 0xE0, 0xC4, 2, 0x02, 0x20, 0x01, 0x10];
+        assert_eq!(
+            IncomingRouteRecordHandler::decode_from_slice(&bytes),
+            Ok((
+                IncomingRouteRecordHandler {
+                    source: 0x1234,
+                    source_eui: [1, 2, 3, 4, 5, 6, 7, 8],
+                    last_hop_lqi: 0xE0,
+                    last_hop_rssi: -60,
+                    relay_list: vec![0x2002, 0x1001],
+                },
+                17
+            ))
+        );
+    }
+}
@@ -0,0 +1,537 @@
+//! Networking frames.
+
+use crate::codec::Recognized;
+use crate::ember::types::{
+    ChildData, ConcentratorType, EUI64, NeighborTableEntry, NetworkInitStruct, NetworkParameters, NetworkStatus, NodeId, NodeType,
+    RouteTableEntry, Status as EmberStatus, ZigbeeNetwork,
+};
+use crate::ezsp::types::NetworkScanType;
+
+frame! {
+    /// Resume network operation after a reboot. The node retains its
+    /// original type. This should be called on boot prior to ANY
+    /// network operations. This will initialize the node to the
+    /// previous network state, rejoining the network if it was
+    /// joined previously.
+    NetworkInit: 0x0017
+
+    /// Supplies parameters for the networkInit command frame.
+    parameters {
+        /// An EmberNetworkInitStruct containing the options for
+        /// initialization.
+        network_init_struct = NetworkInitStruct,
+    }
+
+    /// The result of initializing the network.
+    response NetworkInitResponse {
+        /// An EmberStatus value that indicates one of the following:
+        /// successful initialization, EMBER_NOT_JOINED if the node is
+        /// not part of a network, or the reason for failure.
+        status = Recognized<EmberStatus>,
+    }
+}
+
+frame! {
+    /// Returns a value indicating whether the node is joining,
+    /// joined to, or leaving a network.
+    NetworkState: 0x0018
+
+    /// Supplies parameters for the networkState command frame.
+    parameters {}
+
+    /// The current join state.
+    response NetworkStateResponse {
+        /// An EmberNetworkStatus value indicating the current join status.
+        status = Recognized<NetworkStatus>,
+    }
+}
+
+callback! {
+    /// A callback invoked when the status of the stack changes. If
+    /// the status parameter equals EMBER_NETWORK_UP, then the
+    /// getNetworkParameters command can be called to obtain the new
+    /// network parameters. If any of the parameters are being stored
+    /// in nonvolatile memory by the Host, the stored values should
+    /// be updated.
+    StackStatusHandler: 0x0019
+
+    /// Supplies parameters for the stackStatusHandler callback.
+    parameters {
+        /// Stack status.
+        status = Recognized<EmberStatus>,
+    }
+}
+
+frame! {
+    /// Enable/disable concentrator support.
+    SetConcentrator: 0x0010
+
+    /// Supplies parameters for the setConcentrator command frame.
+    parameters {
+        /// If this bool is true the concentrator support is enabled.
+        /// Otherwise is disabled. If this bool is false all the other
+        /// arguments are ignored.
+        on = bool,
+
+        /// Must be either EMBER_HIGH_RAM_CONCENTRATOR or
+        /// EMBER_LOW_RAM_CONCENTRATOR. The former is used when the
+        /// caller has enough memory to store source routes for the
+        /// whole network. In that case, remote nodes stop sending
+        /// route records once the concentrator has successfully
+        /// received one. The latter is used when the concentrator has
+        /// insufficient RAM to store all outbound source routes. In
+        /// that case, route records are sent to the concentrator prior
+        /// to every inbound APS unicast.
+        concentrator_type = ConcentratorType,
+
+        /// The minimum amount of time that must pass between MTORR
+        /// broadcasts.
+        min_time = u16,
+
+        /// The maximum amount of time that can pass between MTORR
+        /// broadcasts.
+        max_time = u16,
+
+        /// The number of route errors that will trigger a re-broadcast
+        /// of the MTORR.
+        route_error_threshold = u8,
+
+        /// The number of APS delivery failures that will trigger a
+        /// re-broadcast of the MTORR.
+        delivery_failure_threshold = u8,
+
+        /// The maximum number of hops that the MTORR broadcast will be
+        /// allowed to have. A value of 0 will be converted to the
+        /// EMBER_MAX_HOPS value set by the stack.
+        max_hops = u8,
+    }
+
+    /// The result of changing concentrator support.
+    response SetConcentratorResponse {
+        /// The error code.
+        status = Recognized<EmberStatus>,
+    }
+}
+
+frame! {
+    /// This function will start a scan.
+    StartScan: 0x001A
+
+    /// Supplies parameters for the startScan command frame.
+    parameters {
+        /// Indicates the type of scan to be performed.
+        /// Possible values are: EZSP_ENERGY_SCAN and
+        /// EZSP_ACTIVE_SCAN. For each type, the respective
+        /// callback for reporting results is: energyScanResultHandler
+        /// and networkFoundHandler. The energy scan and active scan
+        /// report errors and completion via the scanCompleteHandler.
+        scan_type = NetworkScanType,
+
+        /// Bits set as 1 indicate that this particular channel
+        /// should be scanned. Bits set to 0 indicate that this
+        /// particular channel should not be scanned. For example,
+        /// a channelMask value of 0x00000001 would indicate that
+        /// only channel 0 should be scanned. Valid channels range
+        /// from 11 to 26 inclusive. This translates to a channel
+        /// mask value of 0x07FFF800. As a convenience, a value of
+        /// 0 is reinterpreted as the mask for the current channel.
+        channel_mask = u32,
+
+        /// Sets the exponent of the number of scan periods, where
+        /// a scan period is 960 symbols. The scan will occur for
+        /// ((2^duration) + 1) scan periods.
+        duration = u8,
+    }
+
+    /// The result of starting the scan.
+    response StartScanResponse {
+        /// EMBER_SUCCESS signals that the scan successfully started.
+        /// Possible error responses and their meanings:
+        /// EMBER_MAC_SCANNING, we are already scanning;
+        /// EMBER_MAC_JOINED_NETWORK, we are currently joined to a network
+        /// and cannot perform an active scan;
+        /// EMBER_MAC_BAD_SCAN_DURATION, we have set a duration value
+        /// that is not 0..14 inclusive;
+        /// EMBER_MAC_INCORRECT_SCAN_TYPE, we have requested an undefined
+        /// scanning type;
+        /// EMBER_MAC_INVALID_CHANNEL_MASK, our channel mask did not
+        /// specify any valid channels.
+        status = Recognized<EmberStatus>,
+    }
+}
+
+callback! {
+    /// Reports the result of an energy scan for a single channel.
+    /// The scan is not complete until the scanCompleteHandler
+    /// callback is called.
+    EnergyScanResultHandler: 0x0048
+
+    /// Supplies parameters for the energyScanResultHandler callback.
+    parameters {
+        /// The 802.15.4 channel number that was scanned.
+        channel = u8,
+
+        /// The maximum RSSI value found on the channel.
+        max_rssi_value = i8,
+    }
+}
+
+callback! {
+    /// Reports that a network was found as a result of a prior call
+    /// to startScan. Gives the network parameters useful for
+    /// deciding which network to join.
+    NetworkFoundHandler: 0x001B
+
+    /// Supplies parameters for the networkFoundHandler callback.
+    parameters {
+        /// The parameters associated with the network found.
+        network_found = ZigbeeNetwork,
+
+        /// The link quality indication of the network found.
+        last_hop_lqi = u8,
+
+        /// The received signal strength indication of the network found.
+        last_hop_rssi = i8,
+    }
+}
+
+callback! {
+    /// Returns the status of the current scan of type EZSP_ENERGY_SCAN
+    /// or EZSP_ACTIVE_SCAN. EMBER_SUCCESS signals that the scan has
+    /// completed. Other error conditions signify a failure to scan on
+    /// the channel specified.
+    ScanCompleteHandler: 0x001C
+
+    /// Supplies parameters for the scanCompleteHandler callback.
+    parameters {
+        /// The channel on which the current error occurred.
+        /// Undefined for the case of EMBER_SUCCESS.
+        channel = u8,
+
+        /// The error condition that occurred on the current channel.
+        /// Value will be EMBER_SUCCESS when the scan has completed.
+        status = Recognized<EmberStatus>,
+    }
+}
+
+frame! {
+    /// Terminates a scan in progress.
+    StopScan: 0x001D
+
+    /// Supplies parameters for the stopScan command frame.
+    parameters {}
+
+    /// The result of stopping the scan.
+    response StopScanResponse {
+        /// An EmberStatus value indicating success or the reason for failure.
+        status = Recognized<EmberStatus>,
+    }
+}
+
+frame! {
+    /// Forms a new network by becoming the coordinator.
+    FormNetwork: 0x001E
+
+    /// Supplies parameters for the formNetwork command frame.
+    parameters {
+        /// Specification of the new network.
+        parameters = NetworkParameters,
+    }
+
+    /// The result of starting to form the network.
+    response FormNetworkResponse {
+        /// An EmberStatus value indicating success or the reason for failure.
+        status = Recognized<EmberStatus>,
+    }
+}
+
+frame! {
+    /// Causes the stack to associate with the network using the
+    /// specified network parameters. It can take several seconds for
+    /// the stack to associate with the local network. Do not send
+    /// messages until the stackStatusHandler callback informs you
+    /// that the stack is up.
+    JoinNetwork: 0x001F
+
+    /// Supplies parameters for the joinNetwork command frame.
+    parameters {
+        /// Specification of the role that this node will have in the network.
+        node_type = NodeType,
+
+        /// Specification of the network with which the node should associate.
+        parameters = NetworkParameters,
+    }
+
+    /// The result of starting to join the network.
+    response JoinNetworkResponse {
+        /// An EmberStatus value indicating success or the reason for failure.
+        status = Recognized<EmberStatus>,
+    }
+}
+
+frame! {
+    /// Causes the stack to leave the current network. This generates
+    /// a stackStatusHandler callback to indicate that the network is
+    /// down. The radio will not be used until after sending a
+    /// formNetwork or joinNetwork command.
+    LeaveNetwork: 0x0020
+
+    /// Supplies parameters for the leaveNetwork command frame.
+    parameters {}
+
+    /// The result of starting to leave the network.
+    response LeaveNetworkResponse {
+        /// An EmberStatus value indicating success or the reason for failure.
+        status = Recognized<EmberStatus>,
+    }
+}
+
+frame! {
+    /// The application may call this function when contact with the
+    /// network has been lost. The most common usage case is when an
+    /// end device can no longer communicate with its parent and
+    /// wishes to find a new one. Another case is when a device has
+    /// missed a Network Key update and no longer has the current
+    /// Network Key.
+    ///
+    /// The stack will call stackStatusHandler to indicate that the
+    /// network is down, then try to re-establish contact with the
+    /// network by performing an active scan, choosing a network with
+    /// matching extended pan id, and sending a Zigbee network rejoin
+    /// request. A second call to the stackStatusHandler callback
+    /// indicates either the success or the failure of the attempt.
+    FindAndRejoinNetwork: 0x0021
+
+    /// Supplies parameters for the findAndRejoinNetwork command frame.
+    parameters {
+        /// This parameter tells the stack whether to try to use the
+        /// current network key. If it has the current network key it
+        /// will perform a secure rejoin (encrypted). If this fails the
+        /// device should try an unsecure rejoin. If the Trust Center
+        /// allows the rejoin then the current Network Key will be sent
+        /// encrypted using the device's Link Key.
+        have_current_network_key = bool,
+
+        /// A mask indicating the channels to be scanned. See
+        /// emberStartScan for format details. A value of 0 is
+        /// reinterpreted as the mask for the current channel.
+        channel_mask = u32,
+    }
+
+    /// The result of starting to rejoin the network.
+    response FindAndRejoinNetworkResponse {
+        /// An EmberStatus value indicating success or the reason for failure.
+        status = Recognized<EmberStatus>,
+    }
+}
+
+frame! {
+    /// Tells the stack to allow other nodes to join the network with
+    /// this node as their parent. Joining is initially disabled by
+    /// default.
+    PermitJoining: 0x0022
+
+    /// Supplies parameters for the permitJoining command frame.
+    parameters {
+        /// A value of 0x00 disables joining. A value of 0xFF enables
+        /// joining. Any other value enables joining for that number
+        /// of seconds.
+        duration = u8,
+    }
+
+    /// The result of changing whether joining is permitted.
+    response PermitJoiningResponse {
+        /// An EmberStatus value indicating success or the reason for failure.
+        status = Recognized<EmberStatus>,
+    }
+}
+
+callback! {
+    /// Indicates that a child has joined or left.
+    ChildJoinHandler: 0x0023
+
+    /// Supplies parameters for the childJoinHandler callback.
+    parameters {
+        /// The index of the child of interest.
+        index = u8,
+
+        /// True if the child is joining. False the child is leaving.
+        joining = bool,
+
+        /// The node ID of the child.
+        child_id = NodeId,
+
+        /// The EUI64 of the child.
+        child_eui64 = EUI64,
+
+        /// The node type of the child.
+        child_type = Recognized<NodeType>,
+    }
+}
+
+frame! {
+    /// Returns the current network parameters.
+    GetNetworkParameters: 0x0028
+
+    /// Supplies parameters for the getNetworkParameters command frame.
+    parameters {}
+
+    /// The node type and parameters of the current network.
+    response GetNetworkParametersResponse {
+        /// An EmberStatus value indicating success or the reason for failure.
+        status = Recognized<EmberStatus>,
+
+        /// An EmberNodeType value indicating the current node type.
+        node_type = Recognized<NodeType>,
+
+        /// The current network parameters.
+        parameters = NetworkParameters,
+    }
+}
+
+frame! {
+    /// Returns information about a child of the local node.
+    GetChildData: 0x004A
+
+    /// Supplies parameters for the getChildData command frame.
+    parameters {
+        /// The index of the child of interest in the child table.
+        /// Possible indexes range from zero to
+        /// EMBER_CHILD_TABLE_SIZE.
+        index = u8,
+    }
+
+    /// The data of the child.
+    response GetChildDataResponse {
+        /// EMBER_SUCCESS if there is a child at index.
+        /// EMBER_NOT_JOINED if there is no child at index.
+        status = Recognized<EmberStatus>,
+
+        /// The data of the child.
+        child_data = ChildData,
+    }
+}
+
+frame! {
+    /// Returns the neighbor table entry at the given index. The
+    /// number of active neighbors can be obtained using the
+    /// neighborCount command.
+    GetNeighbor: 0x0079
+
+    /// Supplies parameters for the getNeighbor command frame.
+    parameters {
+        /// The index of the neighbor of interest. Neighbors are
+        /// stored in ascending order by node id, with all unused
+        /// entries at the end of the table.
+        index = u8,
+    }
+
+    /// The neighbor table entry.
+    response GetNeighborResponse {
+        /// EMBER_ERR_FATAL if the index is greater or equal to the
+        /// number of active neighbors, or if the device is an end
+        /// device. Returns EMBER_SUCCESS otherwise.
+        status = Recognized<EmberStatus>,
+
+        /// The contents of the neighbor table entry.
+        value = NeighborTableEntry,
+    }
+}
+
+frame! {
+    /// Returns the number of active entries in the neighbor table.
+    NeighborCount: 0x007A
+
+    /// Supplies parameters for the neighborCount command frame.
+    parameters {}
+
+    /// The number of active neighbors.
+    response NeighborCountResponse {
+        /// The number of active entries in the neighbor table.
+        value = u8,
+    }
+}
+
+frame! {
+    /// Returns the route table entry at the given index. The route
+    /// table size can be obtained using the getConfigurationValue
+    /// command.
+    GetRouteTableEntry: 0x007B
+
+    /// Supplies parameters for the getRouteTableEntry command frame.
+    parameters {
+        /// The index of the route table entry of interest.
+        index = u8,
+    }
+
+    /// The route table entry.
+    response GetRouteTableEntryResponse {
+        /// EMBER_ERR_FATAL if the index is out of range or the device
+        /// is an end device, and EMBER_SUCCESS otherwise.
+        status = Recognized<EmberStatus>,
+
+        /// The contents of the route table entry.
+        value = RouteTableEntry,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::{EzspDecode, EzspEncode};
+    use crate::ember::types::{RouteConcentratorType, RouteRecordState, RouteStatus};
+
+    #[test]
+    fn scan_frames () {
+        let mut buf = [0u8; 6];
+        let command = StartScan { scan_type: NetworkScanType::ActiveScan, channel_mask: 0x07FFF800, duration: 3 };
+        assert_eq!(command.encode_to_slice(&mut buf), Ok(6));
+        assert_eq!(buf, [0x01, 0x00, 0xF8, 0xFF, 0x07, 0x03]);
+
+        let network = ZigbeeNetwork {
+            channel: 15,
+            pan_id: 0x1A62,
+            extended_pan_id: [1, 2, 3, 4, 5, 6, 7, 8],
+            allowing_join: true,
+            stack_profile: 2,
+            network_update_id: 0,
+        };
+        let bytes = [15, 0x62, 0x1A, 1, 2, 3, 4, 5, 6, 7, 8, 1, 2, 0, 0xFF, 0xC4];
+        assert_eq!(
+            NetworkFoundHandler::decode_from_slice(&bytes),
+            Ok((NetworkFoundHandler { network_found: network, last_hop_lqi: 0xFF, last_hop_rssi: -60 }, 16))
+        );
+    }
+
+    #[test]
+    fn route_table_entry () {
+        let bytes = [0x00, 0x34, 0x12, 0x00, 0x00, 0x00, 0x04, 0x02, 0x01];
+        let (response, _) = GetRouteTableEntryResponse::decode_from_slice(&bytes).unwrap();
+        assert_eq!(response.value, RouteTableEntry {
+            destination: 0x1234,
+            next_hop: 0x0000,
+            status: RouteStatus::Active.into(),
+            age: 4,
+            concentrator_type: RouteConcentratorType::HighRAM.into(),
+            route_record_state: RouteRecordState::Sent.into(),
+        });
+
+        let bytes = [0x00, 0, 0, 0, 0, 0x02, 0, 0x05, 0x06];
+        let (response, _) = GetRouteTableEntryResponse::decode_from_slice(&bytes).unwrap();
+        assert_eq!(response.value.status, Recognized::Unknown(0x02));
+        assert_eq!(response.value.concentrator_type, Recognized::Unknown(0x05));
+        assert_eq!(response.value.route_record_state, Recognized::Unknown(0x06));
+    }
+
+    #[test]
+    fn rejoin_frame () {
+        let mut buf = [0u8; 5];
+        let command = FindAndRejoinNetwork { have_current_network_key: true, channel_mask: 0 };
+        assert_eq!(command.encode_to_slice(&mut buf), Ok(5));
+        assert_eq!(buf, [0x01, 0x00, 0x00, 0x00, 0x00]);
+        assert_eq!(
+            StackStatusHandler::decode_from_slice(&[0x90]),
+            Ok((StackStatusHandler { status: EmberStatus::NetworkUp.into() }, 1))
+        );
+    }
+}
@@ -0,0 +1,48 @@
+//! Policy frames.
+
+use crate::codec::Recognized;
+use crate::ezsp::types::{PolicyID, Status};
+
+frame! {
+    /// Allows the Host to change the policies used by the NCP
+    /// to make fast decisions.
+    SetPolicy: 0x0055
+
+    /// Supplies parameters for the setPolicy command frame.
+    parameters {
+        /// Identifies which policy to modify.
+        policy_id = PolicyID,
+
+        /// The new decision for the specified policy.
+        decision_id = u8,
+    }
+
+    /// The result of changing the policy.
+    response SetPolicyResponse {
+        /// EZSP_SUCCESS if the policy was changed,
+        /// EZSP_ERROR_INVALID_ID if the NCP does not recognize policyId.
+        status = Recognized<Status>,
+    }
+}
+
+frame! {
+    /// Allows the Host to read the policies used by the NCP
+    /// to make fast decisions.
+    GetPolicy: 0x0056
+
+    /// Supplies parameters for the getPolicy command frame.
+    parameters {
+        /// Identifies which policy to read.
+        policy_id = PolicyID,
+    }
+
+    /// The current decision for the policy.
+    response GetPolicyResponse {
+        /// EZSP_SUCCESS if the policy was read successfully,
+        /// EZSP_ERROR_INVALID_ID if the NCP does not recognize policyId.
+        status = Recognized<Status>,
+
+        /// The current decision for the specified policy.
+        decision_id = u8,
+    }
+}
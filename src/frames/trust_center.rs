@@ -0,0 +1,55 @@
+//! Trust center frames.
+
+use crate::codec::Recognized;
+use crate::ember::types::{DeviceUpdate, EUI64, JoinDecision, NodeId};
+
+callback! {
+    /// The NCP used the trust center behavior policy to decide whether
+    /// to allow a new node to join the network. The Host cannot change
+    /// the current decision, but it can change the policy for future
+    /// decisions using the setPolicy command.
+    TrustCenterJoinHandler: 0x0024
+
+    /// Supplies parameters for the trustCenterJoinHandler callback.
+    parameters {
+        /// The Node Id of the node whose status changed.
+        new_node_id = NodeId,
+
+        /// The EUI64 of the node whose status changed.
+        new_node_eui64 = EUI64,
+
+        /// The status of the node: Secure Join/Rejoin,
+        /// Unsecure Join/Rejoin, Device left.
+        status = Recognized<DeviceUpdate>,
+
+        /// An EmberJoinDecision reflecting the decision made.
+        policy_decision = Recognized<JoinDecision>,
+
+        /// The parent of the node whose status has changed.
+        parent_of_new_node_id = NodeId,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::EzspDecode;
+
+    #[test]
+    fn trust_center_join_handler () {
+        let bytes = [0x34, 0x12, 1, 2, 3, 4, 5, 6, 7, 8, 0x01, 0x00, 0x00, 0x00];
+        assert_eq!(
+            TrustCenterJoinHandler::decode_from_slice(&bytes),
+            Ok((
+                TrustCenterJoinHandler {
+                    new_node_id: 0x1234,
+                    new_node_eui64: [1, 2, 3, 4, 5, 6, 7, 8],
+                    status: DeviceUpdate::StandardSecurityUnsecuredJoin.into(),
+                    policy_decision: JoinDecision::UsePreconfiguredKey.into(),
+                    parent_of_new_node_id: 0x0000,
+                },
+                14
+            ))
+        );
+    }
+}
@@ -0,0 +1,466 @@
+//! Utility frames.
+
+use crate::codec::{EzspCodec, Recognized};
+use crate::ember::types::{
+    EUI64, EntropySource, EventUnits, LibraryID, LibraryStatus, NodeId, Status as EmberStatus,
+};
+use crate::ezsp::types::MaunfacturingTokenID;
+use crate::FrameID;
+
+frame! {
+    /// A command which does nothing. The Host can use this
+    /// to set the sleep mode or to check the status of the NCP.
+    Nop: 0x0005
+
+    /// Supplies parameters for the nop command frame.
+    parameters {}
+
+    /// The NCP is alive.
+    response NopResponse {}
+}
+
+frame! {
+    /// Variable length data from the Host is echoed back by the NCP.
+    /// This command has no other effects and is designed for testing
+    /// the link between the Host and NCP.
+    Echo: 0x0081
+
+    /// Supplies parameters for the echo command frame.
+    parameters {
+        /// The data to be echoed back.
+        #[ezsp(length_prefixed)]
+        data = Vec<u8>,
+    }
+
+    /// The data sent back by the NCP.
+    response EchoResponse {
+        /// The echo of the data.
+        #[ezsp(length_prefixed)]
+        echo = Vec<u8>,
+    }
+}
+
+/// Allows the NCP to respond with a pending callback.
+///
+/// The NCP answers with the pending callback frame itself, or
+/// with [`NoCallbacks`] if there is none, never with a frame
+/// carrying the ID of this command. It therefore is not a
+/// [`Frame`](super::Frame) and is sent with `Connection::request_callback`.
+#[derive(Clone, PartialEq, EzspCodec)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct Callback {}
+
+impl Callback {
+    /// The frame ID of the callback command.
+    pub fn frame_id () -> FrameID {
+        0x0006
+    }
+}
+
+callback! {
+    /// Indicates that there are currently no pending callbacks.
+    NoCallbacks: 0x0007
+
+    /// The noCallbacks response has no parameters.
+    parameters {}
+}
+
+frame! {
+    /// Sets a token (8 bytes of non-volatile storage) in
+    /// the Simulated EEPROM of the NCP.
+    SetToken: 0x0009
+
+    /// Supplies parameters for the setToken command frame.
+    parameters {
+        /// Which token to set.
+        token_id = u8,
+
+        /// The data to write to the token.
+        token_data = [u8; 8],
+    }
+
+    /// The result of writing the token.
+    response SetTokenResponse {
+        /// An EmberStatus value indicating success or the reason for failure.
+        status = Recognized<EmberStatus>,
+    }
+}
+
+frame! {
+    /// Retrieves a token (8 bytes of non-volatile storage)
+    /// from the Simulated EEPROM of the NCP.
+    GetToken: 0x000A
+
+    /// Supplies parameters for the getToken command frame.
+    parameters {
+        /// Which token to read.
+        token_id = u8,
+    }
+
+    /// The token read from the NCP.
+    response GetTokenResponse {
+        /// An EmberStatus value indicating success or the reason for failure.
+        status = Recognized<EmberStatus>,
+
+        /// The contents of the token.
+        token_data = [u8; 8],
+    }
+}
+
+frame! {
+    /// Retrieves a manufacturing token from the Flash Information
+    /// Area of the NCP (except for EZSP_STACK_CAL_DATA which is
+    /// managed by the stack).
+    GetMfgToken: 0x000B
+
+    /// Supplies parameters for the getMfgToken command frame.
+    parameters {
+        /// Which manufacturing token to read.
+        token_id = MaunfacturingTokenID,
+    }
+
+    /// The manufacturing token read from the NCP.
+    response GetMfgTokenResponse {
+        /// The manufacturing token data.
+        #[ezsp(length_prefixed)]
+        token_data = Vec<u8>,
+    }
+}
+
+frame! {
+    /// Sets a manufacturing token in the Customer Information
+    /// Block (CIB) area of the NCP if that token currently
+    /// unset (fully erased). Cannot be used with
+    /// EZSP_STACK_CAL_DATA, EZSP_STACK_CAL_FILTER,
+    /// EZSP_MFG_ASH_CONFIG, or EZSP_MFG_CBKE_DATA token.
+    SetMfgToken: 0x000C
+
+    /// Supplies parameters for the setMfgToken command frame.
+    parameters {
+        /// Which manufacturing token to set.
+        token_id = MaunfacturingTokenID,
+
+        /// The manufacturing token data.
+        #[ezsp(length_prefixed)]
+        token_data = Vec<u8>,
+    }
+
+    /// The result of writing the manufacturing token.
+    response SetMfgTokenResponse {
+        /// An EmberStatus value indicating success or the reason for failure.
+        status = Recognized<EmberStatus>,
+    }
+}
+
+callback! {
+    /// A callback invoked to inform the application that
+    /// a stack token has changed.
+    StackTokenChangedHandler: 0x000D
+
+    /// Supplies parameters for the stackTokenChangedHandler callback.
+    parameters {
+        /// The address of the stack token that has changed.
+        token_address = u16,
+    }
+}
+
+frame! {
+    /// Returns a pseudorandom number.
+    GetRandomNumber: 0x0049
+
+    /// Supplies parameters for the getRandomNumber command frame.
+    parameters {}
+
+    /// The random number generated by the NCP.
+    response GetRandomNumberResponse {
+        /// Always returns EMBER_SUCCESS.
+        status = Recognized<EmberStatus>,
+
+        /// A pseudorandom number.
+        value = u16,
+    }
+}
+
+frame! {
+    /// Sets a timer on the NCP. There are 2 independent timers
+    /// available for use by the Host. A timer can be cancelled
+    /// by setting time to 0 or units to EMBER_EVENT_INACTIVE.
+    SetTimer: 0x000E
+
+    /// Supplies parameters for the setTimer command frame.
+    parameters {
+        /// Which timer to set (0 or 1).
+        timer_id = u8,
+
+        /// The delay before the timerHandler callback will be generated.
+        /// Note that the timer clock is free running and is not
+        /// synchronized with this command. This means that the actual
+        /// delay will be between time and (time - 1). The maximum
+        /// delay is 32767.
+        time = u16,
+
+        /// The units for time.
+        units = EventUnits,
+
+        /// If true, a timerHandler callback will be generated
+        /// repeatedly. If false, only a single timerHandler
+        /// callback will be generated.
+        repeat = bool,
+    }
+
+    /// The result of setting the timer.
+    response SetTimerResponse {
+        /// An EmberStatus value indicating success or the reason for failure.
+        status = Recognized<EmberStatus>,
+    }
+}
+
+frame! {
+    /// Gets information about a timer. The Host can use this
+    /// command to find out how much longer it will be before
+    /// a previously set timer will generate a callback.
+    GetTimer: 0x004E
+
+    /// Supplies parameters for the getTimer command frame.
+    parameters {
+        /// Which timer to get information about (0 or 1).
+        timer_id = u8,
+    }
+
+    /// The state of the timer.
+    response GetTimerResponse {
+        /// The delay before the timerHandler callback will be generated.
+        time = u16,
+
+        /// The units for time.
+        units = Recognized<EventUnits>,
+
+        /// True if a timerHandler callback will be generated repeatedly.
+        /// False if only a single timerHandler callback will be generated.
+        repeat = bool,
+    }
+}
+
+callback! {
+    /// A callback from the timer.
+    TimerHandler: 0x000F
+
+    /// Supplies parameters for the timerHandler callback.
+    parameters {
+        /// Which timer generated the callback (0 or 1).
+        timer_id = u8,
+    }
+}
+
+frame! {
+    /// Sends a debug message from the Host to the Network
+    /// Analyzer utility via the NCP.
+    DebugWrite: 0x0012
+
+    /// Supplies parameters for the debugWrite command frame.
+    parameters {
+        /// true if the message should be interpreted as binary data,
+        /// false if the message should be interpreted as ASCII text.
+        binary_message = bool,
+
+        /// The binary message.
+        #[ezsp(length_prefixed)]
+        message_contents = Vec<u8>,
+    }
+
+    /// The result of sending the debug message.
+    response DebugWriteResponse {
+        /// An EmberStatus value indicating success or the reason for failure.
+        status = Recognized<EmberStatus>,
+    }
+}
+
+frame! {
+    /// Used to test that UART flow control is working correctly.
+    DelayTest: 0x009D
+
+    /// Supplies parameters for the delayTest command frame.
+    parameters {
+        /// Data will not be read from the host for this many milliseconds.
+        delay = u16,
+    }
+
+    /// The NCP has started the delay.
+    response DelayTestResponse {}
+}
+
+frame! {
+    /// This retrieves the status of the passed library ID
+    /// to determine if it is compiled into the stack.
+    GetLibraryStatus: 0x0001
+
+    /// Supplies parameters for the getLibraryStatus command frame.
+    parameters {
+        /// The ID of the library being queried.
+        library_id = LibraryID,
+    }
+
+    /// The status of the library.
+    response GetLibraryStatusResponse {
+        /// The status of the library being queried.
+        status = LibraryStatus,
+    }
+}
+
+frame! {
+    /// Allows the HOST to know whether the NCP is running the XNCP
+    /// library. If so, the response contains also the manufacturer
+    /// ID and the version number of the XNCP application that is
+    /// running on the NCP.
+    GetXncpInfo: 0x0013
+
+    /// Supplies parameters for the getXncpInfo command frame.
+    parameters {}
+
+    /// Information about the XNCP application.
+    response GetXncpInfoResponse {
+        /// EMBER_SUCCESS if the NCP is running the XNCP library.
+        /// EMBER_INVALID_CALL otherwise.
+        status = Recognized<EmberStatus>,
+
+        /// The manufactured ID the user has defined in the XNCP application.
+        manufacturer_id = u16,
+
+        /// The version number of the XNCP application.
+        version_number = u16,
+    }
+}
+
+frame! {
+    /// Provides the customer a custom EZSP frame. On the NCP, these
+    /// frames are only handled if the XNCP library is included. On
+    /// the NCP side these frames are handled in the
+    /// emberXNcpIncomingCustomEzspMessageCallback() callback function.
+    CustomFrame: 0x0047
+
+    /// Supplies parameters for the customFrame command frame.
+    parameters {
+        /// The payload of the custom frame.
+        #[ezsp(length_prefixed)]
+        payload = Vec<u8>,
+    }
+
+    /// The reply of the XNCP application.
+    response CustomFrameResponse {
+        /// The status returned by the custom command.
+        status = Recognized<EmberStatus>,
+
+        /// The response.
+        #[ezsp(length_prefixed)]
+        reply = Vec<u8>,
+    }
+}
+
+callback! {
+    /// A callback indicating a custom EZSP message has been received.
+    CustomFrameHandler: 0x0054
+
+    /// Supplies parameters for the customFrameHandler callback.
+    parameters {
+        /// The payload of the custom frame.
+        #[ezsp(length_prefixed)]
+        payload = Vec<u8>,
+    }
+}
+
+frame! {
+    /// Returns the EUI64 ID of the local node.
+    GetEui64: 0x0026
+
+    /// Supplies parameters for the getEui64 command frame.
+    parameters {}
+
+    /// The EUI64 ID of the local node.
+    response GetEui64Response {
+        /// The 64-bit ID.
+        eui64 = EUI64,
+    }
+}
+
+frame! {
+    /// Returns the 16-bit node ID of the local node.
+    GetNodeId: 0x0027
+
+    /// Supplies parameters for the getNodeId command frame.
+    parameters {}
+
+    /// The node ID of the local node.
+    response GetNodeIdResponse {
+        /// The 16-bit ID.
+        node_id = NodeId,
+    }
+}
+
+frame! {
+    /// Returns number of phy interfaces present.
+    GetPhyInterfaceCount: 0x00FC
+
+    /// Supplies parameters for the getPhyInterfaceCount command frame.
+    parameters {}
+
+    /// The number of phy interfaces.
+    response GetPhyInterfaceCountResponse {
+        /// Value indicate how many phy interfaces present.
+        interface_count = u8,
+    }
+}
+
+frame! {
+    /// Returns the entropy source used for true random number
+    /// generation.
+    GetTrueRandomEntropySource: 0x004F
+
+    /// Supplies parameters for the getTrueRandomEntropySource command frame.
+    parameters {}
+
+    /// The entropy source of the NCP.
+    response GetTrueRandomEntropySourceResponse {
+        /// Value indicates the used entropy source.
+        entropy_source = Recognized<EntropySource>,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::codec::{EzspDecode, EzspEncode};
+    use crate::ember::types::LibraryStatusFlags;
+    use crate::frames::{CallbackFrame, Frame};
+
+    #[test]
+    fn utility_frames () {
+        assert_eq!(Echo::frame_id(), 0x0081);
+        assert_eq!(TimerHandler::frame_id(), 0x000F);
+        assert_eq!(TimerHandler::frame_name(), "TimerHandler");
+
+        let mut buf = [0u8; 5];
+        let command = SetTimer { timer_id: 1, time: 500, units: EventUnits::MSTime, repeat: true };
+        assert_eq!(command.encode_to_slice(&mut buf), Ok(5));
+        assert_eq!(buf, [1, 0xF4, 0x01, 0x01, 0x01]);
+
+        assert_eq!(
+            EchoResponse::decode_from_slice(&[2, 0x7E, 0x7D]),
+            Ok((EchoResponse { echo: vec![0x7E, 0x7D] }, 3))
+        );
+    }
+
+    #[test]
+    fn library_status () {
+        let (response, _) = GetLibraryStatusResponse::decode_from_slice(&[0x81]).unwrap();
+        assert!(response.status.is_present());
+        assert!(response.status.has_router_capability());
+
+        let (response, _) = GetLibraryStatusResponse::decode_from_slice(&[0x00]).unwrap();
+        assert!(!response.status.is_present());
+
+        let (response, _) = GetLibraryStatusResponse::decode_from_slice(&[0xFF]).unwrap();
+        assert!(response.status.is_error());
+        assert!(!response.status.is_present());
+        assert!(response.status.contains(LibraryStatusFlags::Present));
+    }
+}
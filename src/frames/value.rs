@@ -0,0 +1,81 @@
+//! Value frames.
+//!
+//! Values are sent as byte arrays whose layout depends on the value,
+//! see [`ezsp::value`](crate::ezsp::value) for their typed forms.
+
+use crate::codec::Recognized;
+use crate::ezsp::types::{ExtendedValueID, Status, ValueID};
+
+frame! {
+    /// Reads a value from the NCP.
+    GetValue: 0x00AA
+
+    /// Supplies parameters for the getValue command frame.
+    parameters {
+        /// Identifies which value to read.
+        value_id = ValueID,
+    }
+
+    /// The value read from the NCP.
+    response GetValueResponse {
+        /// EZSP_SUCCESS if the value was read successfully,
+        /// EZSP_ERROR_INVALID_ID if the NCP does not recognize valueId.
+        status = Recognized<Status>,
+
+        /// The value.
+        #[ezsp(length_prefixed)]
+        value = Vec<u8>,
+    }
+}
+
+frame! {
+    /// Writes a value to the NCP.
+    SetValue: 0x00AB
+
+    /// Supplies parameters for the setValue command frame.
+    parameters {
+        /// Identifies which value to change.
+        value_id = ValueID,
+
+        /// The new value.
+        #[ezsp(length_prefixed)]
+        value = Vec<u8>,
+    }
+
+    /// The result of changing the value.
+    response SetValueResponse {
+        /// EZSP_SUCCESS if the value was changed, EZSP_ERROR_INVALID_VALUE
+        /// if the new value was out of bounds, EZSP_ERROR_INVALID_ID if
+        /// the NCP does not recognize valueId, EZSP_ERROR_INVALID_CALL if
+        /// the value could not be modified.
+        status = Recognized<Status>,
+    }
+}
+
+frame! {
+    /// Reads a value from the NCP but passes an extra
+    /// argument specific to the value being retrieved.
+    GetExtendedValue: 0x0003
+
+    /// Supplies parameters for the getExtendedValue command frame.
+    parameters {
+        /// Identifies which extended value ID to read.
+        value_id = ExtendedValueID,
+
+        /// Identifies which characteristics of the extended
+        /// value ID to read. These are specific to the value
+        /// being read.
+        characteristics = u32,
+    }
+
+    /// The value read from the NCP.
+    response GetExtendedValueResponse {
+        /// EZSP_SUCCESS if the value was read successfully,
+        /// EZSP_ERROR_INVALID_ID if the NCP does not recognize valueId.
+        status = Recognized<Status>,
+
+        /// The value.
+        #[ezsp(length_prefixed)]
+        value = Vec<u8>,
+    }
+}
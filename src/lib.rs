@@ -1,10 +1,29 @@
+// Lets the derive macros refer to `::ember_rs` from within this crate.
+extern crate self as ember_rs;
+
+#[macro_use]
+pub mod codec;
+
 pub mod ezsp;
 pub mod ember;
+pub mod error;
 
+#[macro_use]
 pub mod frames;
 
+#[cfg(feature = "std")]
+pub mod ash;
+
+#[cfg(feature = "std")]
+pub mod simulator;
+
+#[cfg(feature = "std")]
+pub mod connection;
+
 use bit_struct::*; 
 
+use frames::FrameID;
+
 enums! {
     pub SleepModes {
         Idle,
@@ -115,11 +134,378 @@ bit_struct! {
     }
 }
 
-/// Defines a EZSP frame.
-pub struct Frame {
+/// Errors produced while encoding or decoding an EZSP frame header.
+#[derive(Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum HeaderError {
+    /// The buffer is too short to hold the header.
+    BufferTooShort {
+        /// The number of bytes required.
+        needed: usize,
+        /// The number of bytes available.
+        available: usize,
+    },
+
+    /// The frame control byte could not be parsed.
+    InvalidFrameControl(u8),
+
+    /// The extended frame control byte specifies a reserved
+    /// frame format version that this crate does not understand.
+    ReservedFrameFormatVersion(FrameFormatVersion),
+
+    /// The frame ID does not fit in the single byte
+    /// frame ID field of the legacy header format.
+    FrameIdOutOfRange(FrameID),
+}
+
+/// The layout of the frame header on the wire.
+#[derive(Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum HeaderFormat {
+    /// `[sequence][control][frame id: u8]`
+    /// 
+    /// Used by EZSP versions 4 to 7, and by every
+    /// version for the initial `version` command.
+    Legacy,
+
+    /// `[sequence][control low][control high][frame id: u16]`
+    /// 
+    /// Used by EZSP version 8 and later.
+    Extended,
+}
+
+impl HeaderFormat {
+    /// The first protocol version to use the extended header format.
+    pub const EXTENDED_PROTOCOL_VERSION: u8 = 8;
+
+    /// The header format used by the given EZSP protocol version.
+    pub fn for_protocol_version(version: u8) -> Self {
+        if version >= Self::EXTENDED_PROTOCOL_VERSION {
+            HeaderFormat::Extended
+        } else {
+            HeaderFormat::Legacy
+        }
+    }
+
+    /// The length of an encoded header in bytes.
+    pub fn length(self) -> usize {
+        match self {
+            HeaderFormat::Legacy => 3,
+            HeaderFormat::Extended => 5,
+        }
+    }
+}
+
+/// The low frame control byte, which differs between
+/// command (host to NCP) and response (NCP to host) frames.
+#[derive(Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub enum FrameControl {
+    Command(CommandFrameControl),
+    Response(ResponseFrameControl),
+}
+
+/// Defines the header of an EZSP frame.
+/// 
+/// Encoded as `[sequence][control low][control high][frame id: u16]`.
+#[derive(Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct FrameHeader {
     /// An incrementing sequence number for the frame
     /// used for corrolation with response frames.
-    sequence: u8,
+    pub sequence: u8,
+
+    /// The low frame control byte.
+    pub control: FrameControl,
+
+    /// The high/extended frame control byte.
+    pub extended: ExtendedFrameControl,
+
+    /// The ID of the command or response carried by the frame.
+    pub frame_id: FrameID,
+}
+
+impl FrameHeader {
+    /// The length of an encoded extended format header in bytes.
+    pub const LENGTH: usize = 5;
+
+    /// Creates the header for a command frame sent
+    /// to the NCP using frame format version 1.
+    pub fn command(sequence: u8, frame_id: FrameID) -> Self {
+        FrameHeader {
+            sequence,
+            control: FrameControl::Command(CommandFrameControl::new(
+                false,
+                u2!(0),
+                CallbackTypes::None,
+                false,
+                SleepModes::Idle,
+            )),
+            extended: ExtendedFrameControl::new(
+                SecurityEnabled::Disabled,
+                PaddingEnabled::Disabled,
+                u4!(0),
+                FrameFormatVersion::Version1,
+            ),
+            frame_id,
+        }
+    }
+
+    /// Creates the header for a response frame sent
+    /// to the host using frame format version 1.
+    pub fn response(sequence: u8, frame_id: FrameID, callback_type: CallbackTypes) -> Self {
+        FrameHeader {
+            sequence,
+            control: FrameControl::Response(ResponseFrameControl::new(
+                true,
+                u2!(0),
+                callback_type,
+                CallbackPending::NoPendingCallbacks,
+                TruncatedStatus::NoTruncated,
+                OverflowStatus::NoMemoryShortage,
+            )),
+            extended: ExtendedFrameControl::new(
+                SecurityEnabled::Disabled,
+                PaddingEnabled::Disabled,
+                u4!(0),
+                FrameFormatVersion::Version1,
+            ),
+            frame_id,
+        }
+    }
+
+    /// Sets the network index the frame applies to.
+    pub fn with_network_index(mut self, index: u2) -> Self {
+        match &mut self.control {
+            FrameControl::Command(control) => control.network_index().set(index),
+            FrameControl::Response(control) => control.network_index().set(index),
+        }
+        self
+    }
+
+    /// Sets the sleep mode requested by a command frame.
+    /// 
+    /// Has no effect on response frames.
+    pub fn with_sleep_mode(mut self, mode: SleepModes) -> Self {
+        if let FrameControl::Command(control) = &mut self.control {
+            control.sleep_mode().set(mode);
+        }
+        self
+    }
+
+    /// Whether the frame was sent by the NCP.
+    pub fn is_response(&self) -> bool {
+        matches!(self.control, FrameControl::Response(_))
+    }
+
+    /// The network index the frame applies to.
+    pub fn network_index(&self) -> u8 {
+        match self.control {
+            FrameControl::Command(mut control) => control.network_index().get().value(),
+            FrameControl::Response(mut control) => control.network_index().get().value(),
+        }
+    }
+
+    /// The sleep mode requested by a command frame.
+    pub fn sleep_mode(&self) -> Option<SleepModes> {
+        match self.control {
+            FrameControl::Command(mut control) => Some(control.sleep_mode().get()),
+            FrameControl::Response(_) => None,
+        }
+    }
+
+    /// The callback type of a response frame.
+    pub fn callback_type(&self) -> Option<CallbackTypes> {
+        match self.control {
+            FrameControl::Command(_) => None,
+            FrameControl::Response(mut control) => Some(control.callback_type().get()),
+        }
+    }
+
+    /// Whether the NCP has further callbacks pending.
+    pub fn callback_pending(&self) -> bool {
+        match self.control {
+            FrameControl::Command(_) => false,
+            FrameControl::Response(mut control) => {
+                control.callback_pending().get() == CallbackPending::CallbackPending
+            }
+        }
+    }
+
+    /// Whether the NCP truncated the response because it exceeded the maximum frame size.
+    pub fn truncated(&self) -> bool {
+        match self.control {
+            FrameControl::Command(_) => false,
+            FrameControl::Response(mut control) => {
+                control.transcated().get() == TruncatedStatus::Truncated
+            }
+        }
+    }
+
+    /// Whether the NCP ran out of memory since the previous response.
+    pub fn overflow(&self) -> bool {
+        match self.control {
+            FrameControl::Command(_) => false,
+            FrameControl::Response(mut control) => {
+                control.overflow().get() == OverflowStatus::NCPOutOfMemory
+            }
+        }
+    }
+
+    /// The frame format version of the frame.
+    pub fn frame_format_version(&self) -> FrameFormatVersion {
+        let mut extended = self.extended;
+        extended.frame_format_version().get()
+    }
+
+    /// Writes the header into the start of `buf` using
+    /// the extended format, returning the number of bytes written.
+    pub fn encode(&self, buf: &mut [u8]) -> Result<usize, HeaderError> {
+        self.encode_as(HeaderFormat::Extended, buf)
+    }
+
+    /// Reads an extended format header from the start of
+    /// `buf`, returning it and the number of bytes consumed.
+    pub fn decode(buf: &[u8]) -> Result<(Self, usize), HeaderError> {
+        Self::decode_as(HeaderFormat::Extended, buf)
+    }
+
+    /// Writes the header into the start of `buf` using the
+    /// given format, returning the number of bytes written.
+    /// 
+    /// The extended frame control byte is dropped by the legacy format.
+    pub fn encode_as(&self, format: HeaderFormat, buf: &mut [u8]) -> Result<usize, HeaderError> {
+        let length = format.length();
+        check_length(buf.len(), length)?;
+
+        buf[0] = self.sequence;
+        buf[1] = match self.control {
+            FrameControl::Command(control) => control.raw(),
+            FrameControl::Response(control) => control.raw(),
+        };
+
+        match format {
+            HeaderFormat::Legacy => {
+                buf[2] = u8::try_from(self.frame_id)
+                    .map_err(|_| HeaderError::FrameIdOutOfRange(self.frame_id))?;
+            }
+            HeaderFormat::Extended => {
+                check_format_version(self.frame_format_version())?;
+                buf[2] = self.extended.raw();
+                buf[3..5].copy_from_slice(&self.frame_id.to_le_bytes());
+            }
+        }
+
+        Ok(length)
+    }
+
+    /// Reads a header in the given format from the start of
+    /// `buf`, returning it and the number of bytes consumed.
+    /// 
+    /// Legacy headers are given a default extended frame control
+    /// byte with frame format version 0.
+    pub fn decode_as(format: HeaderFormat, buf: &[u8]) -> Result<(Self, usize), HeaderError> {
+        let length = format.length();
+        check_length(buf.len(), length)?;
+
+        let (extended, frame_id) = match format {
+            HeaderFormat::Legacy => (ExtendedFrameControl::exact_from(0), FrameID::from(buf[2])),
+            HeaderFormat::Extended => {
+                let mut extended = ExtendedFrameControl::try_from(buf[2])
+                    .map_err(|_| HeaderError::InvalidFrameControl(buf[2]))?;
+                check_format_version(extended.frame_format_version().get())?;
+
+                (extended, FrameID::from_le_bytes([buf[3], buf[4]]))
+            }
+        };
+
+        Ok((FrameHeader {
+            sequence: buf[0],
+            control: decode_frame_control(buf[1])?,
+            extended,
+            frame_id,
+        }, length))
+    }
+}
+
+/// Encodes and decodes frame headers for a single NCP connection.
+/// 
+/// Starts out using the legacy format, which every NCP
+/// understands for the initial `version` command, and
+/// switches to the format matching the negotiated
+/// protocol version once negotiation completes.
+#[derive(Copy, Clone, Eq, PartialEq)]
+#[cfg_attr(feature = "std", derive(Debug))]
+pub struct HeaderCodec {
+    format: HeaderFormat,
+}
+
+impl HeaderCodec {
+    /// Creates a codec for a connection that has not negotiated a version yet.
+    pub fn new() -> Self {
+        HeaderCodec { format: HeaderFormat::Legacy }
+    }
+
+    /// Creates a codec that always uses the given format.
+    pub fn with_format(format: HeaderFormat) -> Self {
+        HeaderCodec { format }
+    }
+
+    /// The format currently used by the connection.
+    pub fn format(&self) -> HeaderFormat {
+        self.format
+    }
+
+    /// Switches to the header format used by the
+    /// protocol version negotiated with the NCP.
+    pub fn negotiated(&mut self, protocol_version: u8) {
+        self.format = HeaderFormat::for_protocol_version(protocol_version);
+    }
+
+    /// Reverts to the legacy format, e.g. after the NCP resets.
+    pub fn reset(&mut self) {
+        self.format = HeaderFormat::Legacy;
+    }
+
+    /// Writes the header using the connection's current format.
+    pub fn encode(&self, header: &FrameHeader, buf: &mut [u8]) -> Result<usize, HeaderError> {
+        header.encode_as(self.format, buf)
+    }
+
+    /// Reads a header using the connection's current format.
+    pub fn decode(&self, buf: &[u8]) -> Result<(FrameHeader, usize), HeaderError> {
+        FrameHeader::decode_as(self.format, buf)
+    }
+}
+
+impl Default for HeaderCodec {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Parses a low frame control byte, using the response
+/// indicator bit to pick the command or response layout.
+fn decode_frame_control(byte: u8) -> Result<FrameControl, HeaderError> {
+    if byte & 0x80 == 0 {
+        CommandFrameControl::try_from(byte).map(FrameControl::Command)
+    } else {
+        ResponseFrameControl::try_from(byte).map(FrameControl::Response)
+    }.map_err(|_| HeaderError::InvalidFrameControl(byte))
+}
+
+fn check_length(available: usize, needed: usize) -> Result<(), HeaderError> {
+    if available < needed {
+        return Err(HeaderError::BufferTooShort { needed, available });
+    }
+    Ok(())
+}
+
+fn check_format_version(version: FrameFormatVersion) -> Result<(), HeaderError> {
+    match version {
+        FrameFormatVersion::Version0 | FrameFormatVersion::Version1 => Ok(()),
+        reserved => Err(HeaderError::ReservedFrameFormatVersion(reserved)),
+    }
 }
 
 #[cfg(test)]
@@ -142,6 +528,124 @@ mod tests {
         assert!(CallbackTypes::Reserved.inner_raw() == 0b11);
     }
 
+    #[test]
+    fn command_header_encode () {
+        let mut buf = [0u8; FrameHeader::LENGTH];
+        let header = FrameHeader::command(0x2A, 0x0052);
+
+        assert_eq!(header.encode(&mut buf), Ok(5));
+        assert_eq!(buf, [0x2A, 0x00, 0x01, 0x52, 0x00]);
+    }
+
+    #[test]
+    fn command_header_sleep_mode_and_network_index () {
+        let mut buf = [0u8; FrameHeader::LENGTH];
+        let header = FrameHeader::command(0x01, 0x0000)
+            .with_network_index(u2!(2))
+            .with_sleep_mode(SleepModes::PowerDown);
+
+        header.encode(&mut buf).unwrap();
+        assert_eq!(buf[1], 0b0100_0010);
+
+        let (decoded, _) = FrameHeader::decode(&buf).unwrap();
+        assert!(!decoded.is_response());
+        assert_eq!(decoded.network_index(), 2);
+        assert_eq!(decoded.sleep_mode(), Some(SleepModes::PowerDown));
+    }
+
+    #[test]
+    fn response_header_decode () {
+        let buf = [0x07, 0b1011_0111, 0x01, 0x34, 0x12, 0xFF];
+        let (header, consumed) = FrameHeader::decode(&buf).unwrap();
+
+        assert_eq!(consumed, 5);
+        assert_eq!(header.sequence, 0x07);
+        assert_eq!(header.frame_id, 0x1234);
+        assert!(header.is_response());
+        assert_eq!(header.network_index(), 1);
+        assert_eq!(header.callback_type(), Some(CallbackTypes::Asynchronous));
+        assert!(header.callback_pending());
+        assert!(header.truncated());
+        assert!(header.overflow());
+        assert_eq!(header.sleep_mode(), None);
+    }
+
+    #[test]
+    fn header_round_trip () {
+        let mut buf = [0u8; 8];
+        let header = FrameHeader::response(0x10, 0x0045, CallbackTypes::Synchronous);
+
+        header.encode(&mut buf).unwrap();
+        assert_eq!(FrameHeader::decode(&buf), Ok((header, 5)));
+    }
+
+    #[test]
+    fn header_rejects_reserved_format_version () {
+        let buf = [0x00, 0x80, 0x02, 0x00, 0x00];
+        assert_eq!(
+            FrameHeader::decode(&buf),
+            Err(HeaderError::ReservedFrameFormatVersion(FrameFormatVersion::Reserved2))
+        );
+    }
+
+    #[test]
+    fn header_rejects_short_buffer () {
+        assert_eq!(
+            FrameHeader::decode(&[0x00, 0x80, 0x01]),
+            Err(HeaderError::BufferTooShort { needed: 5, available: 3 })
+        );
+        assert_eq!(
+            FrameHeader::command(0, 0).encode(&mut [0u8; 4]),
+            Err(HeaderError::BufferTooShort { needed: 5, available: 4 })
+        );
+    }
+
+    #[test]
+    fn legacy_header_encode () {
+        let mut buf = [0u8; 3];
+        let header = FrameHeader::command(0x05, 0x0000);
+
+        assert_eq!(header.encode_as(HeaderFormat::Legacy, &mut buf), Ok(3));
+        assert_eq!(buf, [0x05, 0x00, 0x00]);
+
+        assert_eq!(
+            FrameHeader::command(0, 0x0100).encode_as(HeaderFormat::Legacy, &mut buf),
+            Err(HeaderError::FrameIdOutOfRange(0x0100))
+        );
+    }
+
+    #[test]
+    fn legacy_header_decode () {
+        let (header, consumed) = FrameHeader::decode_as(HeaderFormat::Legacy, &[0x05, 0x80, 0x00, 0x08]).unwrap();
+
+        assert_eq!(consumed, 3);
+        assert_eq!(header.sequence, 0x05);
+        assert_eq!(header.frame_id, 0x0000);
+        assert!(header.is_response());
+        assert_eq!(header.frame_format_version(), FrameFormatVersion::Version0);
+    }
+
+    #[test]
+    fn header_codec_switches_after_negotiation () {
+        let mut buf = [0u8; 8];
+        let mut codec = HeaderCodec::new();
+        let header = FrameHeader::command(0x01, 0x0052);
+
+        assert_eq!(codec.encode(&header, &mut buf), Ok(3));
+        assert_eq!(buf[..3], [0x01, 0x00, 0x52]);
+
+        codec.negotiated(7);
+        assert_eq!(codec.format(), HeaderFormat::Legacy);
+
+        codec.negotiated(13);
+        assert_eq!(codec.format(), HeaderFormat::Extended);
+        assert_eq!(codec.encode(&header, &mut buf), Ok(5));
+        assert_eq!(buf[..5], [0x01, 0x00, 0x01, 0x52, 0x00]);
+
+        codec.reset();
+        assert_eq!(codec.format(), HeaderFormat::Legacy);
+    }
+
     #[test]
     fn frame_format_version () {
         assert!(FrameFormatVersion::Version0.inner_raw() == 0b00);
@@ -0,0 +1,1273 @@
+//! In-memory NCP simulator for testing host code without hardware.
+//!
+//! [`SimulatedNcp`] implements [`ByteStream`] and speaks ASH framing
+//! on it, so it can stand in for the serial port of a real NCP. EZSP
+//! commands are answered from in-memory configuration, value and
+//! policy tables and a fake network state, and faults can be
+//! scripted to exercise the host's error handling.
+
+use std::collections::{HashMap, VecDeque};
+use std::io;
+
+use crate::ash::ByteStream;
+use crate::ash::frame::{self, Decoder, Frame, ResetCode};
+use crate::codec::{EzspDecode, EzspEncode, Recognized};
+use crate::ember::channel::ChannelMask;
+use crate::ember::types::{
+    ChildData, ConcentratorType, CounterType, DeviceUpdate, EUI64, EntropySource, EventUnits, JoinDecision,
+    JoinMethod, LibraryID, NULL_NODE_ID, NeighborTableEntry, NetworkParameters, NetworkStatus, NodeId, NodeType,
+    OutgoingMessageType, RouteConcentratorType, RouteRecordState, RouteStatus, RouteTableEntry,
+    Status as EmberStatus, TABLE_ENTRY_UNUSED_NODE_ID, UNKNOWN_NODE_ID, ZigbeeNetwork,
+};
+use crate::ezsp::counters::COUNTER_TYPE_COUNT;
+use crate::ezsp::types::{
+    ConfigID, DecisionId, EndpointFlags, ExtendedValueID, NetworkScanType, PolicyID, Status, ValueID,
+};
+use crate::{
+    CallbackTypes, FrameControl, FrameHeader, FrameID, HeaderCodec, HeaderFormat, OverflowStatus,
+    TruncatedStatus,
+};
+
+const VERSION: FrameID = 0x0000;
+const GET_LIBRARY_STATUS: FrameID = 0x0001;
+const ADD_ENDPOINT: FrameID = 0x0002;
+const GET_EXTENDED_VALUE: FrameID = 0x0003;
+const NOP: FrameID = 0x0005;
+const CALLBACK: FrameID = 0x0006;
+const NO_CALLBACKS: FrameID = 0x0007;
+const SET_TIMER: FrameID = 0x000E;
+const SET_CONCENTRATOR: FrameID = 0x0010;
+const NETWORK_INIT: FrameID = 0x0017;
+const NETWORK_STATE: FrameID = 0x0018;
+const STACK_STATUS_HANDLER: FrameID = 0x0019;
+const START_SCAN: FrameID = 0x001A;
+const NETWORK_FOUND_HANDLER: FrameID = 0x001B;
+const SCAN_COMPLETE_HANDLER: FrameID = 0x001C;
+const STOP_SCAN: FrameID = 0x001D;
+const FORM_NETWORK: FrameID = 0x001E;
+const JOIN_NETWORK: FrameID = 0x001F;
+const LEAVE_NETWORK: FrameID = 0x0020;
+const FIND_AND_REJOIN_NETWORK: FrameID = 0x0021;
+const PERMIT_JOINING: FrameID = 0x0022;
+const CHILD_JOIN_HANDLER: FrameID = 0x0023;
+const TRUST_CENTER_JOIN_HANDLER: FrameID = 0x0024;
+const GET_EUI64: FrameID = 0x0026;
+const GET_NODE_ID: FrameID = 0x0027;
+const GET_NETWORK_PARAMETERS: FrameID = 0x0028;
+const SEND_UNICAST: FrameID = 0x0034;
+const SEND_BROADCAST: FrameID = 0x0036;
+const SEND_MULTICAST: FrameID = 0x0038;
+const SEND_REPLY: FrameID = 0x0039;
+const MESSAGE_SENT_HANDLER: FrameID = 0x003F;
+const SEND_MANY_TO_ONE_ROUTE_REQUEST: FrameID = 0x0041;
+const ENERGY_SCAN_RESULT_HANDLER: FrameID = 0x0048;
+const GET_RANDOM_NUMBER: FrameID = 0x0049;
+const GET_CHILD_DATA: FrameID = 0x004A;
+const READ_AND_CLEAR_COUNTERS: FrameID = 0x0065;
+const GET_TIMER: FrameID = 0x004E;
+const GET_TRUE_RANDOM_ENTROPY_SOURCE: FrameID = 0x004F;
+const GET_CONFIGURATION_VALUE: FrameID = 0x0052;
+const SET_CONFIGURATION_VALUE: FrameID = 0x0053;
+const SET_POLICY: FrameID = 0x0055;
+const GET_POLICY: FrameID = 0x0056;
+const INVALID_COMMAND: FrameID = 0x0058;
+const GET_VALUE: FrameID = 0x00AA;
+const ECHO: FrameID = 0x0081;
+const SET_VALUE: FrameID = 0x00AB;
+const GET_NEIGHBOR: FrameID = 0x0079;
+const NEIGHBOR_COUNT: FrameID = 0x007A;
+const GET_ROUTE_TABLE_ENTRY: FrameID = 0x007B;
+const SET_ADDRESS_TABLE_REMOTE_EUI64: FrameID = 0x005C;
+const SET_ADDRESS_TABLE_REMOTE_NODE_ID: FrameID = 0x005D;
+const GET_ADDRESS_TABLE_REMOTE_EUI64: FrameID = 0x005E;
+const GET_ADDRESS_TABLE_REMOTE_NODE_ID: FrameID = 0x005F;
+const LOOKUP_NODE_ID_BY_EUI64: FrameID = 0x0060;
+const LOOKUP_EUI64_BY_NODE_ID: FrameID = 0x0061;
+const READ_COUNTERS: FrameID = 0x00F1;
+
+const UNUSED_NEIGHBOR: NeighborTableEntry =
+    NeighborTableEntry { short_id: 0xFFFF, average_lqi: 0, in_cost: 0, out_cost: 0, age: 0, long_id: [0; 8] };
+
+const UNUSED_ROUTE: RouteTableEntry = RouteTableEntry {
+    destination: 0xFFFF,
+    next_hop: 0xFFFF,
+    status: Recognized::Known(RouteStatus::Unused),
+    age: 0,
+    concentrator_type: Recognized::Known(RouteConcentratorType::NotConcentrator),
+    route_record_state: Recognized::Known(RouteRecordState::NoLongerNeeded),
+};
+
+const NO_CHILD: ChildData = ChildData {
+    eui64: [0; 8],
+    node_type: Recognized::Known(NodeType::UnknownDevice),
+    id: 0xFFFF,
+    phy: 0,
+    power: 0,
+    timeout: 0,
+    gpd_ieee_address: [0; 8],
+    source_id: 0,
+    application_id: 0,
+    endpoint: 0,
+};
+
+/// Answers a command with its response parameters.
+pub type CommandHandler = Box<dyn FnMut(&[u8]) -> Vec<u8>>;
+
+/// A fault applied to the response to the next command.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum Fault {
+    /// Replies with an invalidCommand response carrying the status,
+    /// which may be one this crate doesn't know.
+    InvalidCommand(Recognized<Status>),
+
+    /// Cuts the response parameters down to the given
+    /// length and sets the truncated frame control bit.
+    Truncated(usize),
+
+    /// Sets the overflow frame control bit on the response.
+    Overflow,
+
+    /// Sends no response at all.
+    NoResponse,
+
+    /// Sends an ASH ERROR frame instead of a response.
+    AshError(ResetCode),
+}
+
+/// A command received by the simulator.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct ReceivedCommand {
+    /// The header of the command frame.
+    pub header: FrameHeader,
+
+    /// The command parameters.
+    pub parameters: Vec<u8>,
+}
+
+/// A simulated NCP reached through ASH framing.
+pub struct SimulatedNcp {
+    decoder: Decoder,
+    outgoing: VecDeque<u8>,
+    ash_connected: bool,
+    frame_tx: u8,
+    frame_rx: u8,
+    headers: HeaderCodec,
+    reset_code: ResetCode,
+
+    protocol_version: u8,
+    min_protocol_version: u8,
+    stack_type: u8,
+    stack_version: u16,
+    negotiated_version: Option<u8>,
+
+    configuration: HashMap<u8, u16>,
+    configuration_rejections: HashMap<u8, Status>,
+    values: HashMap<u8, Vec<u8>>,
+    extended_values: HashMap<(u8, u32), Vec<u8>>,
+    policies: HashMap<u8, u8>,
+    endpoints: HashMap<u8, Vec<u8>>,
+    network_status: NetworkStatus,
+    node_type: NodeType,
+    network_parameters: NetworkParameters,
+    eui64: EUI64,
+    node_id: NodeId,
+    random: u16,
+    entropy_source: EntropySource,
+    library_statuses: HashMap<LibraryID, u8>,
+    timers: [(u16, EventUnits, bool); 2],
+    pending_callbacks: VecDeque<(FrameID, Vec<u8>)>,
+    counters: [u16; COUNTER_TYPE_COUNT],
+    channel_energy: HashMap<u8, i8>,
+    networks: Vec<(ZigbeeNetwork, u8, i8)>,
+    follow_ups: Vec<(FrameID, Vec<u8>)>,
+    permit_joining: u8,
+    children: Vec<(NodeId, EUI64, NodeType)>,
+    aps_sequence: u8,
+    neighbors: Vec<NeighborTableEntry>,
+    routes: Vec<RouteTableEntry>,
+    concentrator: Option<ConcentratorType>,
+    undeliverable: Vec<u16>,
+    address_table: HashMap<u8, (EUI64, NodeId)>,
+
+    handlers: HashMap<FrameID, CommandHandler>,
+    faults: VecDeque<Fault>,
+    received: Vec<ReceivedCommand>,
+}
+
+impl SimulatedNcp {
+    /// Creates a simulator running EZSP protocol version 12
+    /// that has not joined a network.
+    pub fn new() -> Self {
+        let mut ncp = SimulatedNcp {
+            decoder: Decoder::new(),
+            outgoing: VecDeque::new(),
+            ash_connected: false,
+            frame_tx: 0,
+            frame_rx: 0,
+            headers: HeaderCodec::new(),
+            reset_code: ResetCode::Software,
+
+            protocol_version: 12,
+            min_protocol_version: 4,
+            stack_type: 2,
+            stack_version: 0x7300,
+            negotiated_version: None,
+
+            configuration: HashMap::new(),
+            configuration_rejections: HashMap::new(),
+            values: HashMap::new(),
+            extended_values: HashMap::new(),
+            policies: HashMap::new(),
+            endpoints: HashMap::new(),
+            network_status: NetworkStatus::NoNetwork,
+            node_type: NodeType::UnknownDevice,
+            network_parameters: NetworkParameters {
+                extended_pan_id: [0; 8],
+                pan_id: 0xFFFF,
+                radio_tx_power: 0,
+                radio_channel: 11,
+                join_method: JoinMethod::MACAssociation.into(),
+                network_manager_id: 0x0000,
+                network_update_id: 0,
+                channels: ChannelMask::EMPTY,
+            },
+            eui64: [0x01, 0x23, 0x45, 0x67, 0x89, 0xAB, 0xCD, 0xEF],
+            node_id: 0xFFFE,
+            random: 0xACE1,
+            entropy_source: EntropySource::MbedTLSTRND,
+            library_statuses: HashMap::new(),
+            timers: [(0, EventUnits::Inactive, false); 2],
+            pending_callbacks: VecDeque::new(),
+            counters: [0; COUNTER_TYPE_COUNT],
+            channel_energy: HashMap::new(),
+            networks: Vec::new(),
+            follow_ups: Vec::new(),
+            permit_joining: 0,
+            children: Vec::new(),
+            aps_sequence: 0,
+            neighbors: Vec::new(),
+            routes: Vec::new(),
+            concentrator: None,
+            undeliverable: Vec::new(),
+            address_table: HashMap::new(),
+
+            handlers: HashMap::new(),
+            faults: VecDeque::new(),
+            received: Vec::new(),
+        };
+
+        for (id, value) in [
+            (ConfigID::StackProfile, 2),
+            (ConfigID::SecurityLevel, 5),
+            (ConfigID::AddressTableSize, 8),
+            (ConfigID::NeighborTableSize, 16),
+            (ConfigID::RouteTableSize, 16),
+            (ConfigID::SourceRouteTableSize, 7),
+            (ConfigID::MaxHops, 30),
+            (ConfigID::MaxEndDeviceChildren, 6),
+            (ConfigID::IndirectTransmissionTimeout, 7680),
+            (ConfigID::KeyTableSize, 4),
+            (ConfigID::SupporedNetworks, 1),
+        ] {
+            ncp.configuration.insert(id as u8, value);
+        }
+
+        for (id, decision) in [
+            (PolicyID::BindingModification, DecisionId::DisallowBindingModification),
+            (PolicyID::UnicastReplies, DecisionId::HostWillNotSupplyReply),
+        ] {
+            ncp.policies.insert(id as u8, decision as u8);
+        }
+        ncp.policies.insert(PolicyID::TrustCenter as u8, 0x00);
+
+        ncp.values.insert(ValueID::FreeBuffers as u8, vec![0xF0]);
+        ncp.values.insert(ValueID::MaximumIncomingTransferSize as u8, 82u16.to_le_bytes().to_vec());
+        ncp.values.insert(ValueID::MaximumOutgoingTransferSize as u8, 82u16.to_le_bytes().to_vec());
+
+        ncp
+    }
+
+    /// Sets the version information reported by the `version` command.
+    ///
+    /// Versions from `min_protocol_version` to `protocol_version` are accepted.
+    pub fn with_version(mut self, protocol_version: u8, min_protocol_version: u8, stack_version: u16) -> Self {
+        self.protocol_version = protocol_version;
+        self.min_protocol_version = min_protocol_version;
+        self.stack_version = stack_version;
+        self
+    }
+
+    /// The protocol version agreed with the host, if any.
+    pub fn negotiated_version(&self) -> Option<u8> {
+        self.negotiated_version
+    }
+
+    /// A configuration value.
+    pub fn configuration(&self, id: ConfigID) -> Option<u16> {
+        self.configuration.get(&(id as u8)).copied()
+    }
+
+    /// Sets a configuration value, making it readable and writable by the host.
+    pub fn set_configuration(&mut self, id: ConfigID, value: u16) {
+        self.configuration.insert(id as u8, value);
+    }
+
+    /// Makes the host's attempts to set a configuration value fail with `status`.
+    pub fn reject_configuration(&mut self, id: ConfigID, status: Status) {
+        self.configuration_rejections.insert(id as u8, status);
+    }
+
+    /// A value, as the bytes it is sent as.
+    pub fn value(&self, id: ValueID) -> Option<&[u8]> {
+        self.values.get(&(id as u8)).map(Vec::as_slice)
+    }
+
+    /// Sets a value, making it readable and writable by the host.
+    pub fn set_value(&mut self, id: ValueID, value: &[u8]) {
+        self.values.insert(id as u8, value.to_vec());
+    }
+
+    /// Sets an extended value for the given characteristics, making it readable by the host.
+    pub fn set_extended_value(&mut self, id: ExtendedValueID, characteristics: u32, value: &[u8]) {
+        self.extended_values.insert((id as u8, characteristics), value.to_vec());
+    }
+
+    /// The decision of a policy.
+    pub fn policy(&self, id: PolicyID) -> Option<u8> {
+        self.policies.get(&(id as u8)).copied()
+    }
+
+    /// Sets the decision of a policy, making it readable and writable by the host.
+    pub fn set_policy(&mut self, id: PolicyID, decision: u8) {
+        self.policies.insert(id as u8, decision);
+    }
+
+    /// The addEndpoint parameters the host registered an endpoint with.
+    pub fn endpoint(&self, id: u8) -> Option<&[u8]> {
+        self.endpoints.get(&id).map(Vec::as_slice)
+    }
+
+    /// The simulated network status.
+    pub fn network_status(&self) -> NetworkStatus {
+        self.network_status
+    }
+
+    /// Sets the simulated network status.
+    pub fn set_network_status(&mut self, status: NetworkStatus) {
+        self.network_status = status;
+    }
+
+    /// The simulated node type and network parameters.
+    pub fn network_parameters(&self) -> (NodeType, NetworkParameters) {
+        (self.node_type, self.network_parameters)
+    }
+
+    /// Sets the simulated node type and network parameters.
+    pub fn set_network_parameters(&mut self, node_type: NodeType, parameters: NetworkParameters) {
+        self.node_type = node_type;
+        self.network_parameters = parameters;
+    }
+
+    /// The EUI64 of the simulated node.
+    pub fn eui64(&self) -> EUI64 {
+        self.eui64
+    }
+
+    /// Sets the EUI64 of the simulated node.
+    pub fn set_eui64(&mut self, eui64: EUI64) {
+        self.eui64 = eui64;
+    }
+
+    /// The node ID of the simulated node.
+    pub fn node_id(&self) -> NodeId {
+        self.node_id
+    }
+
+    /// Sets the node ID of the simulated node.
+    pub fn set_node_id(&mut self, node_id: NodeId) {
+        self.node_id = node_id;
+    }
+
+    /// Sets the status reported for a library, which otherwise
+    /// is reported as not present.
+    pub fn set_library_status(&mut self, library_id: LibraryID, status: u8) {
+        self.library_statuses.insert(library_id, status);
+    }
+
+    /// A timer set by the host, as its time, units and whether it repeats.
+    pub fn timer(&self, timer_id: u8) -> Option<(u16, EventUnits, bool)> {
+        self.timers.get(timer_id as usize).copied()
+    }
+
+    /// Queues a callback to be sent in response to the host's `callback` command.
+    pub fn queue_callback(&mut self, frame_id: FrameID, parameters: &[u8]) {
+        self.pending_callbacks.push_back((frame_id, parameters.to_vec()));
+    }
+
+    /// The value of a counter.
+    pub fn counter(&self, counter: CounterType) -> u16 {
+        self.counters.get(counter as usize).copied().unwrap_or(0)
+    }
+
+    /// Sets the value of a counter.
+    pub fn set_counter(&mut self, counter: CounterType, value: u16) {
+        if let Some(slot) = self.counters.get_mut(counter as usize) {
+            *slot = value;
+        }
+    }
+
+    /// Sets the maximum RSSI reported by energy scans of a channel,
+    /// which otherwise is -90 dBm.
+    pub fn set_channel_energy(&mut self, channel: u8, max_rssi: i8) {
+        self.channel_energy.insert(channel, max_rssi);
+    }
+
+    /// Adds a network reported by active scans of its channel,
+    /// with the LQI and RSSI of its beacon.
+    pub fn add_network(&mut self, network: ZigbeeNetwork, lqi: u8, rssi: i8) {
+        self.networks.push((network, lqi, rssi));
+    }
+
+    /// The duration joining was last permitted for.
+    pub fn permit_joining(&self) -> u8 {
+        self.permit_joining
+    }
+
+    /// Lets a device join as a child of this node, reporting it with
+    /// trustCenterJoinHandler and childJoinHandler callbacks.
+    ///
+    /// Does nothing unless joining is permitted.
+    pub fn join_device(&mut self, node_id: NodeId, eui64: EUI64, node_type: NodeType) {
+        if self.permit_joining == 0 {
+            return;
+        }
+
+        let trust_center = [
+            &node_id.to_le_bytes()[..],
+            &eui64,
+            &[DeviceUpdate::StandardSecurityUnsecuredJoin as u8, JoinDecision::UsePreconfiguredKey as u8],
+            &self.node_id.to_le_bytes(),
+        ]
+        .concat();
+        self.emit_callback(TRUST_CENTER_JOIN_HANDLER, &trust_center);
+
+        let index = self.children.len() as u8;
+        self.children.push((node_id, eui64, node_type));
+        let child = [&[index, 1][..], &node_id.to_le_bytes(), &eui64, &[node_type as u8]].concat();
+        self.emit_callback(CHILD_JOIN_HANDLER, &child);
+    }
+
+    /// Adds an entry to the neighbor table.
+    pub fn add_neighbor(&mut self, entry: NeighborTableEntry) {
+        self.neighbors.push(entry);
+    }
+
+    /// Adds an entry to the route table.
+    pub fn add_route(&mut self, entry: RouteTableEntry) {
+        self.routes.push(entry);
+    }
+
+    /// The concentrator type set with setConcentrator, if enabled.
+    pub fn concentrator(&self) -> Option<ConcentratorType> {
+        self.concentrator
+    }
+
+    /// Reports unicasts to a node ID, address table index or
+    /// binding index as not acknowledged.
+    pub fn fail_delivery(&mut self, index_or_destination: u16) {
+        self.undeliverable.push(index_or_destination);
+    }
+
+    /// Answers commands with the given frame ID using `handler`
+    /// instead of the built-in behaviour.
+    pub fn on_command(&mut self, frame_id: FrameID, handler: impl FnMut(&[u8]) -> Vec<u8> + 'static) {
+        self.handlers.insert(frame_id, Box::new(handler));
+    }
+
+    /// Applies `fault` to the response to the next command.
+    ///
+    /// Faults are applied in the order they are injected, one per command.
+    pub fn inject_fault(&mut self, fault: Fault) {
+        self.faults.push_back(fault);
+    }
+
+    /// Sends an asynchronous callback frame to the host.
+    pub fn emit_callback(&mut self, frame_id: FrameID, parameters: &[u8]) {
+        let header = FrameHeader::response(0, frame_id, CallbackTypes::Asynchronous);
+        self.send_ezsp(header, parameters);
+    }
+
+    /// Sends an asynchronous callback frame holding an encoded value.
+    pub fn emit_callback_with(&mut self, frame_id: FrameID, parameters: &impl EzspEncode) {
+        self.emit_callback(frame_id, &encode(parameters));
+    }
+
+    /// Simulates the NCP resetting on its own, sending a RSTACK with `code`.
+    pub fn reset(&mut self, code: ResetCode) {
+        self.reset_state();
+        self.send_ash(Frame::RstAck { version: frame::VERSION, reset_code: code.into() });
+    }
+
+    /// The commands received from the host, oldest first.
+    pub fn received(&self) -> &[ReceivedCommand] {
+        &self.received
+    }
+
+    fn reset_state(&mut self) {
+        self.ash_connected = true;
+        self.frame_tx = 0;
+        self.frame_rx = 0;
+        self.headers.reset();
+        self.negotiated_version = None;
+        self.network_status = NetworkStatus::NoNetwork;
+        for (id, _) in self.endpoints.drain() {
+            self.extended_values.remove(&(ExtendedValueID::EndpointFlags as u8, id.into()));
+        }
+    }
+
+    fn handle_ash(&mut self, frame: Frame) {
+        match frame {
+            Frame::Rst => {
+                self.outgoing.clear();
+                self.reset_state();
+                self.send_ash(Frame::RstAck {
+                    version: frame::VERSION,
+                    reset_code: Recognized::Known(self.reset_code),
+                });
+            }
+            Frame::Data { frame_number, retransmit, data, .. } if self.ash_connected => {
+                if frame_number == self.frame_rx {
+                    self.frame_rx = frame::next(self.frame_rx);
+                    self.send_ash(Frame::Ack { ack_number: self.frame_rx, not_ready: false });
+                    self.handle_ezsp(&data);
+                } else if retransmit {
+                    self.send_ash(Frame::Ack { ack_number: self.frame_rx, not_ready: false });
+                } else {
+                    self.send_ash(Frame::Nak { ack_number: self.frame_rx, not_ready: false });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_ezsp(&mut self, bytes: &[u8]) {
+        let Ok((header, length)) = self.headers.decode(bytes) else {
+            return;
+        };
+        let parameters = &bytes[length..];
+        self.received.push(ReceivedCommand { header, parameters: parameters.to_vec() });
+
+        let fault = self.faults.pop_front();
+        let (frame_id, mut response) = match fault {
+            Some(Fault::InvalidCommand(status)) => (INVALID_COMMAND, vec![status.raw()]),
+            Some(Fault::NoResponse) => return,
+            Some(Fault::AshError(code)) => {
+                self.ash_connected = false;
+                self.send_ash(Frame::Error { version: frame::VERSION, error_code: code.into() });
+                return;
+            }
+            _ => self.respond(header.frame_id, parameters),
+        };
+
+        let callback_type = if header.frame_id == CALLBACK && frame_id != NO_CALLBACKS && frame_id != INVALID_COMMAND {
+            CallbackTypes::Synchronous
+        } else {
+            CallbackTypes::None
+        };
+        let mut response_header = FrameHeader::response(header.sequence, frame_id, callback_type);
+        if let FrameControl::Response(control) = &mut response_header.control {
+            match fault {
+                Some(Fault::Truncated(length)) => {
+                    response.truncate(length);
+                    control.transcated().set(TruncatedStatus::Truncated);
+                }
+                Some(Fault::Overflow) => {
+                    control.overflow().set(OverflowStatus::NCPOutOfMemory);
+                }
+                _ => {}
+            }
+        }
+
+        self.send_ezsp(response_header, &response);
+
+        if header.frame_id == VERSION
+            && self.negotiated_version.is_some()
+            && let Some(&version) = response.first()
+        {
+            self.headers.negotiated(version);
+        }
+
+        for (frame_id, parameters) in std::mem::take(&mut self.follow_ups) {
+            self.emit_callback(frame_id, &parameters);
+        }
+    }
+
+    /// Produces the response to a command, returning its frame ID and parameters.
+    fn respond(&mut self, frame_id: FrameID, parameters: &[u8]) -> (FrameID, Vec<u8>) {
+        if let Some(handler) = self.handlers.get_mut(&frame_id) {
+            return (frame_id, handler(parameters));
+        }
+
+        if frame_id == VERSION {
+            let desired = parameters.first().copied().unwrap_or(0);
+            let version = if (self.min_protocol_version..=self.protocol_version).contains(&desired) {
+                self.negotiated_version = Some(desired);
+                desired
+            } else {
+                self.protocol_version
+            };
+
+            let mut response = vec![version, self.stack_type];
+            response.extend_from_slice(&self.stack_version.to_le_bytes());
+            return (frame_id, response);
+        }
+
+        if self.negotiated_version.is_none() {
+            return (INVALID_COMMAND, vec![Status::ErrorVersionNotSet as u8]);
+        }
+
+        let id = parameters.first().copied();
+        let response = match (frame_id, id) {
+            (NOP, _) => Vec::new(),
+            (CALLBACK, _) => match self.pending_callbacks.pop_front() {
+                Some(callback) => return callback,
+                None => return (NO_CALLBACKS, Vec::new()),
+            },
+            (ECHO, Some(length)) => match parameters.get(1..1 + length as usize) {
+                Some(data) => [&[length][..], data].concat(),
+                None => return (INVALID_COMMAND, vec![Status::ErrorTruncated as u8]),
+            },
+            (GET_EUI64, _) => self.eui64.to_vec(),
+            (GET_NODE_ID, _) => self.node_id.to_le_bytes().to_vec(),
+            (GET_RANDOM_NUMBER, _) => {
+                // A 16-bit Galois LFSR, good enough to vary between calls.
+                self.random = (self.random >> 1) ^ (0u16.wrapping_sub(self.random & 1) & 0xB400);
+                [&[EmberStatus::Success as u8][..], &self.random.to_le_bytes()].concat()
+            }
+            (GET_TRUE_RANDOM_ENTROPY_SOURCE, _) => vec![self.entropy_source as u8],
+            (GET_LIBRARY_STATUS, Some(id)) => vec![self.library_statuses.get(&id).copied().unwrap_or(0x00)],
+            (SET_TIMER, Some(id)) if parameters.len() >= 5 => {
+                let timer = EventUnits::try_from(parameters[3])
+                    .ok()
+                    .and_then(|units| Some((self.timers.get_mut(id as usize)?, units)));
+                let status = match timer {
+                    Some((timer, units)) => {
+                        *timer = (u16::from_le_bytes([parameters[1], parameters[2]]), units, parameters[4] != 0);
+                        EmberStatus::Success
+                    }
+                    None => EmberStatus::BadArgument,
+                };
+                vec![status as u8]
+            }
+            (GET_TIMER, Some(id)) => {
+                let (time, units, repeat) = self.timers.get(id as usize).copied().unwrap_or((0, EventUnits::Inactive, false));
+                [&time.to_le_bytes()[..], &[units as u8, repeat as u8]].concat()
+            }
+            (READ_COUNTERS, _) => encode(&self.counters),
+            (READ_AND_CLEAR_COUNTERS, _) => encode(&core::mem::replace(&mut self.counters, [0; COUNTER_TYPE_COUNT])),
+            (START_SCAN, Some(scan_type)) if parameters.len() >= 6 => {
+                let mask = u32::from_le_bytes([parameters[1], parameters[2], parameters[3], parameters[4]]);
+                vec![self.start_scan(scan_type, mask, parameters[5]) as u8]
+            }
+            (STOP_SCAN, _) => vec![EmberStatus::Success as u8],
+            (NETWORK_INIT, _) => vec![self.network_init() as u8],
+            (FORM_NETWORK, _) => match NetworkParameters::decode_from_slice(parameters) {
+                Ok((network, _)) => vec![self.form_network(network) as u8],
+                Err(_) => vec![EmberStatus::BadArgument as u8],
+            },
+            (JOIN_NETWORK, Some(node_type)) => {
+                let network = NetworkParameters::decode_from_slice(&parameters[1..]);
+                match (NodeType::try_from(node_type), network) {
+                    (Ok(node_type), Ok((network, _))) => vec![self.join_network(node_type, network) as u8],
+                    _ => vec![EmberStatus::BadArgument as u8],
+                }
+            }
+            (LEAVE_NETWORK, _) => vec![self.leave_network() as u8],
+            (SET_CONCENTRATOR, Some(on)) if parameters.len() >= 3 => {
+                let concentrator_type = u16::from_le_bytes([parameters[1], parameters[2]]);
+                match ConcentratorType::try_from(concentrator_type) {
+                    Ok(concentrator_type) => {
+                        self.concentrator = (on != 0).then_some(concentrator_type);
+                        vec![EmberStatus::Success as u8]
+                    }
+                    Err(_) => vec![EmberStatus::BadArgument as u8],
+                }
+            }
+            (SEND_MANY_TO_ONE_ROUTE_REQUEST, _) if self.network_status == NetworkStatus::NoNetwork => {
+                vec![EmberStatus::NetworkDown as u8]
+            }
+            (SEND_MANY_TO_ONE_ROUTE_REQUEST, _) => vec![EmberStatus::Success as u8],
+            (SET_ADDRESS_TABLE_REMOTE_EUI64, Some(index)) if parameters.len() >= 9 => {
+                if !self.in_address_table(index) {
+                    vec![EmberStatus::AddressTableIndexOutOfRange as u8]
+                } else {
+                    let eui64: EUI64 = parameters[1..9].try_into().unwrap_or_default();
+                    let node_id = self.lookup_node_id(&eui64).unwrap_or(UNKNOWN_NODE_ID);
+                    self.address_table.insert(index, (eui64, node_id));
+                    vec![EmberStatus::Success as u8]
+                }
+            }
+            (SET_ADDRESS_TABLE_REMOTE_NODE_ID, Some(index)) if parameters.len() >= 3 => {
+                if self.in_address_table(index) {
+                    let entry = self.address_table.entry(index).or_insert(([0; 8], TABLE_ENTRY_UNUSED_NODE_ID));
+                    entry.1 = u16::from_le_bytes([parameters[1], parameters[2]]);
+                }
+                Vec::new()
+            }
+            (GET_ADDRESS_TABLE_REMOTE_EUI64, Some(index)) => {
+                let (status, eui64) = match self.address_table.get(&index) {
+                    _ if !self.in_address_table(index) => (EmberStatus::AddressTableIndexOutOfRange, [0; 8]),
+                    Some((eui64, _)) => (EmberStatus::Success, *eui64),
+                    None => (EmberStatus::Success, [0; 8]),
+                };
+                [&[status as u8][..], &eui64].concat()
+            }
+            (GET_ADDRESS_TABLE_REMOTE_NODE_ID, Some(index)) => {
+                let entry = self.address_table.get(&index);
+                entry.map_or(TABLE_ENTRY_UNUSED_NODE_ID, |(_, node_id)| *node_id).to_le_bytes().to_vec()
+            }
+            (LOOKUP_NODE_ID_BY_EUI64, _) if parameters.len() >= 8 => {
+                let eui64: EUI64 = parameters[..8].try_into().unwrap_or_default();
+                self.lookup_node_id(&eui64).unwrap_or(NULL_NODE_ID).to_le_bytes().to_vec()
+            }
+            (LOOKUP_EUI64_BY_NODE_ID, _) if parameters.len() >= 2 => {
+                let node_id = u16::from_le_bytes([parameters[0], parameters[1]]);
+                match self.known_devices().find(|(id, _)| *id == node_id) {
+                    Some((_, eui64)) => [&[EmberStatus::Success as u8][..], &eui64].concat(),
+                    None => [&[EmberStatus::ErrFatal as u8][..], &[0; 8]].concat(),
+                }
+            }
+            (NEIGHBOR_COUNT, _) => vec![self.neighbors.len() as u8],
+            (GET_NEIGHBOR, Some(index)) => match self.neighbors.get(index as usize) {
+                Some(entry) => [vec![EmberStatus::Success as u8], encode(entry)].concat(),
+                None => [vec![EmberStatus::ErrFatal as u8], encode(&UNUSED_NEIGHBOR)].concat(),
+            },
+            (GET_ROUTE_TABLE_ENTRY, Some(index)) => {
+                let size = self.configuration.get(&(ConfigID::RouteTableSize as u8)).copied().unwrap_or(0);
+                let (status, entry) = match self.routes.get(index as usize) {
+                    _ if u16::from(index) >= size => (EmberStatus::ErrFatal, &UNUSED_ROUTE),
+                    Some(entry) => (EmberStatus::Success, entry),
+                    None => (EmberStatus::Success, &UNUSED_ROUTE),
+                };
+                [vec![status as u8], encode(entry)].concat()
+            }
+            (GET_CHILD_DATA, Some(index)) => {
+                let (status, child) = match self.children.get(index as usize) {
+                    Some((id, eui64, node_type)) => {
+                        (EmberStatus::Success, ChildData { eui64: *eui64, node_type: (*node_type).into(), id: *id, ..NO_CHILD })
+                    }
+                    None => (EmberStatus::NotJoined, NO_CHILD),
+                };
+                [vec![status as u8], encode(&child)].concat()
+            }
+            (PERMIT_JOINING, Some(duration)) => vec![self.set_permit_joining(duration) as u8],
+            (SEND_UNICAST, Some(message_type)) if parameters.len() >= 16 => {
+                let destination = u16::from_le_bytes([parameters[1], parameters[2]]);
+                self.send_message(message_type, destination, &parameters[3..14], parameters[14], &parameters[15..])
+            }
+            (SEND_BROADCAST, _) if parameters.len() >= 16 => {
+                let destination = u16::from_le_bytes([parameters[0], parameters[1]]);
+                let message_type = OutgoingMessageType::Broadcast as u8;
+                self.send_message(message_type, destination, &parameters[2..13], parameters[14], &parameters[15..])
+            }
+            (SEND_MULTICAST, _) if parameters.len() >= 15 => {
+                let message_type = OutgoingMessageType::Multicast as u8;
+                self.send_message(message_type, 0, &parameters[..11], parameters[13], &parameters[14..])
+            }
+            (SEND_REPLY, _) if self.network_status == NetworkStatus::NoNetwork => vec![EmberStatus::NetworkDown as u8],
+            (SEND_REPLY, _) => vec![EmberStatus::Success as u8],
+            (FIND_AND_REJOIN_NETWORK, _) => vec![self.rejoin_network() as u8],
+            (NETWORK_STATE, _) => vec![self.network_status as u8],
+            (GET_NETWORK_PARAMETERS, _) => {
+                let status = match self.network_status {
+                    NetworkStatus::NoNetwork => EmberStatus::NotJoined,
+                    _ => EmberStatus::Success,
+                };
+                let mut response = vec![status as u8, self.node_type as u8];
+                response.extend(encode(&self.network_parameters));
+                response
+            }
+            (GET_CONFIGURATION_VALUE, Some(id)) => match self.configuration.get(&id) {
+                Some(value) => [&[Status::Success as u8][..], &value.to_le_bytes()].concat(),
+                None => vec![Status::ErrorInvalidID as u8, 0, 0],
+            },
+            (SET_CONFIGURATION_VALUE, Some(id)) if parameters.len() >= 3 => {
+                let status = if let Some(status) = self.configuration_rejections.get(&id) {
+                    *status
+                } else if !self.configuration.contains_key(&id) {
+                    Status::ErrorInvalidID
+                } else if self.network_status != NetworkStatus::NoNetwork {
+                    Status::ErrorInvalidCall
+                } else {
+                    self.configuration.insert(id, u16::from_le_bytes([parameters[1], parameters[2]]));
+                    Status::Success
+                };
+                vec![status as u8]
+            }
+            (GET_POLICY, Some(id)) => match self.policies.get(&id) {
+                Some(decision) => vec![Status::Success as u8, *decision],
+                None => vec![Status::ErrorInvalidID as u8, 0],
+            },
+            (SET_POLICY, Some(id)) if parameters.len() >= 2 => {
+                match self.policies.get_mut(&id) {
+                    Some(decision) => {
+                        *decision = parameters[1];
+                        vec![Status::Success as u8]
+                    }
+                    None => vec![Status::ErrorInvalidID as u8],
+                }
+            }
+            (GET_VALUE, Some(id)) => match self.values.get(&id) {
+                Some(value) => {
+                    let mut response = vec![Status::Success as u8, value.len() as u8];
+                    response.extend_from_slice(value);
+                    response
+                }
+                None => vec![Status::ErrorInvalidID as u8, 0],
+            },
+            (SET_VALUE, Some(id)) if id == ValueID::EndpointFlags as u8 => {
+                let flags = parameters.get(2..5).and_then(|value| {
+                    let flags = EndpointFlags::try_from(u16::from_le_bytes([value[1], value[2]])).ok()?;
+                    Some((value[0], flags))
+                });
+                match flags {
+                    Some((endpoint, flags)) if self.endpoints.contains_key(&endpoint) => {
+                        self.set_extended_value(ExtendedValueID::EndpointFlags, endpoint.into(), &(flags as u16).to_le_bytes());
+                        vec![Status::Success as u8]
+                    }
+                    _ => vec![Status::ErrorInvalidValue as u8],
+                }
+            }
+            (SET_VALUE, Some(id)) if parameters.len() >= 2 => {
+                let length = parameters[1] as usize;
+                match parameters.get(2..2 + length) {
+                    Some(value) if self.values.contains_key(&id) => {
+                        self.values.insert(id, value.to_vec());
+                        vec![Status::Success as u8]
+                    }
+                    Some(_) => vec![Status::ErrorInvalidID as u8],
+                    None => vec![Status::ErrorInvalidValue as u8],
+                }
+            }
+            (ADD_ENDPOINT, Some(id)) if parameters.len() >= 8 => {
+                let status = if self.network_status != NetworkStatus::NoNetwork || self.endpoints.contains_key(&id) {
+                    Status::ErrorInvalidCall
+                } else {
+                    self.endpoints.insert(id, parameters.to_vec());
+                    self.set_extended_value(ExtendedValueID::EndpointFlags, id.into(), &(EndpointFlags::Enabled as u16).to_le_bytes());
+                    Status::Success
+                };
+                vec![status as u8]
+            }
+            (GET_EXTENDED_VALUE, Some(id)) if parameters.len() >= 5 => {
+                let characteristics = u32::from_le_bytes([parameters[1], parameters[2], parameters[3], parameters[4]]);
+                match self.extended_values.get(&(id, characteristics)) {
+                    Some(value) => {
+                        let mut response = vec![Status::Success as u8, value.len() as u8];
+                        response.extend_from_slice(value);
+                        response
+                    }
+                    None => vec![Status::ErrorInvalidID as u8, 0],
+                }
+            }
+            (GET_CONFIGURATION_VALUE | SET_CONFIGURATION_VALUE | GET_POLICY | SET_POLICY | GET_VALUE | SET_VALUE
+            | GET_EXTENDED_VALUE | ADD_ENDPOINT | ECHO | GET_LIBRARY_STATUS | SET_TIMER | GET_TIMER | START_SCAN
+            | JOIN_NETWORK | PERMIT_JOINING | GET_NEIGHBOR | GET_ROUTE_TABLE_ENTRY | GET_CHILD_DATA
+            | SET_CONCENTRATOR | SEND_UNICAST | SEND_BROADCAST | SEND_MULTICAST | SET_ADDRESS_TABLE_REMOTE_EUI64
+            | SET_ADDRESS_TABLE_REMOTE_NODE_ID | GET_ADDRESS_TABLE_REMOTE_EUI64 | GET_ADDRESS_TABLE_REMOTE_NODE_ID
+            | LOOKUP_NODE_ID_BY_EUI64 | LOOKUP_EUI64_BY_NODE_ID, _) => {
+                return (INVALID_COMMAND, vec![Status::ErrorTruncated as u8]);
+            }
+            _ => return (INVALID_COMMAND, vec![Status::ErrorInvalidFrameID as u8]),
+        };
+
+        (frame_id, response)
+    }
+
+    /// Resumes the network stored before the last reset, if any.
+    fn network_init(&mut self) -> EmberStatus {
+        if self.node_type == NodeType::UnknownDevice {
+            return EmberStatus::NotJoined;
+        }
+        if self.network_status != NetworkStatus::NoNetwork {
+            return EmberStatus::InvalidCall;
+        }
+        self.bring_network_up();
+        EmberStatus::Success
+    }
+
+    fn form_network(&mut self, parameters: NetworkParameters) -> EmberStatus {
+        if self.network_status != NetworkStatus::NoNetwork {
+            return EmberStatus::InvalidCall;
+        }
+        self.node_type = NodeType::Coordinator;
+        self.node_id = 0x0000;
+        self.network_parameters = parameters;
+        self.bring_network_up();
+        EmberStatus::Success
+    }
+
+    /// Joins a network previously added with [`add_network`](SimulatedNcp::add_network)
+    /// that permits joining on the channel and PAN given, or reports a failed join.
+    fn join_network(&mut self, node_type: NodeType, parameters: NetworkParameters) -> EmberStatus {
+        if self.network_status != NetworkStatus::NoNetwork {
+            return EmberStatus::InvalidCall;
+        }
+
+        let found = self.networks.iter().any(|(network, _, _)| {
+            network.allowing_join
+                && network.channel == parameters.radio_channel
+                && network.pan_id == parameters.pan_id
+                && network.extended_pan_id == parameters.extended_pan_id
+        });
+        if found {
+            self.node_type = node_type;
+            self.network_parameters = parameters;
+            self.bring_network_up();
+        } else {
+            self.follow_ups.push((STACK_STATUS_HANDLER, vec![EmberStatus::JoinFailed as u8]));
+        }
+        EmberStatus::Success
+    }
+
+    fn leave_network(&mut self) -> EmberStatus {
+        if self.network_status == NetworkStatus::NoNetwork {
+            return EmberStatus::InvalidCall;
+        }
+        self.network_status = NetworkStatus::NoNetwork;
+        self.node_type = NodeType::UnknownDevice;
+        self.permit_joining = 0;
+        self.children.clear();
+        self.follow_ups.push((STACK_STATUS_HANDLER, vec![EmberStatus::NetworkDown as u8]));
+        EmberStatus::Success
+    }
+
+    fn rejoin_network(&mut self) -> EmberStatus {
+        if self.node_type == NodeType::UnknownDevice {
+            return EmberStatus::NotJoined;
+        }
+        self.follow_ups.push((STACK_STATUS_HANDLER, vec![EmberStatus::NetworkDown as u8]));
+        self.bring_network_up();
+        EmberStatus::Success
+    }
+
+    fn set_permit_joining(&mut self, duration: u8) -> EmberStatus {
+        if self.network_status == NetworkStatus::NoNetwork {
+            return EmberStatus::InvalidCall;
+        }
+        self.permit_joining = duration;
+        let status = if duration == 0 { EmberStatus::NetworkClosed } else { EmberStatus::NetworkOpened };
+        self.follow_ups.push((STACK_STATUS_HANDLER, vec![status as u8]));
+        EmberStatus::Success
+    }
+
+    /// Whether `index` is within the configured address table size.
+    fn in_address_table(&self, index: u8) -> bool {
+        let size = self.configuration.get(&(ConfigID::AddressTableSize as u8)).copied().unwrap_or(0);
+        u16::from(index) < size
+    }
+
+    /// The devices whose node ID and EUI64 the address,
+    /// child and neighbor tables know.
+    fn known_devices(&self) -> impl Iterator<Item = (NodeId, EUI64)> + '_ {
+        let addresses = self
+            .address_table
+            .values()
+            .filter(|(_, node_id)| ![UNKNOWN_NODE_ID, TABLE_ENTRY_UNUSED_NODE_ID].contains(node_id))
+            .map(|(eui64, node_id)| (*node_id, *eui64));
+        let children = self.children.iter().map(|(node_id, eui64, _)| (*node_id, *eui64));
+        let neighbors = self.neighbors.iter().map(|neighbor| (neighbor.short_id, neighbor.long_id));
+        addresses.chain(children).chain(neighbors)
+    }
+
+    fn lookup_node_id(&self, eui64: &EUI64) -> Option<NodeId> {
+        self.known_devices().find(|(_, known)| known == eui64).map(|(node_id, _)| node_id)
+    }
+
+    /// Queues a message, reporting its delivery with a messageSentHandler
+    /// callback that echoes the APS frame and length-prefixed contents.
+    fn send_message(
+        &mut self,
+        message_type: u8,
+        index_or_destination: u16,
+        aps_frame: &[u8],
+        message_tag: u8,
+        message_contents: &[u8],
+    ) -> Vec<u8> {
+        if self.network_status == NetworkStatus::NoNetwork {
+            return vec![EmberStatus::NetworkDown as u8, 0];
+        }
+
+        self.aps_sequence = self.aps_sequence.wrapping_add(1);
+        let unicast = message_type < OutgoingMessageType::Multicast as u8;
+        let status = if unicast && self.undeliverable.contains(&index_or_destination) {
+            EmberStatus::DeliveryFailed
+        } else {
+            EmberStatus::Success
+        };
+
+        let mut aps_frame = aps_frame.to_vec();
+        aps_frame[10] = self.aps_sequence;
+        let sent = [
+            &[message_type][..],
+            &index_or_destination.to_le_bytes(),
+            &aps_frame,
+            &[message_tag, status as u8],
+            message_contents,
+        ]
+        .concat();
+        self.follow_ups.push((MESSAGE_SENT_HANDLER, sent));
+        vec![EmberStatus::Success as u8, self.aps_sequence]
+    }
+
+    fn bring_network_up(&mut self) {
+        self.network_status = NetworkStatus::JoinedNetwork;
+        self.follow_ups.push((STACK_STATUS_HANDLER, vec![EmberStatus::NetworkUp as u8]));
+    }
+
+    /// Validates a scan and queues its results to follow the response.
+    fn start_scan(&mut self, scan_type: u8, channel_mask: u32, duration: u8) -> EmberStatus {
+        let channels: Vec<u8> = (11..=26).filter(|channel| channel_mask & 1 << channel != 0).collect();
+        if duration > 14 {
+            return EmberStatus::MACBadScanDuration;
+        }
+        if channels.is_empty() {
+            return EmberStatus::MACInvalidChannelMask;
+        }
+
+        match NetworkScanType::try_from(scan_type) {
+            Ok(NetworkScanType::EnergyScan) => {
+                for channel in channels {
+                    let max_rssi = self.channel_energy.get(&channel).copied().unwrap_or(-90);
+                    self.follow_ups.push((ENERGY_SCAN_RESULT_HANDLER, vec![channel, max_rssi as u8]));
+                }
+            }
+            Ok(NetworkScanType::ActiveScan) => {
+                for (network, lqi, rssi) in &self.networks {
+                    if channels.contains(&network.channel) {
+                        let mut parameters = encode(network);
+                        parameters.extend([*lqi, *rssi as u8]);
+                        self.follow_ups.push((NETWORK_FOUND_HANDLER, parameters));
+                    }
+                }
+            }
+            Err(_) => return EmberStatus::MACIncorrectScanType,
+        }
+
+        self.follow_ups.push((SCAN_COMPLETE_HANDLER, vec![0, EmberStatus::Success as u8]));
+        EmberStatus::Success
+    }
+
+    fn send_ezsp(&mut self, header: FrameHeader, parameters: &[u8]) {
+        let mut data = vec![0u8; HeaderFormat::Extended.length()];
+        let Ok(length) = self.headers.encode(&header, &mut data) else {
+            return;
+        };
+        data.truncate(length);
+        data.extend_from_slice(parameters);
+
+        let frame_number = self.frame_tx;
+        self.frame_tx = frame::next(self.frame_tx);
+        self.send_ash(Frame::Data { frame_number, retransmit: false, ack_number: self.frame_rx, data });
+    }
+
+    fn send_ash(&mut self, frame: Frame) {
+        let mut bytes = Vec::new();
+        frame.encode(&mut bytes);
+        self.outgoing.extend(bytes);
+    }
+}
+
+impl Default for SimulatedNcp {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ByteStream for SimulatedNcp {
+    fn write_all(&mut self, bytes: &[u8]) -> io::Result<()> {
+        for byte in bytes {
+            if let Some(Ok(frame)) = self.decoder.push(*byte) {
+                self.handle_ash(frame);
+            }
+        }
+        Ok(())
+    }
+
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let count = buf.len().min(self.outgoing.len());
+        for (slot, byte) in buf.iter_mut().zip(self.outgoing.drain(..count)) {
+            *slot = byte;
+        }
+        Ok(count)
+    }
+}
+
+fn encode(value: &impl EzspEncode) -> Vec<u8> {
+    let mut buf = [0u8; frame::MAX_DATA_LENGTH];
+    let length = value.encode_to_slice(&mut buf).unwrap_or(0);
+    buf[..length].to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ash::{Ash, AshConfig, AshError};
+    use std::time::Duration;
+
+    fn connect(ncp: SimulatedNcp) -> Ash<SimulatedNcp> {
+        let mut ash = Ash::new(ncp, AshConfig::default());
+        assert_eq!(ash.reset(), Ok(ResetCode::Software.into()));
+        ash
+    }
+
+    /// Sends a command and returns the response header and parameters.
+    fn command_as(
+        ash: &mut Ash<SimulatedNcp>,
+        format: HeaderFormat,
+        sequence: u8,
+        frame_id: FrameID,
+        parameters: &[u8],
+    ) -> (FrameHeader, Vec<u8>) {
+        let mut data = vec![0u8; format.length()];
+        FrameHeader::command(sequence, frame_id).encode_as(format, &mut data).unwrap();
+        data.extend_from_slice(parameters);
+        ash.send(&data).unwrap();
+
+        let response = ash.receive_timeout(Duration::from_millis(100)).unwrap().unwrap();
+        let (header, length) = FrameHeader::decode_as(format, &response).unwrap();
+        (header, response[length..].to_vec())
+    }
+
+    fn command(ash: &mut Ash<SimulatedNcp>, sequence: u8, frame_id: FrameID, parameters: &[u8]) -> (FrameHeader, Vec<u8>) {
+        command_as(ash, HeaderFormat::Extended, sequence, frame_id, parameters)
+    }
+
+    fn negotiate(ash: &mut Ash<SimulatedNcp>) {
+        let mut data = vec![0u8; 3];
+        FrameHeader::command(0, VERSION).encode_as(HeaderFormat::Legacy, &mut data).unwrap();
+        data.push(12);
+        ash.send(&data).unwrap();
+
+        let response = ash.receive_timeout(Duration::from_millis(100)).unwrap().unwrap();
+        assert_eq!(response, [0x00, 0x80, 0x00, 12, 0x02, 0x00, 0x73]);
+    }
+
+    #[test]
+    fn version_negotiation () {
+        let mut ash = connect(SimulatedNcp::new());
+        let (header, parameters) = command_as(&mut ash, HeaderFormat::Legacy, 1, NETWORK_STATE, &[]);
+        assert_eq!(header.frame_id, INVALID_COMMAND);
+        assert_eq!(parameters, [Status::ErrorVersionNotSet as u8]);
+
+        let mut ash = connect(SimulatedNcp::new());
+        negotiate(&mut ash);
+        assert_eq!(ash.stream().negotiated_version(), Some(12));
+
+        let (header, parameters) = command(&mut ash, 1, VERSION, &[12]);
+        assert_eq!(header.frame_id, VERSION);
+        assert_eq!(parameters, [12, 0x02, 0x00, 0x73]);
+    }
+
+    #[test]
+    fn configuration_table () {
+        let mut ncp = SimulatedNcp::new();
+        ncp.reject_configuration(ConfigID::MaxHops, Status::ErrorInvalidValue);
+        let mut ash = connect(ncp);
+        negotiate(&mut ash);
+
+        let id = ConfigID::AddressTableSize as u8;
+        assert_eq!(command(&mut ash, 1, GET_CONFIGURATION_VALUE, &[id]).1, [0x00, 8, 0]);
+        assert_eq!(command(&mut ash, 2, SET_CONFIGURATION_VALUE, &[id, 16, 0]).1, [0x00]);
+        assert_eq!(ash.stream().configuration(ConfigID::AddressTableSize), Some(16));
+
+        let rejected = ConfigID::MaxHops as u8;
+        assert_eq!(command(&mut ash, 3, SET_CONFIGURATION_VALUE, &[rejected, 1, 0]).1, [Status::ErrorInvalidValue as u8]);
+
+        let missing = ConfigID::GPSinkTableSize as u8;
+        assert_eq!(command(&mut ash, 4, GET_CONFIGURATION_VALUE, &[missing]).1, [Status::ErrorInvalidID as u8, 0, 0]);
+    }
+
+    #[test]
+    fn value_and_policy_tables () {
+        let mut ash = connect(SimulatedNcp::new());
+        negotiate(&mut ash);
+
+        let id = ValueID::FreeBuffers as u8;
+        assert_eq!(command(&mut ash, 1, GET_VALUE, &[id]).1, [0x00, 1, 0xF0]);
+        assert_eq!(command(&mut ash, 2, SET_VALUE, &[id, 1, 0x10]).1, [0x00]);
+        assert_eq!(ash.stream().value(ValueID::FreeBuffers), Some(&[0x10][..]));
+
+        let policy = PolicyID::BindingModification as u8;
+        let allow = DecisionId::AllowBindingModification as u8;
+        assert_eq!(command(&mut ash, 3, SET_POLICY, &[policy, allow]).1, [0x00]);
+        assert_eq!(command(&mut ash, 4, GET_POLICY, &[policy]).1, [0x00, allow]);
+    }
+
+    #[test]
+    fn network_state () {
+        let mut ash = connect(SimulatedNcp::new());
+        negotiate(&mut ash);
+
+        assert_eq!(command(&mut ash, 1, NETWORK_STATE, &[]).1, [NetworkStatus::NoNetwork as u8]);
+        assert_eq!(command(&mut ash, 2, GET_NETWORK_PARAMETERS, &[]).1[0], EmberStatus::NotJoined as u8);
+
+        let (node_type, mut parameters) = ash.stream().network_parameters();
+        assert_eq!(node_type, NodeType::UnknownDevice);
+        parameters.pan_id = 0x1A62;
+        ash.stream_mut().set_network_parameters(NodeType::Coordinator, parameters);
+        ash.stream_mut().set_network_status(NetworkStatus::JoinedNetwork);
+
+        let response = command(&mut ash, 3, GET_NETWORK_PARAMETERS, &[]).1;
+        assert_eq!(response[..2], [EmberStatus::Success as u8, NodeType::Coordinator as u8]);
+        assert_eq!(response[10..12], [0x62, 0x1A]);
+    }
+
+    #[test]
+    fn scripted_faults () {
+        let mut ash = connect(SimulatedNcp::new());
+        negotiate(&mut ash);
+
+        ash.stream_mut().inject_fault(Fault::Truncated(1));
+        ash.stream_mut().inject_fault(Fault::Overflow);
+        ash.stream_mut().inject_fault(Fault::InvalidCommand(Status::ErrorOutOfMemory.into()));
+
+        let id = ConfigID::MaxHops as u8;
+        let (header, parameters) = command(&mut ash, 1, GET_CONFIGURATION_VALUE, &[id]);
+        assert!(header.truncated());
+        assert_eq!(parameters, [0x00]);
+
+        let (header, parameters) = command(&mut ash, 2, GET_CONFIGURATION_VALUE, &[id]);
+        assert!(header.overflow());
+        assert_eq!(parameters, [0x00, 30, 0]);
+
+        let (header, parameters) = command(&mut ash, 3, GET_CONFIGURATION_VALUE, &[id]);
+        assert_eq!(header.frame_id, INVALID_COMMAND);
+        assert_eq!(parameters, [Status::ErrorOutOfMemory as u8]);
+
+        assert_eq!(command(&mut ash, 4, 0x0FFF, &[]).1, [Status::ErrorInvalidFrameID as u8]);
+
+        ash.stream_mut().inject_fault(Fault::AshError(ResetCode::Assert));
+        ash.send(&[5, 0x00, 0x01, 0x18, 0x00]).unwrap();
+        assert_eq!(ash.poll(), Err(AshError::NcpError(ResetCode::Assert.into())));
+    }
+
+    #[test]
+    fn callbacks_and_handlers () {
+        let mut ash = connect(SimulatedNcp::new());
+        negotiate(&mut ash);
+
+        ash.stream_mut().on_command(0x0005, |parameters| vec![parameters.len() as u8]);
+        assert_eq!(command(&mut ash, 1, 0x0005, &[1, 2, 3]).1, [3]);
+        assert_eq!(ash.stream().received().last().unwrap().parameters, [1, 2, 3]);
+
+        ash.stream_mut().emit_callback(0x0019, &[EmberStatus::NetworkUp as u8]);
+        let callback = ash.receive().unwrap().unwrap();
+        let (header, length) = FrameHeader::decode(&callback).unwrap();
+        assert_eq!(header.callback_type(), Some(CallbackTypes::Asynchronous));
+        assert_eq!(callback[length..], [EmberStatus::NetworkUp as u8]);
+
+        ash.stream_mut().reset(ResetCode::Watchdog);
+        assert_eq!(ash.poll(), Err(AshError::NcpReset(ResetCode::Watchdog.into())));
+    }
+}